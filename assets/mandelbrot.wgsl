@@ -1,22 +1,293 @@
+// Features are compiled in per pipeline variant with shader defs (see
+// `PipelineKey`):
+//
+// - DISTANCE_ESTIMATE: track the orbit derivative, and draw boundary mode and
+//   the glow.
+// - RELIEF_SHADING: apply relief shading when it is enabled.
+// - DETAIL_PASS: stop the iterate pass at a fraction of the cap and leave
+//   the points still iterating to the detail pass.
+// - ANGLE_COLORING: record the escape angle of each pixel and color by it.
+// - ORBIT_AVERAGE: record the average position of each pixel's orbit and
+//   color by it.
+// - COMPUTE_BUDGET: iterate a span of workgroups per frame, starting from
+//   `dispatch_base`, rather than the whole texture.
+// - BLEND_MODES: iterate a second fractal and blend its colors into the
+//   view's.
+// - ORBIT_DENSITY: draw the orbit visits the `orbits` pass counted over the
+//   view.
+// - TRANSFER_FUNCTION: map escape iterations to the palette with the
+//   instructions in `transfer_ops` instead of the mapping curve.
+// - COLOR_CYCLE: shift the palette along the escape iterations with `time`.
+// - WARM_START: keep each pixel's orbit in `orbit_states`, skip the pixels
+//   that already escaped and resume the rest where the last frame's cap
+//   stopped them.
+// - STEREO: render a view per eye into the layers of an array texture, the
+//   layer chosen by the workgroup's z, each offset sideways by half of
+//   `eye_separation`.
+// - OUTPUT_RGBA16FLOAT, OUTPUT_RGBA8UNORM: write the output texture in that
+//   format, on adapters that can't write rgba32float storage textures.
+//
+// WORKGROUP_WIDTH and WORKGROUP_HEIGHT are always defined, as the shape of
+// the workgroups.
+
 struct Uniforms {
-    time: u32,
+    time: f32,
+    iteration_storage: u32,
+    coloring_mode: u32,
+    mapping_curve: u32,
+    shading: u32,
+    light_angle: f32,
+    center: vec2<f32>,
+    scale: f32,
+    non_finite: u32,
+    alpha_mode: u32,
+    rotation: f32,
+    non_finite_color: vec4<f32>,
+    boundary: u32,
+    boundary_width: f32,
+    dispatch_order: u32,
+    // The style being faded out while `transition` is below 1.
+    previous_mapping_curve: u32,
+    previous_shading: u32,
+    previous_boundary: u32,
+    // Progress of the crossfade to the current style, from 0 to 1.
+    transition: f32,
+    glow: u32,
+    glow_color: vec4<f32>,
+    glow_radius: f32,
+    warp_amp: f32,
+    warp_freq: f32,
+    pixel_aspect: f32,
+    channel_phase: vec3<f32>,
+    mode: u32,
+    julia_c: vec2<f32>,
+    use_symmetry: u32,
+    max_iterations: u32,
+    history_offset: vec2<f32>,
+    history_blend: f32,
+    history_parity: u32,
+    iteration_cue: u32,
+    iteration_cue_strength: f32,
+    frame: u32,
+    blend_mode: u32,
+    blend_mix: f32,
+    projection: u32,
+    // Fraction of the mapped escape range colored by the sky, 0 without one.
+    sky_end: f32,
+    // Separation between the eyes of a stereo view, in view heights.
+    eye_separation: f32,
+    // Sky colors at the start and the end of its range.
+    sky_color: vec4<f32>,
+    sky_edge_color: vec4<f32>,
 }
 
+#ifdef STEREO
+#ifdef OUTPUT_RGBA8UNORM
+@group(0) @binding(0) var output: texture_storage_2d_array<rgba8unorm, write>;
+#else
+#ifdef OUTPUT_RGBA16FLOAT
+@group(0) @binding(0) var output: texture_storage_2d_array<rgba16float, write>;
+#else
+@group(0) @binding(0) var output: texture_storage_2d_array<rgba32float, write>;
+#endif
+#endif
+#else
+#ifdef OUTPUT_RGBA8UNORM
+@group(0) @binding(0) var output: texture_storage_2d<rgba8unorm, write>;
+#else
+#ifdef OUTPUT_RGBA16FLOAT
+@group(0) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+#else
 @group(0) @binding(0) var output: texture_storage_2d<rgba32float, write>;
+#endif
+#endif
+#endif
 @group(0) @binding(1) var<uniform> uniforms: Uniforms;
+// Per-pixel iteration results, written by `iterate` and read by `recolor`.
+// Holds `f32` bit patterns in smooth storage, or `u16` counts packed two per
+// word in integer storage.
+@group(0) @binding(2) var<storage, read_write> iterations: array<atomic<u32>>;
+// Per-pixel estimate of the distance to the set in the complex plane, written
+// by `iterate` and read by `recolor`.
+@group(0) @binding(3) var<storage, read_write> distances: array<f32>;
+// The palette baked into one row of texels, read by `palette_color`.
+@group(0) @binding(4) var palette: texture_2d<f32>;
+// Final colors of the previous and the current frame, one after the other,
+// for temporal blending. `history_parity` selects the half written this
+// frame.
+@group(0) @binding(5) var<storage, read_write> history: array<vec4<f32>>;
+// Indices of the pixels the iterate pass left to the detail pass.
+@group(0) @binding(6) var<storage, read_write> detail_pixels: array<u32>;
+// Indirect dispatch arguments of the detail pass, workgroups in x, y and z,
+// followed by the number of pixels in `detail_pixels`.
+@group(0) @binding(7) var<storage, read_write> detail_args: array<atomic<u32>, 4>;
+// Per-pixel angle of z where the orbit escaped, in (-pi, pi], under
+// ANGLE_COLORING.
+@group(0) @binding(8) var<storage, read_write> angles: array<f32>;
+// The first workgroup, in dispatch order, of this frame's iterate dispatch
+// under COMPUTE_BUDGET.
+@group(0) @binding(9) var<storage, read> dispatch_base: u32;
+// Divergence of each pixel in the fractal blended over the view, under
+// BLEND_MODES.
+@group(0) @binding(10) var<storage, read_write> blend_iterations: array<f32>;
+// Visits of each pixel by the orbits the `orbits` pass traced, followed by a
+// running maximum of the visits.
+@group(0) @binding(11) var<storage, read_write> orbit_density: array<atomic<u32>>;
+// The points the `orbits` pass traces around, one per row of its workgroups.
+@group(0) @binding(12) var<storage, read> orbit_seeds: array<vec2<f32>>;
+// Per-pixel average of z over the orbit before it escaped, under
+// ORBIT_AVERAGE.
+@group(0) @binding(13) var<storage, read_write> orbit_averages: array<vec2<f32>>;
+// Instructions of the transfer function under TRANSFER_FUNCTION, padded
+// with TRANSFER_END.
+@group(0) @binding(14) var<storage, read> transfer_ops: array<TransferOp>;
+// Per-pixel orbits under WARM_START, zeroed whenever iteration starts over.
+@group(0) @binding(15) var<storage, read_write> orbit_states: array<OrbitState>;
+
+// The eye the invocation renders under STEREO, the layer of `output` it
+// writes, 0 for the left. Set by each entry point that works on pixels.
+var<private> eye: u32;
+
+// Chosen by `RenderConfig::workgroup_size`, or cycled through at runtime.
+const WORKGROUP_WIDTH: u32 = #{WORKGROUP_WIDTH};
+const WORKGROUP_HEIGHT: u32 = #{WORKGROUP_HEIGHT};
+const WORKGROUP_SHAPE: vec2<u32> = vec2(WORKGROUP_WIDTH, WORKGROUP_HEIGHT);
+
+// Invocations per workgroup of the detail pass. Its dispatch only spans x,
+// so workgroups are wide to keep their count within limits on big textures.
+const DETAIL_WORKGROUP_SIZE: u32 = 256;
+// Under DETAIL_PASS, the iterate pass stops at the cap divided by this.
+const DETAIL_SPLIT: u32 = 4;
+// Invocations per workgroup of the orbits pass, each tracing one orbit.
+const ORBIT_WORKGROUP_SIZE: u32 = 64;
+// Traced orbits stop once |z| exceeds this, past which they only head off
+// to infinity.
+const ORBIT_ESCAPE_RADIUS: f32 = 2.;
 
-const MAX_ITERS: u32 = 2000;
 const DIVERGENCE_BOUND: f32 = 1.e5;
 
+const PI: f32 = 3.14159265358979;
+
+const STORAGE_SMOOTH: u32 = 0;
+const STORAGE_INTEGER: u32 = 1;
+
+const COLORING_SMOOTH: u32 = 0;
+const COLORING_BANDED: u32 = 1;
+const COLORING_ANGLE: u32 = 2;
+const COLORING_DISTANCE: u32 = 3;
+const COLORING_AVERAGE: u32 = 4;
+
+const BLEND_ADD: u32 = 0;
+const BLEND_MULTIPLY: u32 = 1;
+const BLEND_SCREEN: u32 = 2;
+
+const CURVE_LINEAR: u32 = 0;
+const CURVE_SQRT: u32 = 1;
+const CURVE_LOG: u32 = 2;
+const CURVE_CUBE_ROOT: u32 = 3;
+
+// Integer count recorded for points that never escaped.
+const INTEGER_INTERIOR: u32 = 0xffff;
+// Integer count recorded for highlighted non-finite points.
+const INTEGER_NON_FINITE: u32 = 0xfffe;
+
+const NON_FINITE_INTERIOR: u32 = 0;
+const NON_FINITE_ESCAPED: u32 = 1;
+const NON_FINITE_HIGHLIGHT: u32 = 2;
+
+const ORDER_LINEAR: u32 = 0;
+const ORDER_MORTON: u32 = 1;
+
+const MODE_MANDELBROT: u32 = 0;
+const MODE_JULIA: u32 = 1;
+
+const PROJECTION_CARTESIAN: u32 = 0;
+const PROJECTION_LOG_POLAR: u32 = 1;
+
+const ALPHA_OPAQUE: u32 = 0;
+const ALPHA_STRAIGHT: u32 = 1;
+const ALPHA_PREMULTIPLIED: u32 = 2;
+
+// Divergence recorded for highlighted non-finite points. Like the interior's
+// -1 it is negative, so shading treats it as flat.
+const DIVERGENCE_NON_FINITE: f32 = -2.;
+
+// Height of the relief surface per escape iteration, relative to a pixel.
+const RELIEF_HEIGHT: f32 = 0.5;
+// Elevation of the relief light above the image plane.
+const LIGHT_ELEVATION: f32 = 1.;
+// Fraction of the palette color kept on fully shadowed slopes.
+const AMBIENT: f32 = 0.3;
+
+// How far the previous frame's color may be from the current one before
+// it's clamped, so uncovered detail doesn't ghost.
+const HISTORY_TOLERANCE: f32 = 0.1;
+
+// Fraction of the iteration cap past which escaped points count towards the
+// iteration cue.
+const CUE_START: f32 = 0.25;
+// Distance in texels to the neighbors the iteration cue checks for interior.
+const CUE_RADIUS: i32 = 2;
+
+// Palette lengths per second the palette shifts by under COLOR_CYCLE.
+const COLOR_CYCLE_RATE: f32 = 0.05;
+
+// Saturation of the hues picked by angle coloring.
+const ANGLE_SATURATION: f32 = 0.8;
+// Distance of an orbit's average from 0 at which average coloring is fully
+// saturated. Orbits that stay bounded keep within this radius.
+const AVERAGE_RADIUS: f32 = 2.;
+
+// Color of the band drawn in boundary mode.
+const BOUNDARY_COLOR: vec4<f32> = vec4(1., 1., 1., 1.);
+
+// Values the transfer function's stack holds. Matches `MAX_TRANSFER_STACK`
+// in `src/transfer.rs`, which rejects deeper expressions.
+const TRANSFER_STACK: u32 = 16;
+// Instructions of the transfer function, numbered as `Opcode` in
+// `src/transfer.rs`.
+const TRANSFER_END: u32 = 0;
+const TRANSFER_CONSTANT: u32 = 1;
+const TRANSFER_ITERATION: u32 = 2;
+const TRANSFER_MAX_ITERATIONS: u32 = 3;
+const TRANSFER_ADD: u32 = 4;
+const TRANSFER_SUBTRACT: u32 = 5;
+const TRANSFER_MULTIPLY: u32 = 6;
+const TRANSFER_DIVIDE: u32 = 7;
+const TRANSFER_POWER: u32 = 8;
+const TRANSFER_NEGATE: u32 = 9;
+const TRANSFER_SQRT: u32 = 10;
+const TRANSFER_LOG: u32 = 11;
+const TRANSFER_EXP: u32 = 12;
+const TRANSFER_SIN: u32 = 13;
+const TRANSFER_COS: u32 = 14;
+const TRANSFER_ABS: u32 = 15;
+const TRANSFER_FLOOR: u32 = 16;
+const TRANSFER_FRACT: u32 = 17;
+
+// Color the orbit density is drawn in, at the most visited pixels.
+const ORBIT_COLOR: vec3<f32> = vec3(0.4, 0.9, 1.);
+
 struct Complex {
     x: f32,
     y: f32,
 }
 
+// One instruction of the transfer function. `value` is the constant pushed
+// by TRANSFER_CONSTANT.
+struct TransferOp {
+    op: u32,
+    value: f32,
+}
+
 fn complex_add(a: Complex, b: Complex) -> Complex {
     return Complex(a.x + b.x, a.y + b.y);
 }
 
+fn complex_mul(a: Complex, b: Complex) -> Complex {
+    return Complex(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
 fn complex_sq(z: Complex) -> Complex {
     return Complex(z.x * z.x - z.y * z.y, 2 * (z.x * z.y));
 }
@@ -25,38 +296,959 @@ fn complex_mag2(z: Complex) -> f32 {
     return z.x * z.x + z.y * z.y;
 }
 
-fn divergence(c: Complex) -> f32 {
-    var z: Complex = Complex(0., 0.);
+// WGSL has no isNan/isInf, so test the exponent bits directly: they are all
+// set exactly for infinities and NaNs.
+fn is_finite(x: f32) -> bool {
+    return (bitcast<u32>(x) & 0x7f800000) != 0x7f800000;
+}
+
+// Divergence of an orbit that overflowed on iteration `i`.
+fn non_finite_divergence(i: u32) -> f32 {
+    switch uniforms.non_finite {
+        case NON_FINITE_ESCAPED: {
+            return f32(i);
+        }
+        case NON_FINITE_HIGHLIGHT: {
+            return DIVERGENCE_NON_FINITE;
+        }
+        default: {
+            return -1.;
+        }
+    }
+}
+
+// The outcome of iterating a point.
+struct Escape {
+    // Smooth escape iteration, negative for points that never escaped.
+    divergence: f32,
+    // Estimated distance to the set in the complex plane, 0 for points that
+    // never escaped.
+    distance: f32,
+    // Angle of z where the orbit escaped, 0 for points that never escaped.
+    angle: f32,
+    // Average of z over the orbit before it escaped, under ORBIT_AVERAGE. 0
+    // for points that never escaped.
+    average: vec2<f32>,
+}
+
+// How far the orbit of a point has been iterated.
+struct OrbitState {
+    z: vec2<f32>,
+    // Derivative of z with respect to the point, under DISTANCE_ESTIMATE.
+    dz: vec2<f32>,
+    // Sum of the orbit's points inside the escape radius, under
+    // ORBIT_AVERAGE.
+    sum: vec2<f32>,
+    // Iterations done, 0 for an orbit that hasn't started.
+    iterations: u32,
+    // Nonzero once the orbit escaped or overflowed.
+    escaped: u32,
+}
+
+// The orbit of a point before its first iteration. In Mandelbrot mode the
+// point is c and z starts at 0; in Julia mode the point is the starting z
+// and c is `julia_c`.
+fn start_orbit(point: Complex, mode: u32) -> OrbitState {
+    var state = OrbitState(vec2(0.), vec2(0.), vec2(0.), 0u, 0u);
+    if mode == MODE_JULIA {
+        state.z = vec2(point.x, point.y);
+        state.dz = vec2(1., 0.);
+    }
+    return state;
+}
+
+// Iterates z^2 + c for a point of the plane, up to `max_iterations` times.
+fn escape(point: Complex, mode: u32, max_iterations: u32) -> Escape {
+    var state = start_orbit(point, mode);
+    return resume_escape(point, mode, max_iterations, &state);
+}
+
+// Continues the orbit of a point from `state` until it has been iterated
+// `max_iterations` times, and leaves where it stopped in `state`.
+fn resume_escape(
+    point: Complex,
+    mode: u32,
+    max_iterations: u32,
+    state: ptr<function, OrbitState>,
+) -> Escape {
+    var z = Complex((*state).z.x, (*state).z.y);
+    var c = point;
+    if mode == MODE_JULIA {
+        c = Complex(uniforms.julia_c.x, uniforms.julia_c.y);
+    }
+#ifdef DISTANCE_ESTIMATE
+    // What each step adds to the derivative.
+    var dz = Complex((*state).dz.x, (*state).dz.y);
+    var dc = 1.;
+    if mode == MODE_JULIA {
+        dc = 0.;
+    }
+#endif
+#ifdef ORBIT_AVERAGE
+    var sum = (*state).sum;
+#endif
 
-    for (var i = 0u; i < MAX_ITERS; i++) {
+    for (var i = (*state).iterations; i < max_iterations; i++) {
+#ifdef DISTANCE_ESTIMATE
+        let twice_z = Complex(2. * z.x, 2. * z.y);
+        dz = complex_add(complex_mul(twice_z, dz), Complex(dc, 0.));
+#endif
         z = complex_add(complex_sq(z), c);
         let mag = complex_mag2(z);
+        // NaN fails every comparison and infinity breaks the smoothing below,
+        // so catch both before the escape test.
+        if !is_finite(mag) {
+            (*state).escaped = 1u;
+            return Escape(non_finite_divergence(i), 0., 0., vec2(0.));
+        }
         if mag >= DIVERGENCE_BOUND * DIVERGENCE_BOUND {
-            return f32(i) - log(log(mag) / log(DIVERGENCE_BOUND)) / log(2);
+            (*state).escaped = 1u;
+            let divergence = f32(i) - log(log(mag) / log(DIVERGENCE_BOUND)) / log(2);
+            let angle = atan2(z.y, z.x);
+#ifdef ORBIT_AVERAGE
+            // Points escaping on the first step have no points inside.
+            let average = sum / max(f32(i), 1.);
+#else
+            let average = vec2(0.);
+#endif
+#ifdef DISTANCE_ESTIMATE
+            // |z| ln|z| / |dz|. The derivative can overflow for points
+            // hugging the set, which are as close as it gets.
+            var distance = 0.25 * sqrt(mag / complex_mag2(dz)) * log(mag);
+            if !is_finite(distance) {
+                distance = 0.;
+            }
+            return Escape(divergence, distance, angle, average);
+#else
+            return Escape(divergence, 0., angle, average);
+#endif
         }
+#ifdef ORBIT_AVERAGE
+        sum += vec2(z.x, z.y);
+#endif
     }
-    return -1.;
+
+    (*state).z = vec2(z.x, z.y);
+#ifdef DISTANCE_ESTIMATE
+    (*state).dz = vec2(dz.x, dz.y);
+#endif
+#ifdef ORBIT_AVERAGE
+    (*state).sum = sum;
+#endif
+    (*state).iterations = max((*state).iterations, max_iterations);
+    return Escape(-1., 0., 0., vec2(0.));
 }
 
-fn mandelbrot_color(x: f32, y: f32) -> vec4<f32> {
-    let c: Complex = Complex(x, y);
-    let d = divergence(c);
-    if d < 0. {
+// A random number generator for stochastic modes. Seeded with `rng_seed`,
+// its sequence differs between pixels and between frames, so samples
+// accumulated over frames converge instead of repeating.
+struct Rng {
+    state: u32,
+}
+
+// The PCG hash of Jarzynski and Olano, "Hash Functions for GPU Rendering".
+fn pcg_hash(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// A generator for the pixel at `location` in this frame. Seeded from the
+// frame count rather than `time`, which stands still while paused.
+fn rng_seed(location: vec2<u32>) -> Rng {
+    return Rng(pcg_hash(location.x ^ pcg_hash(location.y ^ pcg_hash(uniforms.frame))));
+}
+
+// The next uniformly distributed `u32`.
+fn rng_next(rng: ptr<function, Rng>) -> u32 {
+    (*rng).state = pcg_hash((*rng).state);
+    return (*rng).state;
+}
+
+// The next uniformly distributed `f32` in [0, 1).
+fn rng_f32(rng: ptr<function, Rng>) -> f32 {
+    // The top 24 bits, as many as an f32 represents exactly.
+    return f32(rng_next(rng) >> 8u) / 16777216.;
+}
+
+// Rotates `v` counterclockwise by `angle` radians.
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec2(c * v.x - s * v.y, s * v.x + c * v.y);
+}
+
+// Change in the logarithm of the distance from the center per texel of height
+// in log-polar views. The full width spans one turn about the center, so the
+// angle changes by `pixel_aspect` times as much per texel across.
+fn log_polar_step() -> f32 {
+    return 2. * PI / (f32(textureDimensions(output).x) * uniforms.pixel_aspect);
+}
+
+// Maps a texel to the complex plane. `uniforms.scale` is half the view height,
+// and texels are `pixel_aspect` times as wide as they are tall before the view
+// is rotated. In log-polar views the angle about the center grows to the
+// right and the distance from it grows exponentially upward, with the middle
+// row `scale` away.
+fn pixel_to_complex(location: vec2<u32>) -> Complex {
+    let size = vec2<f32>(textureDimensions(output));
+    let texels = vec2<f32>(location) + 0.5 - size / 2.;
+    let center = eye_center();
+    if uniforms.projection == PROJECTION_LOG_POLAR {
+        let step = log_polar_step();
+        let angle = uniforms.rotation + texels.x * uniforms.pixel_aspect * step;
+        let radius = uniforms.scale * exp(-texels.y * step);
+        let c = center + radius * vec2(cos(angle), sin(angle));
+        return Complex(c.x, c.y);
+    }
+    // Texture rows grow downward, the imaginary axis grows upward.
+    let offset = vec2(texels.x * uniforms.pixel_aspect, -texels.y) * (2. * uniforms.scale / size.y);
+    let c = center + rotate(offset, uniforms.rotation);
+    return Complex(c.x, c.y);
+}
+
+// The center of the view seen from the eye of the invocation: half the eye
+// separation to the left for the left eye, to the right for the right one.
+// Single views have no eyes, and see the center itself.
+fn eye_center() -> vec2<f32> {
+#ifdef STEREO
+    let shift = (f32(eye) - 0.5) * uniforms.eye_separation * 2. * uniforms.scale;
+    return uniforms.center + rotate(vec2(shift, 0.), uniforms.rotation);
+#else
+    return uniforms.center;
+#endif
+}
+
+// Maps a point of the plane to the texel it falls in, the inverse of
+// `pixel_to_complex`. Points out of view map outside the texture.
+fn complex_to_pixel(point: Complex) -> vec2<i32> {
+    let size = vec2<f32>(textureDimensions(output));
+    let offset = vec2(point.x, point.y) - uniforms.center;
+    var texels: vec2<f32>;
+    if uniforms.projection == PROJECTION_LOG_POLAR {
+        let step = log_polar_step();
+        // The angle past the rotation, wrapped to [-pi, pi).
+        let turns = (atan2(offset.y, offset.x) - uniforms.rotation) / (2. * PI);
+        let angle = (turns - floor(turns + 0.5)) * 2. * PI;
+        let rise = -log(length(offset) / uniforms.scale) / step;
+        texels = vec2(angle / (uniforms.pixel_aspect * step), rise);
+    } else {
+        let unrotated = rotate(offset, -uniforms.rotation) * (size.y / (2. * uniforms.scale));
+        texels = vec2(unrotated.x / uniforms.pixel_aspect, -unrotated.y);
+    }
+    return vec2<i32>(floor(texels + size / 2.));
+}
+
+// Gathers the even bits of `v` into its low half.
+fn compact_bits(v: u32) -> u32 {
+    var x = v & 0x55555555;
+    x = (x | (x >> 1)) & 0x33333333;
+    x = (x | (x >> 2)) & 0x0f0f0f0f;
+    x = (x | (x >> 4)) & 0x00ff00ff;
+    x = (x | (x >> 8)) & 0x0000ffff;
+    return x;
+}
+
+// The texel an invocation works on. In Morton order the dispatch is a
+// power-of-two square, and the linear workgroup index is read as a Z-order
+// curve over it, so consecutive workgroups cover nearby tiles. Invocations
+// that land outside the texture are skipped.
+fn pixel_location(
+    workgroup: vec3<u32>,
+    local: vec3<u32>,
+    count: vec3<u32>,
+) -> vec2<u32> {
+    var tile = workgroup.xy;
+    if uniforms.dispatch_order == ORDER_MORTON {
+        let code = workgroup.y * count.x + workgroup.x;
+        tile = vec2(compact_bits(code), compact_bits(code >> 1));
+    }
+    return tile * WORKGROUP_SHAPE + local.xy;
+}
+
+// The texel an invocation of a budgeted iterate dispatch works on. The
+// dispatch is one row of workgroups, continuing the full dispatch from its
+// `dispatch_base`-th workgroup in the same order.
+fn budget_location(workgroup: vec3<u32>, local: vec3<u32>) -> vec2<u32> {
+    let code = dispatch_base + workgroup.x;
+    var tile: vec2<u32>;
+    if uniforms.dispatch_order == ORDER_MORTON {
+        tile = vec2(compact_bits(code), compact_bits(code >> 1));
+    } else {
+        let columns = (textureDimensions(output).x + WORKGROUP_WIDTH - 1) / WORKGROUP_WIDTH;
+        tile = vec2(code % columns, code / columns);
+    }
+    return tile * WORKGROUP_SHAPE + local.xy;
+}
+
+fn in_bounds(location: vec2<u32>) -> bool {
+    return all(location < textureDimensions(output));
+}
+
+// Whether the view is mirrored about the real axis: the Mandelbrot set is
+// symmetric under conjugation, and so is the warp, so an unrotated view
+// centered on the real axis has conjugate points on mirrored rows. Log-polar
+// views mirror columns instead, which isn't worth the bookkeeping.
+fn symmetric_view() -> bool {
+#ifdef BLEND_MODES
+    // A blended Julia set is only symmetric for some constants.
+    return false;
+#else
+    return uniforms.use_symmetry != 0 && uniforms.mode == MODE_MANDELBROT
+        && uniforms.center.y == 0. && uniforms.rotation == 0.
+        && uniforms.projection == PROJECTION_CARTESIAN;
+#endif
+}
+
+// Distorts the plane before iterating, moving each component of `c` by
+// `warp_amp * sin(warp_freq * c)`. A zero amplitude leaves `c` unchanged.
+fn warp(c: Complex) -> Complex {
+    let v = vec2(c.x, c.y);
+    let warped = v + uniforms.warp_amp * sin(uniforms.warp_freq * v);
+    return Complex(warped.x, warped.y);
+}
+
+fn pixel_index(location: vec2<u32>) -> u32 {
+    let size = textureDimensions(output);
+#ifdef STEREO
+    // Each eye's pixels follow the ones of the eyes before it.
+    return (eye * size.y + location.y) * size.x + location.x;
+#else
+    return location.y * size.x + location.x;
+#endif
+}
+
+// Writes the color of a texel in the layer of the invocation's eye.
+fn store_output(location: vec2<u32>, color: vec4<f32>) {
+#ifdef STEREO
+    textureStore(output, vec2<i32>(location), eye, color);
+#else
+    textureStore(output, vec2<i32>(location), color);
+#endif
+}
+
+// The pixel mirrored about the middle row.
+fn mirror_location(location: vec2<u32>) -> vec2<u32> {
+    return vec2(location.x, textureDimensions(output).y - 1 - location.y);
+}
+
+// Records the divergence of a pixel, negative for points that never escaped.
+fn store_iteration(index: u32, d: f32) {
+    if uniforms.iteration_storage == STORAGE_INTEGER {
+        var count = INTEGER_INTERIOR;
+        if d == DIVERGENCE_NON_FINITE {
+            count = INTEGER_NON_FINITE;
+        } else if d >= 0. {
+            count = min(u32(d), INTEGER_NON_FINITE - 1);
+        }
+        let shift = 16 * (index % 2);
+        // A resumed orbit replaces the interior count it stored before.
+        atomicAnd(&iterations[index / 2], ~(0xffffu << shift));
+        atomicOr(&iterations[index / 2], count << shift);
+    } else {
+        atomicStore(&iterations[index], bitcast<u32>(d));
+    }
+}
+
+// Records the outcome of a pixel, and of its mirror in symmetric views.
+fn store_escape(location: vec2<u32>, result: Escape) {
+    let index = pixel_index(location);
+    store_iteration(index, result.divergence);
+    distances[index] = result.distance;
+#ifdef ANGLE_COLORING
+    angles[index] = result.angle;
+#endif
+#ifdef ORBIT_AVERAGE
+    orbit_averages[index] = result.average;
+#endif
+    // The middle row of an odd height is its own mirror.
+    let mirror = mirror_location(location);
+    if symmetric_view() && mirror.y != location.y {
+        let mirror_index = pixel_index(mirror);
+        store_iteration(mirror_index, result.divergence);
+        distances[mirror_index] = result.distance;
+#ifdef ANGLE_COLORING
+        // Conjugate points escape at conjugate angles.
+        angles[mirror_index] = -result.angle;
+#endif
+#ifdef ORBIT_AVERAGE
+        // Conjugate points have conjugate orbits.
+        orbit_averages[mirror_index] = vec2(result.average.x, -result.average.y);
+#endif
+    }
+}
+
+fn load_iteration(index: u32) -> f32 {
+    if uniforms.iteration_storage == STORAGE_INTEGER {
+        let count = (atomicLoad(&iterations[index / 2]) >> (16 * (index % 2))) & 0xffff;
+        if count == INTEGER_INTERIOR {
+            return -1.;
+        }
+        if count == INTEGER_NON_FINITE {
+            return DIVERGENCE_NON_FINITE;
+        }
+        return f32(count);
+    }
+    return bitcast<f32>(atomicLoad(&iterations[index]));
+}
+
+// Maps an escape iteration in [0, max_iterations] to [0, 1] along the
+// selected curve.
+fn map_iteration(d: f32, curve: u32) -> f32 {
+    let max_iterations = f32(uniforms.max_iterations);
+    let q = d / max_iterations;
+    switch curve {
+        case CURVE_SQRT: {
+            return sqrt(q);
+        }
+        case CURVE_LOG: {
+            return log(1. + d) / log(1. + max_iterations);
+        }
+        case CURVE_CUBE_ROOT: {
+            return pow(q, 1. / 3.);
+        }
+        default: {
+            return q;
+        }
+    }
+}
+
+// Evaluates the transfer function at escape iteration `n`, running its
+// instructions on a stack. Binary instructions pop their right operand and
+// replace the left one with the result.
+fn transfer(n: f32) -> f32 {
+    var stack: array<f32, TRANSFER_STACK>;
+    var top = 0u;
+    for (var i = 0u; i < arrayLength(&transfer_ops); i++) {
+        let instruction = transfer_ops[i];
+        if instruction.op == TRANSFER_END {
+            break;
+        }
+        if instruction.op >= TRANSFER_ADD && instruction.op <= TRANSFER_POWER {
+            top -= 1u;
+        }
+        let a = stack[max(top, 1u) - 1u];
+        let b = stack[top];
+        var result = 0.;
+        switch instruction.op {
+            case TRANSFER_CONSTANT: {
+                result = instruction.value;
+            }
+            case TRANSFER_ITERATION: {
+                result = n;
+            }
+            case TRANSFER_MAX_ITERATIONS: {
+                result = f32(uniforms.max_iterations);
+            }
+            case TRANSFER_ADD: {
+                result = a + b;
+            }
+            case TRANSFER_SUBTRACT: {
+                result = a - b;
+            }
+            case TRANSFER_MULTIPLY: {
+                result = a * b;
+            }
+            case TRANSFER_DIVIDE: {
+                result = a / b;
+            }
+            case TRANSFER_POWER: {
+                result = pow(a, b);
+            }
+            case TRANSFER_NEGATE: {
+                result = -a;
+            }
+            case TRANSFER_SQRT: {
+                result = sqrt(a);
+            }
+            case TRANSFER_LOG: {
+                result = log(a);
+            }
+            case TRANSFER_EXP: {
+                result = exp(a);
+            }
+            case TRANSFER_SIN: {
+                result = sin(a);
+            }
+            case TRANSFER_COS: {
+                result = cos(a);
+            }
+            case TRANSFER_ABS: {
+                result = abs(a);
+            }
+            case TRANSFER_FLOOR: {
+                result = floor(a);
+            }
+            case TRANSFER_FRACT: {
+                result = fract(a);
+            }
+            default: {}
+        }
+        if instruction.op <= TRANSFER_MAX_ITERATIONS {
+            stack[top] = result;
+            top += 1u;
+        } else {
+            stack[max(top, 1u) - 1u] = result;
+        }
+    }
+    return stack[0];
+}
+
+// Moves a palette coordinate in [0, 1] along by `phase`, reflecting at the
+// ends so channels stay continuous. Offsetting channels by different phases
+// gives iridescent or rainbow palettes.
+fn phase_shift(q: f32, phase: f32) -> f32 {
+    let t = q + phase;
+    return 1. - abs(1. - (t - 2. * floor(t / 2.)));
+}
+
+// The palette at `q` in [0, 1], blending the two nearest texels. Compute
+// shaders can't sample with filtering, so the blend is done by hand.
+fn palette_color(q: f32) -> vec4<f32> {
+    let last = textureDimensions(palette).x - 1;
+    let x = clamp(q, 0., 1.) * f32(last);
+    let i = u32(x);
+    let a = textureLoad(palette, vec2(i, 0u), 0);
+    let b = textureLoad(palette, vec2(min(i + 1, last), 0u), 0);
+    return mix(a, b, fract(x));
+}
+
+// A fully saturated color of hue `h`, as a fraction of the color wheel.
+// Hues wrap around, so `h` and `h + 1` give the same color.
+fn hue(h: f32) -> vec3<f32> {
+    let k = fract(h + vec3(0., 2. / 3., 1. / 3.));
+    return clamp(abs(k * 6. - 3.) - 1., vec3(0.), vec3(1.));
+}
+
+// Angle coloring of an escaped pixel: the escape angle picks the hue, and
+// the escape iteration the value. The color wheel is closed, so the hue is
+// continuous where the angle wraps from pi to -pi.
+fn angle_color(index: u32, d: f32, curve: u32) -> vec4<f32> {
+#ifdef ANGLE_COLORING
+    let h = angles[index] / (2. * PI);
+#else
+    let h = 0.;
+#endif
+    let color = mix(vec3(1.), hue(h), ANGLE_SATURATION);
+    return vec4(color * map_iteration(d, curve), 1.);
+}
+
+// Average coloring of an escaped pixel: the direction of the orbit's
+// average from 0 picks the hue, its distance from 0 the saturation, and the
+// escape iteration the value. The average changes smoothly across escape
+// bands, so colors flow along the filaments rather than ringing the set.
+fn average_color(index: u32, d: f32, curve: u32) -> vec4<f32> {
+#ifdef ORBIT_AVERAGE
+    let average = orbit_averages[index];
+#else
+    let average = vec2(0.);
+#endif
+    let h = atan2(average.y, average.x) / (2. * PI);
+    let saturation = min(length(average) / AVERAGE_RADIUS, 1.);
+    let color = mix(vec3(1.), hue(h), saturation);
+    return vec4(color * map_iteration(d, curve), 1.);
+}
+
+// The palette color of a divergence, without angle or average coloring.
+fn escape_color(value: f32, curve: u32) -> vec4<f32> {
+    if value == DIVERGENCE_NON_FINITE {
+        return uniforms.non_finite_color;
+    }
+    if value < 0. {
         return vec4<f32>(0., 0., 0., 1.);
     }
 
-    let q = d / f32(MAX_ITERS);
-    let r = sqrt(q);
-    return vec4<f32>(r, q, q * q, 1.);
+    var d = value;
+    if uniforms.coloring_mode == COLORING_BANDED {
+        d = floor(d);
+    }
+
+#ifdef TRANSFER_FUNCTION
+    var q = transfer(d);
+#else
+    var q = map_iteration(d, curve);
+#endif
+    // The sky covers the start of the range and the palette the rest.
+    if q < uniforms.sky_end {
+        let sky = mix(uniforms.sky_color, uniforms.sky_edge_color, q / uniforms.sky_end);
+        return vec4(sky.rgb, 1.);
+    }
+    q = (q - uniforms.sky_end) / (1. - uniforms.sky_end);
+#ifdef COLOR_CYCLE
+    let phase = uniforms.channel_phase + uniforms.time * COLOR_CYCLE_RATE;
+#else
+    let phase = uniforms.channel_phase;
+#endif
+    let r = palette_color(phase_shift(q, phase.r)).r;
+    let g = palette_color(phase_shift(q, phase.g)).g;
+    let b = palette_color(phase_shift(q, phase.b)).b;
+    return vec4<f32>(r, g, b, 1.);
 }
 
-@compute @workgroup_size(8, 8, 1)
-fn checker_board(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
-    let location = vec2<i32>(i32(invocation_id.x), i32(invocation_id.y));
+fn iteration_color(index: u32, value: f32, curve: u32) -> vec4<f32> {
+    if uniforms.coloring_mode == COLORING_ANGLE && value >= 0. {
+        return angle_color(index, value, curve);
+    }
+    if uniforms.coloring_mode == COLORING_AVERAGE && value >= 0. {
+        return average_color(index, value, curve);
+    }
+    return escape_color(value, curve);
+}
+
+// The fractal blended over the one the view shows under BLEND_MODES: the
+// Julia set of `julia_c` over the Mandelbrot set, and the Mandelbrot set
+// over a Julia set.
+fn blend_layer_mode() -> u32 {
+    if uniforms.mode == MODE_JULIA {
+        return MODE_MANDELBROT;
+    }
+    return MODE_JULIA;
+}
+
+// Composites `layer` over `base` under `blend_mode`, weighted by
+// `blend_mix`. The blended layer is always palette colored, even under angle
+// coloring.
+fn blend_layers(base: vec4<f32>, layer: vec4<f32>) -> vec4<f32> {
+    var blended: vec3<f32>;
+    switch uniforms.blend_mode {
+        case BLEND_MULTIPLY: {
+            blended = base.rgb * layer.rgb;
+        }
+        case BLEND_SCREEN: {
+            blended = 1. - (1. - base.rgb) * (1. - layer.rgb);
+        }
+        default: {
+            blended = min(base.rgb + layer.rgb, vec3(1.));
+        }
+    }
+    return vec4(mix(base.rgb, blended, uniforms.blend_mix), base.a);
+}
+
+// Lambertian lighting of the smooth iteration surface at a pixel, using the
+// gradient towards its right and lower neighbors.
+fn relief(location: vec2<u32>, d: f32) -> f32 {
+    let last = textureDimensions(output) - 1;
+    let right = load_iteration(pixel_index(vec2(min(location.x + 1, last.x), location.y)));
+    let down = load_iteration(pixel_index(vec2(location.x, min(location.y + 1, last.y))));
+    // Points inside the set have no height; keep the slope there flat.
+    if right < 0. || down < 0. {
+        return 1.;
+    }
 
-    let f = pow(0.5, f32(uniforms.time) / 200.);
-    let x = (2. * f32(invocation_id.x) / 1280. - 1.) * f;
-    let y = (2. * f32(invocation_id.y) / 720. - 1.) * f + 1.;
+    let normal = normalize(vec3((d - right) * RELIEF_HEIGHT, (d - down) * RELIEF_HEIGHT, 1.));
+    let light = normalize(vec3(cos(uniforms.light_angle), sin(uniforms.light_angle), LIGHT_ELEVATION));
+    return max(dot(normal, light), 0.);
+}
+
+// Whether the point `offset` texels from a pixel, clamped to the texture,
+// never escaped.
+fn interior_at(location: vec2<u32>, offset: vec2<i32>) -> bool {
+    let last = vec2<i32>(textureDimensions(output)) - 1;
+    let neighbor = clamp(vec2<i32>(location) + offset, vec2(0), last);
+    return load_iteration(pixel_index(vec2<u32>(neighbor))) == -1.;
+}
 
-    textureStore(output, location, mandelbrot_color(x, y));
+// How much an escaped point looks cut short by the iteration cap, from 0 to
+// 1: it escaped late, and points around it didn't escape at all. Raising the
+// cap tends to turn such neighborhoods into detail.
+fn iteration_starvation(location: vec2<u32>, d: f32) -> f32 {
+    var interior = 0.;
+    if interior_at(location, vec2(CUE_RADIUS, 0)) {
+        interior += 0.25;
+    }
+    if interior_at(location, vec2(-CUE_RADIUS, 0)) {
+        interior += 0.25;
+    }
+    if interior_at(location, vec2(0, CUE_RADIUS)) {
+        interior += 0.25;
+    }
+    if interior_at(location, vec2(0, -CUE_RADIUS)) {
+        interior += 0.25;
+    }
+    return interior * smoothstep(CUE_START, 1., d / f32(uniforms.max_iterations));
+}
+
+// Height of the texel at `location` in the complex plane. Log-polar texels
+// grow with their distance from the center.
+fn texel_size(location: vec2<u32>) -> f32 {
+    let size = vec2<f32>(textureDimensions(output));
+    if uniforms.projection == PROJECTION_LOG_POLAR {
+        let step = log_polar_step();
+        let texels = f32(location.y) + 0.5 - size.y / 2.;
+        return uniforms.scale * exp(-texels * step) * step;
+    }
+    return 2. * uniforms.scale / size.y;
+}
+
+// Width of the boundary band at `location` in the complex plane.
+fn boundary_band(location: vec2<u32>) -> f32 {
+    return uniforms.boundary_width * texel_size(location);
+}
+
+// Blends an escaped point at `location` towards the glow color, fading
+// exponentially with its distance from the set.
+fn apply_glow(location: vec2<u32>, color: vec4<f32>, distance: f32) -> vec4<f32> {
+    let falloff = exp(-distance / (uniforms.glow_radius * texel_size(location)));
+    let glow = uniforms.glow_color;
+    return vec4(mix(color.rgb, glow.rgb, glow.a * falloff), color.a);
+}
+
+// The uniforms that only change how iterations are colored, so switching
+// between them can be crossfaded without iterating again.
+struct Style {
+    mapping_curve: u32,
+    shading: u32,
+    boundary: u32,
+}
+
+// Applies the alpha convention to a final color. Background pixels become
+// transparent.
+fn apply_alpha_mode(color: vec4<f32>, background: bool) -> vec4<f32> {
+    if uniforms.alpha_mode == ALPHA_OPAQUE {
+        return vec4(color.rgb, 1.);
+    }
+
+    var alpha = color.a;
+    if background {
+        alpha = 0.;
+    }
+    if uniforms.alpha_mode == ALPHA_PREMULTIPLIED {
+        return vec4(color.rgb * alpha, alpha);
+    }
+    return vec4(color.rgb, alpha);
+}
+
+// The final color of a pixel with divergence `d` under `style`.
+fn pixel_color(location: vec2<u32>, index: u32, d: f32, style: Style) -> vec4<f32> {
+    var color = iteration_color(index, d, style.mapping_curve);
+#ifdef BLEND_MODES
+    color = blend_layers(color, escape_color(blend_iterations[index], style.mapping_curve));
+#endif
+#ifdef RELIEF_SHADING
+    if style.shading != 0 && d >= 0. {
+        let shade = AMBIENT + (1. - AMBIENT) * relief(location, d);
+        color = vec4(color.rgb * shade, color.a);
+    }
+#endif
+    if uniforms.iteration_cue != 0 && d >= 0. {
+        let luma = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+        let cue = uniforms.iteration_cue_strength * iteration_starvation(location, d);
+        color = vec4(mix(color.rgb, vec3(luma), cue), color.a);
+    }
+    // The interior is background, and in boundary mode so is every escaped
+    // point off the band.
+    var background = d == -1.;
+#ifdef DISTANCE_ESTIMATE
+    if uniforms.glow != 0 && d >= 0. {
+        color = apply_glow(location, color, distances[index]);
+    }
+    if style.boundary != 0 && d >= 0. {
+        if distances[index] <= boundary_band(location) {
+            color = BOUNDARY_COLOR;
+        } else {
+            color = vec4(0., 0., 0., 1.);
+            background = true;
+        }
+    }
+#endif
+#ifdef ORBIT_DENSITY
+    let visits = atomicLoad(&orbit_density[index]);
+    if visits > 0 {
+        let most = atomicLoad(&orbit_density[arrayLength(&orbit_density) - 1]);
+        let density = min(log(1. + f32(visits)) / log(1. + f32(most)), 1.);
+        color = vec4(mix(color.rgb, ORBIT_COLOR, density), color.a);
+        background = false;
+    }
+#endif
+    return apply_alpha_mode(color, background);
+}
+
+// Blends a pixel's color with the one its point had in the previous frame,
+// and records the result for the next frame. Reading and writing separate
+// halves of the history keeps invocations from reading texels other
+// invocations of the same dispatch write.
+fn apply_history(location: vec2<u32>, color: vec4<f32>) -> vec4<f32> {
+    // With temporal blending off the history is a single placeholder texel.
+    if arrayLength(&history) == 1 {
+        return color;
+    }
+    let size = textureDimensions(output);
+    let pixels = size.x * size.y;
+    let written = uniforms.history_parity * pixels;
+    let read = (1 - uniforms.history_parity) * pixels;
+
+    var blended = color;
+    let previous = vec2<i32>(round(vec2<f32>(location) + uniforms.history_offset));
+    if uniforms.history_blend > 0. && all(previous >= vec2(0)) && all(previous < vec2<i32>(size)) {
+        let old = history[read + pixel_index(vec2<u32>(previous))];
+        let clamped = clamp(old, color - HISTORY_TOLERANCE, color + HISTORY_TOLERANCE);
+        blended = mix(color, clamped, uniforms.history_blend);
+    }
+    history[written + pixel_index(location)] = blended;
+    return blended;
+}
+
+@compute @workgroup_size(WORKGROUP_WIDTH, WORKGROUP_HEIGHT, 1)
+fn iterate(
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+    @builtin(local_invocation_id) local: vec3<u32>,
+    @builtin(num_workgroups) count: vec3<u32>,
+) {
+    eye = workgroup.z;
+#ifdef COMPUTE_BUDGET
+    let location = budget_location(workgroup, local);
+#else
+    let location = pixel_location(workgroup, local, count);
+#endif
+    if !in_bounds(location) {
+        return;
+    }
+    // The upper half iterates for both halves, the lower half is idle.
+    if symmetric_view() && mirror_location(location).y < location.y {
+        return;
+    }
+    let point = warp(pixel_to_complex(location));
+#ifdef BLEND_MODES
+    // The blended layer is iterated to the full cap right away, even for
+    // pixels left to the detail pass.
+    let layer = escape(point, blend_layer_mode(), uniforms.max_iterations);
+    blend_iterations[pixel_index(location)] = layer.divergence;
+#endif
+#ifdef DETAIL_PASS
+    // Points still iterating at a fraction of the cap are listed for the
+    // detail pass to iterate to the full cap. Nothing is stored for them
+    // yet, since integer counts are OR-ed into the buffer.
+    let result = escape(point, uniforms.mode, max(uniforms.max_iterations / DETAIL_SPLIT, 1u));
+    if result.divergence == -1. {
+        let slot = atomicAdd(&detail_args[3], 1u);
+        detail_pixels[slot] = pixel_index(location);
+        // Each slot that starts a workgroup adds it to the dispatch.
+        if slot % DETAIL_WORKGROUP_SIZE == 0 {
+            atomicAdd(&detail_args[0], 1u);
+        }
+        return;
+    }
+#else
+#ifdef WARM_START
+    // Escaped orbits keep their stored results, and so do interior ones
+    // already iterated to the cap.
+    let index = pixel_index(location);
+    var state = orbit_states[index];
+    let capped = state.iterations != 0u && state.iterations >= uniforms.max_iterations;
+    if state.escaped != 0u || capped {
+        return;
+    }
+    if state.iterations == 0u {
+        state = start_orbit(point, uniforms.mode);
+    }
+    let result = resume_escape(point, uniforms.mode, uniforms.max_iterations, &state);
+    orbit_states[index] = state;
+#else
+    let result = escape(point, uniforms.mode, uniforms.max_iterations);
+#endif
+#endif
+    store_escape(location, result);
+}
+
+// Finishes the pixels the iterate pass listed, under DETAIL_PASS.
+@compute @workgroup_size(DETAIL_WORKGROUP_SIZE, 1, 1)
+fn detail(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= atomicLoad(&detail_args[3]) {
+        return;
+    }
+    let index = detail_pixels[id.x];
+    let size = textureDimensions(output);
+    // Listed indices run on through the layers of stereo views.
+    eye = index / (size.x * size.y);
+    let pixel = index % (size.x * size.y);
+    let location = vec2(pixel % size.x, pixel / size.x);
+    let point = warp(pixel_to_complex(location));
+    store_escape(location, escape(point, uniforms.mode, uniforms.max_iterations));
+}
+
+// Traces orbits around the seed points, counting the visits of each pixel
+// into `orbit_density`. Each row of workgroups traces one seed, from points
+// jittered within a texel of it, so the density fills in over frames.
+@compute @workgroup_size(ORBIT_WORKGROUP_SIZE, 1, 1)
+fn orbits(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output);
+    var rng = rng_seed(id.xy);
+    let jitter = vec2(rng_f32(&rng), rng_f32(&rng)) - 0.5;
+    let seed = orbit_seeds[id.y] + jitter * (2. * uniforms.scale / f32(size.y));
+    let point = warp(Complex(seed.x, seed.y));
+    var z = Complex(0., 0.);
+    var c = point;
+    if uniforms.mode == MODE_JULIA {
+        z = point;
+        c = Complex(uniforms.julia_c.x, uniforms.julia_c.y);
+    }
+
+    let most = size.x * size.y;
+    for (var i = 0u; i < uniforms.max_iterations; i++) {
+        z = complex_add(complex_sq(z), c);
+        // Written to fail for NaN too.
+        if !(complex_mag2(z) <= ORBIT_ESCAPE_RADIUS * ORBIT_ESCAPE_RADIUS) {
+            return;
+        }
+        let texel = complex_to_pixel(z);
+        if any(texel < vec2(0)) || any(texel >= vec2<i32>(size)) {
+            continue;
+        }
+        let visits = atomicAdd(&orbit_density[pixel_index(vec2<u32>(texel))], 1u) + 1;
+        // Raising the maximum only at powers of two keeps every orbit from
+        // contending for it, and leaves it within a factor of two, close
+        // enough for the log scale it's drawn on.
+        if (visits & (visits - 1)) == 0 {
+            atomicMax(&orbit_density[most], visits);
+        }
+    }
+}
+
+// The raw distance estimate of a pixel under distance coloring: the
+// distance to the set in complex-plane units in red and in texels in green,
+// both 0 inside the set, and alpha 1 outside the set and 0 inside.
+fn distance_output(location: vec2<u32>, index: u32, d: f32) -> vec4<f32> {
+    if d < 0. {
+        return vec4(0.);
+    }
+#ifdef DISTANCE_ESTIMATE
+    let distance = distances[index];
+#else
+    let distance = 0.;
+#endif
+    return vec4(distance, distance / texel_size(location), 0., 1.);
+}
+
+@compute @workgroup_size(WORKGROUP_WIDTH, WORKGROUP_HEIGHT, 1)
+fn recolor(
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+    @builtin(local_invocation_id) local: vec3<u32>,
+    @builtin(num_workgroups) count: vec3<u32>,
+) {
+    eye = workgroup.z;
+    let location = pixel_location(workgroup, local, count);
+    if !in_bounds(location) {
+        return;
+    }
+    let index = pixel_index(location);
+    let d = load_iteration(index);
+    // Distances are data rather than colors, so no styling or blending
+    // applies.
+    if uniforms.coloring_mode == COLORING_DISTANCE {
+        store_output(location, distance_output(location, index, d));
+        return;
+    }
+
+    let current = Style(uniforms.mapping_curve, uniforms.shading, uniforms.boundary);
+    var color = pixel_color(location, index, d, current);
+    if uniforms.transition < 1. {
+        let previous = Style(
+            uniforms.previous_mapping_curve,
+            uniforms.previous_shading,
+            uniforms.previous_boundary,
+        );
+        color = mix(pixel_color(location, index, d, previous), color, uniforms.transition);
+    }
+    color = apply_history(location, color);
+    store_output(location, color);
 }
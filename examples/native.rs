@@ -0,0 +1,788 @@
+//! Renders one frame with the Mandelbrot compute shader on a bare `wgpu`
+//! device, without Bevy, and saves it as `native.png`.
+//!
+//! This sets up the same bind group layout and uniform buffer as
+//! `init_mandelbrot_pipeline`, and shares `assets/mandelbrot.wgsl`, so it
+//! doubles as a reference for driving the kernel from other hosts. The shader's
+//! `#ifdef` feature blocks are resolved by a minimal preprocessor.
+//!
+//! ```sh
+//! cargo run --example native
+//! ```
+//!
+//! Channels are written with 8 bits by default. `-- --png-depth 16` writes 16
+//! bits per channel instead, keeping the precision of the float texture in
+//! smooth gradients.
+//!
+//! `-- --selftest` checks that the GPU and driver can run the kernel instead:
+//! the device limits, shader compilation, a tiny render of a known pixel and
+//! a round trip of the uniforms through the shader. It prints a summary of
+//! the checks and exits with status 1 if any failed. The checks live here
+//! rather than behind a flag of the app, because they drive the kernel on a
+//! bare device; the app only reaches the GPU through Bevy's renderer, once
+//! its window is open, which is what a failing driver keeps from happening.
+
+use std::{borrow::Cow, f32::consts::FRAC_PI_4, fs::File, io::BufWriter};
+
+use bytemuck::{Pod, Zeroable, bytes_of, cast_slice};
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = include_str!("../assets/mandelbrot.wgsl");
+const SIZE: (u32, u32) = (1280, 720);
+const WORKGROUP_SIZE: u32 = 8;
+const OUTPUT_PATH: &str = "native.png";
+/// Size of the self-test render, whose center lies in the main cardioid.
+const SELFTEST_SIZE: (u32, u32) = (64, 64);
+/// Texels in the palette lookup texture, as in `src/palette.rs`.
+const PALETTE_SIZE: u32 = 256;
+
+/// Mirror of `Uniforms` in `src/lib.rs`; the layout must match the `Uniforms`
+/// struct in the shader field for field.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct Uniforms {
+  time: f32,
+  iteration_storage: u32,
+  coloring_mode: u32,
+  mapping_curve: u32,
+  shading: u32,
+  light_angle: f32,
+  center: [f32; 2],
+  scale: f32,
+  non_finite: u32,
+  alpha_mode: u32,
+  rotation: f32,
+  non_finite_color: [f32; 4],
+  boundary: u32,
+  boundary_width: f32,
+  dispatch_order: u32,
+  previous_mapping_curve: u32,
+  previous_shading: u32,
+  previous_boundary: u32,
+  transition: f32,
+  glow: u32,
+  glow_color: [f32; 4],
+  glow_radius: f32,
+  warp_amp: f32,
+  warp_freq: f32,
+  pixel_aspect: f32,
+  channel_phase: [f32; 3],
+  mode: u32,
+  julia_c: [f32; 2],
+  use_symmetry: u32,
+  max_iterations: u32,
+  history_offset: [f32; 2],
+  history_blend: f32,
+  history_parity: u32,
+  iteration_cue: u32,
+  iteration_cue_strength: f32,
+  frame: u32,
+  blend_mode: u32,
+  blend_mix: f32,
+  projection: u32,
+  sky_end: f32,
+  eye_separation: f32,
+  sky_color: [f32; 4],
+  sky_edge_color: [f32; 4],
+}
+
+impl Default for Uniforms {
+  /// The view the app starts with.
+  fn default() -> Self {
+    Self {
+      time: 0.,
+      iteration_storage: 0,
+      coloring_mode: 0,
+      mapping_curve: 1,
+      shading: 0,
+      light_angle: FRAC_PI_4,
+      center: [-0.5, 0.],
+      scale: 1.25,
+      non_finite: 0,
+      alpha_mode: 0,
+      rotation: 0.,
+      non_finite_color: [1., 0., 1., 1.],
+      boundary: 0,
+      boundary_width: 1.5,
+      dispatch_order: 0,
+      previous_mapping_curve: 1,
+      previous_shading: 0,
+      previous_boundary: 0,
+      transition: 1.,
+      glow: 0,
+      glow_color: [1., 0.7, 0.3, 1.],
+      glow_radius: 8.,
+      warp_amp: 0.,
+      warp_freq: 1.,
+      pixel_aspect: 1.,
+      channel_phase: [0.; 3],
+      mode: 0,
+      julia_c: [0.; 2],
+      use_symmetry: 1,
+      max_iterations: 2000,
+      history_offset: [0.; 2],
+      history_blend: 0.,
+      history_parity: 0,
+      iteration_cue: 0,
+      iteration_cue_strength: 0.6,
+      frame: 0,
+      blend_mode: 0,
+      blend_mix: 0.5,
+      projection: 0,
+      sky_end: 0.,
+      eye_separation: 0.,
+      sky_color: [0.; 4],
+      sky_edge_color: [0.; 4],
+    }
+  }
+}
+
+fn main() {
+  let instance = wgpu::Instance::default();
+  let adapter =
+    pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+      .expect("No suitable GPU adapter");
+  let (device, queue) =
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))
+      .expect("Failed to create device");
+  if std::env::args().any(|arg| arg == "--selftest") {
+    let passed = selftest(&device, &queue);
+    std::process::exit(if passed { 0 } else { 1 });
+  }
+  // Checked before rendering, so a typo doesn't cost a frame.
+  let depth = png_depth();
+
+  let texels = render(&device, &queue, SIZE, &Uniforms::default());
+  let bytes_per_sample = if depth == png::BitDepth::Sixteen {
+    2
+  } else {
+    1
+  };
+  let mut pixels = Vec::with_capacity(texels.len() * 4 * bytes_per_sample);
+  for texel in texels {
+    for value in [
+      linear_to_srgb(texel[0]),
+      linear_to_srgb(texel[1]),
+      linear_to_srgb(texel[2]),
+      texel[3],
+    ] {
+      push_sample(&mut pixels, value, depth);
+    }
+  }
+
+  let writer = BufWriter::new(File::create(OUTPUT_PATH).expect("Failed to create output file"));
+  let mut encoder = png::Encoder::new(writer, SIZE.0, SIZE.1);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(depth);
+  encoder
+    .write_header()
+    .and_then(|mut writer| writer.write_image_data(&pixels))
+    .expect("Failed to write PNG");
+  println!("Wrote {OUTPUT_PATH}");
+}
+
+/// Preprocesses and compiles the shader.
+fn create_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
+  // The default view needs none of the feature defs.
+  let workgroup_size = WORKGROUP_SIZE.to_string();
+  let shader_defs = [
+    ("WORKGROUP_WIDTH", workgroup_size.as_str()),
+    ("WORKGROUP_HEIGHT", workgroup_size.as_str()),
+  ];
+  device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("Mandelbrot"),
+    source: wgpu::ShaderSource::Wgsl(Cow::Owned(preprocess(SHADER, &shader_defs))),
+  })
+}
+
+/// Renders `uniforms` into a texture of `size` and reads it back as linear
+/// RGBA texels, row by row.
+fn render(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  size: (u32, u32),
+  uniforms: &Uniforms,
+) -> Vec<[f32; 4]> {
+  let shader = create_shader(device);
+
+  let texture = device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Mandelbrot"),
+    size: wgpu::Extent3d {
+      width: size.0,
+      height: size.1,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: wgpu::TextureFormat::Rgba32Float,
+    usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+    view_formats: &[],
+  });
+  let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+  // The default palette, `Palette::classic`, sampled directly from its curve.
+  let palette_texels: Vec<[f32; 4]> = (0..PALETTE_SIZE)
+    .map(|i| {
+      let t = i as f32 / (PALETTE_SIZE - 1) as f32;
+      [t.sqrt(), t, t * t, 1.]
+    })
+    .collect();
+  let palette = device.create_texture_with_data(
+    queue,
+    &wgpu::TextureDescriptor {
+      label: Some("Palette"),
+      size: wgpu::Extent3d {
+        width: PALETTE_SIZE,
+        height: 1,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba32Float,
+      usage: wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    },
+    wgpu::util::TextureDataOrder::LayerMajor,
+    cast_slice(&palette_texels),
+  );
+  let palette_view = palette.create_view(&wgpu::TextureViewDescriptor::default());
+
+  let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+    label: Some("Uniforms"),
+    contents: bytes_of(uniforms),
+    usage: wgpu::BufferUsages::UNIFORM,
+  });
+  // Smooth iteration storage: one f32 per pixel.
+  let iterations_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Iterations"),
+    size: size.0 as u64 * size.1 as u64 * 4,
+    usage: wgpu::BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+  let distances_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Distances"),
+    size: size.0 as u64 * size.1 as u64 * 4,
+    usage: wgpu::BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+
+  // Temporal blending is off, which the shader reads from a one-texel history.
+  let history_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("History"),
+    size: 16,
+    usage: wgpu::BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+
+  let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+    label: Some("Mandelbrot"),
+    entries: &[
+      wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::StorageTexture {
+          access: wgpu::StorageTextureAccess::WriteOnly,
+          format: wgpu::TextureFormat::Rgba32Float,
+          view_dimension: wgpu::TextureViewDimension::D2,
+        },
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        // Pipeline creation fails if the shader's struct is larger than the
+        // mirror, catching fields added to only one of them.
+        ty: wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: wgpu::BufferSize::new(size_of::<Uniforms>() as u64),
+        },
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry {
+        binding: 2,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Storage { read_only: false },
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry {
+        binding: 3,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Storage { read_only: false },
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry {
+        binding: 4,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Float { filterable: false },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry {
+        binding: 5,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Storage { read_only: false },
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      },
+    ],
+  });
+  let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+    label: Some("Mandelbrot"),
+    layout: &bind_group_layout,
+    entries: &[
+      wgpu::BindGroupEntry {
+        binding: 0,
+        resource: wgpu::BindingResource::TextureView(&texture_view),
+      },
+      wgpu::BindGroupEntry {
+        binding: 1,
+        resource: uniform_buffer.as_entire_binding(),
+      },
+      wgpu::BindGroupEntry {
+        binding: 2,
+        resource: iterations_buffer.as_entire_binding(),
+      },
+      wgpu::BindGroupEntry {
+        binding: 3,
+        resource: distances_buffer.as_entire_binding(),
+      },
+      wgpu::BindGroupEntry {
+        binding: 4,
+        resource: wgpu::BindingResource::TextureView(&palette_view),
+      },
+      wgpu::BindGroupEntry {
+        binding: 5,
+        resource: history_buffer.as_entire_binding(),
+      },
+    ],
+  });
+
+  let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+    label: Some("Mandelbrot"),
+    bind_group_layouts: &[&bind_group_layout],
+    push_constant_ranges: &[],
+  });
+  let pipeline = |entry_point| {
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+      label: Some(entry_point),
+      layout: Some(&pipeline_layout),
+      module: &shader,
+      entry_point: Some(entry_point),
+      compilation_options: Default::default(),
+      cache: None,
+    })
+  };
+  let iterate_pipeline = pipeline("iterate");
+  let recolor_pipeline = pipeline("recolor");
+
+  // Rows of a texture-to-buffer copy must be 256-byte aligned.
+  let bytes_per_row = (size.0 * 16).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+  let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Readback"),
+    size: bytes_per_row as u64 * size.1 as u64,
+    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+    mapped_at_creation: false,
+  });
+
+  let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+  {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+    pass.set_bind_group(0, &bind_group, &[]);
+    let workgroups = (
+      size.0.div_ceil(WORKGROUP_SIZE),
+      size.1.div_ceil(WORKGROUP_SIZE),
+    );
+    pass.set_pipeline(&iterate_pipeline);
+    pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+    pass.set_pipeline(&recolor_pipeline);
+    pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+  }
+  encoder.copy_texture_to_buffer(
+    texture.as_image_copy(),
+    wgpu::TexelCopyBufferInfo {
+      buffer: &readback_buffer,
+      layout: wgpu::TexelCopyBufferLayout {
+        offset: 0,
+        bytes_per_row: Some(bytes_per_row),
+        rows_per_image: None,
+      },
+    },
+    texture.size(),
+  );
+  queue.submit(Some(encoder.finish()));
+
+  // The copy is unaligned once out of the mapped range.
+  let data = read_buffer(device, &readback_buffer);
+  data
+    .chunks_exact(bytes_per_row as usize)
+    .flat_map(|row| row[..size.0 as usize * 16].chunks_exact(16))
+    .map(bytemuck::pod_read_unaligned)
+    .collect()
+}
+
+/// Maps `buffer`, which must be `MAP_READ`, after the work submitted so far
+/// and copies out its contents.
+fn read_buffer(device: &wgpu::Device, buffer: &wgpu::Buffer) -> Vec<u8> {
+  buffer.slice(..).map_async(wgpu::MapMode::Read, |r| {
+    r.expect("Failed to map readback buffer")
+  });
+  device
+    .poll(wgpu::PollType::Wait)
+    .expect("Failed to wait for device");
+  let data = buffer.slice(..).get_mapped_range().to_vec();
+  buffer.unmap();
+  data
+}
+
+/// Runs the self-test checks on `device` and prints a line per check and a
+/// summary. Returns whether every check passed.
+fn selftest(device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+  let checks: [(&str, Result<(), String>); 4] = [
+    ("device limits", check_limits(&device.limits())),
+    ("shader compiles", check_shader(device)),
+    ("known center pixel", check_render(device, queue)),
+    ("uniforms round-trip", check_uniforms(device, queue)),
+  ];
+  let mut passed = 0;
+  for (name, result) in &checks {
+    match result {
+      Ok(()) => {
+        passed += 1;
+        println!("pass  {name}");
+      }
+      Err(error) => println!("FAIL  {name}: {error}"),
+    }
+  }
+  println!("{passed} of {} checks passed", checks.len());
+  passed == checks.len()
+}
+
+/// Checks that `limits` allow the workgroup size, bindings and texture size
+/// `render` uses.
+fn check_limits(limits: &wgpu::Limits) -> Result<(), String> {
+  let pixels = SIZE.0 as u64 * SIZE.1 as u64;
+  let needs = [
+    (
+      "max_compute_workgroup_size_x",
+      limits.max_compute_workgroup_size_x as u64,
+      WORKGROUP_SIZE as u64,
+    ),
+    (
+      "max_compute_workgroup_size_y",
+      limits.max_compute_workgroup_size_y as u64,
+      WORKGROUP_SIZE as u64,
+    ),
+    (
+      "max_compute_invocations_per_workgroup",
+      limits.max_compute_invocations_per_workgroup as u64,
+      (WORKGROUP_SIZE * WORKGROUP_SIZE) as u64,
+    ),
+    // Iterations, distances and history.
+    (
+      "max_storage_buffers_per_shader_stage",
+      limits.max_storage_buffers_per_shader_stage as u64,
+      3,
+    ),
+    (
+      "max_storage_buffer_binding_size",
+      limits.max_storage_buffer_binding_size as u64,
+      pixels * 4,
+    ),
+    (
+      "max_texture_dimension_2d",
+      limits.max_texture_dimension_2d as u64,
+      SIZE.0.max(SIZE.1) as u64,
+    ),
+  ];
+  let short: Vec<String> = needs
+    .into_iter()
+    .filter(|&(_, limit, need)| limit < need)
+    .map(|(name, limit, need)| format!("{name} is {limit}, needs {need}"))
+    .collect();
+  if short.is_empty() {
+    Ok(())
+  } else {
+    Err(short.join(", "))
+  }
+}
+
+/// Checks that the preprocessed shader passes validation.
+fn check_shader(device: &wgpu::Device) -> Result<(), String> {
+  capture_errors(device, || {
+    create_shader(device);
+  })
+}
+
+/// Checks that a tiny render of the default view colors its center, which
+/// lies inside the set, opaque black. A texture the kernel never wrote would
+/// be transparent.
+fn check_render(device: &wgpu::Device, queue: &wgpu::Queue) -> Result<(), String> {
+  let mut texels = Vec::new();
+  capture_errors(device, || {
+    texels = render(device, queue, SELFTEST_SIZE, &Uniforms::default());
+  })?;
+  let center = texels[(SELFTEST_SIZE.1 / 2 * SELFTEST_SIZE.0 + SELFTEST_SIZE.0 / 2) as usize];
+  if center == [0., 0., 0., 1.] {
+    Ok(())
+  } else {
+    Err(format!(
+      "center is {center:?}, expected [0.0, 0.0, 0.0, 1.0]"
+    ))
+  }
+}
+
+/// Kernel copying fields of the uniforms, as the shader declares them, out
+/// as raw bits. Appended to the shader's `Uniforms` struct.
+const UNIFORMS_PROBE: &str = "
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var<storage, read_write> fields: array<u32, 12>;
+
+@compute @workgroup_size(1)
+fn probe() {
+    fields[0] = bitcast<u32>(uniforms.time);
+    fields[1] = bitcast<u32>(uniforms.center.x);
+    fields[2] = bitcast<u32>(uniforms.center.y);
+    fields[3] = bitcast<u32>(uniforms.scale);
+    fields[4] = bitcast<u32>(uniforms.channel_phase.z);
+    fields[5] = uniforms.max_iterations;
+    fields[6] = uniforms.frame;
+    fields[7] = bitcast<u32>(uniforms.sky_end);
+    fields[8] = bitcast<u32>(uniforms.eye_separation);
+    fields[9] = bitcast<u32>(uniforms.sky_edge_color.x);
+    fields[10] = bitcast<u32>(uniforms.sky_edge_color.z);
+    fields[11] = bitcast<u32>(uniforms.sky_edge_color.w);
+}
+";
+
+/// Checks that the shader reads the uniforms where this struct writes them,
+/// by uploading them and having the shader copy fields across the struct
+/// back out. A field whose offset differs between the two reads back as a
+/// neighbor's value.
+fn check_uniforms(device: &wgpu::Device, queue: &wgpu::Queue) -> Result<(), String> {
+  // Distinct values across the struct, so a shifted field shows.
+  let uniforms = Uniforms {
+    time: 1.5,
+    center: [-0.75, 0.1],
+    scale: 0.01,
+    channel_phase: [0.25, 0.5, 0.75],
+    max_iterations: 1234,
+    frame: 7,
+    sky_end: 0.2,
+    eye_separation: 0.03,
+    sky_edge_color: [0.1, 0.2, 0.3, 0.9],
+    ..Uniforms::default()
+  };
+  let expected = [
+    uniforms.time.to_bits(),
+    uniforms.center[0].to_bits(),
+    uniforms.center[1].to_bits(),
+    uniforms.scale.to_bits(),
+    uniforms.channel_phase[2].to_bits(),
+    uniforms.max_iterations,
+    uniforms.frame,
+    uniforms.sky_end.to_bits(),
+    uniforms.eye_separation.to_bits(),
+    uniforms.sky_edge_color[0].to_bits(),
+    uniforms.sky_edge_color[2].to_bits(),
+    uniforms.sky_edge_color[3].to_bits(),
+  ];
+  let names = [
+    "time",
+    "center.x",
+    "center.y",
+    "scale",
+    "channel_phase.z",
+    "max_iterations",
+    "frame",
+    "sky_end",
+    "eye_separation",
+    "sky_edge_color.x",
+    "sky_edge_color.z",
+    "sky_edge_color.w",
+  ];
+
+  // The struct is free of shader defs, so it compiles on its own.
+  let start = SHADER
+    .find("struct Uniforms {")
+    .ok_or("the shader declares no Uniforms")?;
+  let end = start + SHADER[start..].find("\n}").ok_or("unterminated Uniforms")? + 2;
+  let source = format!("{}\n{UNIFORMS_PROBE}", &SHADER[start..end]);
+  let size = size_of_val(&expected) as u64;
+  let mut data = Vec::new();
+  capture_errors(device, || {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("Uniforms probe"),
+      source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+      label: Some("Uniforms probe"),
+      layout: None,
+      module: &module,
+      entry_point: Some("probe"),
+      compilation_options: Default::default(),
+      cache: None,
+    });
+    let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Uniforms"),
+      contents: bytes_of(&uniforms),
+      usage: wgpu::BufferUsages::UNIFORM,
+    });
+    let fields_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Uniform fields"),
+      size,
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    });
+    let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Readback"),
+      size,
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("Uniforms probe"),
+      layout: &pipeline.get_bind_group_layout(0),
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: uniform_buffer.as_entire_binding(),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: fields_buffer.as_entire_binding(),
+        },
+      ],
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+      let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+      pass.set_pipeline(&pipeline);
+      pass.set_bind_group(0, &bind_group, &[]);
+      pass.dispatch_workgroups(1, 1, 1);
+    }
+    encoder.copy_buffer_to_buffer(&fields_buffer, 0, &readback_buffer, 0, size);
+    queue.submit(Some(encoder.finish()));
+    data = read_buffer(device, &readback_buffer);
+  })?;
+
+  let fields: Vec<u32> = data
+    .chunks_exact(4)
+    .map(bytemuck::pod_read_unaligned)
+    .collect();
+  if fields.len() != expected.len() {
+    return Err(format!("read back {} of {size} bytes", data.len()));
+  }
+  let wrong: Vec<&str> = names
+    .iter()
+    .zip(fields.iter().zip(expected))
+    .filter(|&(_, (&field, expected))| field != expected)
+    .map(|(&name, _)| name)
+    .collect();
+  if wrong.is_empty() {
+    Ok(())
+  } else {
+    Err(format!(
+      "the shader reads other values for {}",
+      wrong.join(", ")
+    ))
+  }
+}
+
+/// Runs `f`, turning the validation errors it raises into an error instead of
+/// a panic.
+fn capture_errors(device: &wgpu::Device, f: impl FnOnce()) -> Result<(), String> {
+  device.push_error_scope(wgpu::ErrorFilter::Validation);
+  f();
+  match pollster::block_on(device.pop_error_scope()) {
+    None => Ok(()),
+    Some(error) => Err(error.to_string()),
+  }
+}
+
+/// Resolves `#ifdef`, `#ifndef`, `#else` and `#endif` lines, keeping the
+/// lines whose enclosing conditions all hold, and substitutes `#{NAME}` with
+/// the value of each def.
+fn preprocess(source: &str, defs: &[(&str, &str)]) -> String {
+  let defined = |name: &str| defs.iter().any(|&(def, _)| def == name.trim());
+  let mut conditions: Vec<bool> = Vec::new();
+  let mut output = String::with_capacity(source.len());
+  for line in source.lines() {
+    let directive = line.trim();
+    if let Some(def) = directive.strip_prefix("#ifdef ") {
+      conditions.push(defined(def));
+    } else if let Some(def) = directive.strip_prefix("#ifndef ") {
+      conditions.push(!defined(def));
+    } else if directive == "#else" {
+      let condition = conditions.last_mut().expect("#else without #ifdef");
+      *condition = !*condition;
+    } else if directive == "#endif" {
+      conditions.pop().expect("#endif without #ifdef");
+    } else if conditions.iter().all(|&condition| condition) {
+      let mut line = line.to_string();
+      for (name, value) in defs {
+        line = line.replace(&format!("#{{{name}}}"), value);
+      }
+      output.push_str(&line);
+      output.push('\n');
+    }
+  }
+  output
+}
+
+/// The channel depth chosen with `--png-depth`, 8 bits if it isn't given.
+fn png_depth() -> png::BitDepth {
+  let args: Vec<String> = std::env::args().collect();
+  let Some(flag) = args.iter().position(|arg| arg == "--png-depth") else {
+    return png::BitDepth::Eight;
+  };
+  match args.get(flag + 1).map(String::as_str) {
+    Some("8") => png::BitDepth::Eight,
+    Some("16") => png::BitDepth::Sixteen,
+    value => {
+      eprintln!("--png-depth must be 8 or 16, got {value:?}");
+      std::process::exit(2);
+    }
+  }
+}
+
+/// Quantizes a sample in [0, 1] to `depth` bits and appends it, big endian
+/// as PNG stores 16-bit samples.
+fn push_sample(pixels: &mut Vec<u8>, value: f32, depth: png::BitDepth) {
+  let value = value.clamp(0., 1.);
+  match depth {
+    png::BitDepth::Sixteen => {
+      pixels.extend(((value * 65535.).round() as u16).to_be_bytes());
+    }
+    _ => pixels.push((value * 255.).round() as u8),
+  }
+}
+
+/// The texture holds linear color; PNGs are sRGB encoded. The encoding is
+/// applied before quantizing, so either depth spends its levels evenly in
+/// perceived brightness.
+fn linear_to_srgb(value: f32) -> f32 {
+  let value = value.clamp(0., 1.);
+  if value <= 0.0031308 {
+    value * 12.92
+  } else {
+    1.055 * value.powf(1. / 2.4) - 0.055
+  }
+}
@@ -0,0 +1,130 @@
+//! Keeping the view centered on detail.
+//!
+//! Automated zooms into a fixed point tend to drift into flat exterior,
+//! where every further frame is wasted. With an [`AutoCenter`] resource
+//! present, the iteration data of each frame is read back and `center` is
+//! nudged toward the most detailed part of it, as found by
+//! [`detail_offset`].
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    event::EventReader,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Local, Res, ResMut},
+  },
+  math::Vec2,
+};
+
+use crate::{
+  RenderConfig, Uniforms,
+  export::{self, FrameReadback, ReadbackRequest},
+};
+
+/// Spacing of the texels sampled for detail, in texels.
+const SAMPLE_STRIDE: usize = 4;
+/// Side of the cells detail is accumulated over, in samples.
+const CELL_SAMPLES: usize = 8;
+/// Cap on the detail a single pair of samples contributes, so a few
+/// saturated pixels can't outweigh a whole cell of fine structure.
+const MAX_SAMPLE_DETAIL: f32 = 4.;
+
+/// Nudges `center` toward the detail of each frame while present. Reading
+/// back every frame stalls the GPU, so this is meant for exports and
+/// animations rather than interactive use.
+#[derive(Resource, Clone, Debug)]
+pub struct AutoCenter {
+  /// Fraction of the way to the center of detail moved per frame read
+  /// back, between 0 and 1.
+  pub strength: f32,
+}
+
+impl Default for AutoCenter {
+  fn default() -> Self {
+    Self { strength: 0.1 }
+  }
+}
+
+pub(crate) struct AutoCenterPlugin;
+
+impl Plugin for AutoCenterPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(Update, auto_center.after(export::receive_readbacks));
+  }
+}
+
+/// The offset from the middle of a `size` texture to its center of detail,
+/// in texels growing right and down, or `None` for a featureless frame.
+///
+/// Detail is measured on a sparse grid of samples as the change in escape
+/// iteration to the next sample right and down, with a sample on one side
+/// of the set's boundary and its neighbor on the other counting the most.
+/// Cells of samples are weighted by their detail, so one noisy texel
+/// doesn't pull the center, and the offset points at their weighted mean.
+pub fn detail_offset((width, height): (u32, u32), iterations: &[f32]) -> Option<Vec2> {
+  let (width, height) = (width as usize, height as usize);
+  let cell = SAMPLE_STRIDE * CELL_SAMPLES;
+  let (columns, rows) = (width.div_ceil(cell), height.div_ceil(cell));
+  let mut cells = vec![0f32; columns * rows];
+
+  let sample_detail = |a: f32, b: f32| match (a >= 0., b >= 0.) {
+    (true, true) => (a - b).abs().min(MAX_SAMPLE_DETAIL),
+    (false, false) => 0.,
+    _ => MAX_SAMPLE_DETAIL,
+  };
+  for y in (0..height.saturating_sub(SAMPLE_STRIDE)).step_by(SAMPLE_STRIDE) {
+    for x in (0..width.saturating_sub(SAMPLE_STRIDE)).step_by(SAMPLE_STRIDE) {
+      let here = iterations[y * width + x];
+      let right = iterations[y * width + x + SAMPLE_STRIDE];
+      let down = iterations[(y + SAMPLE_STRIDE) * width + x];
+      cells[(y / cell) * columns + x / cell] +=
+        sample_detail(here, right) + sample_detail(here, down);
+    }
+  }
+
+  let mut total = 0.;
+  let mut weighted = Vec2::ZERO;
+  for (index, &detail) in cells.iter().enumerate() {
+    let middle = Vec2::new(
+      (index % columns) as f32 + 0.5,
+      (index / columns) as f32 + 0.5,
+    ) * cell as f32;
+    total += detail;
+    weighted += middle * detail;
+  }
+  (total > 0.).then(|| weighted / total - Vec2::new(width as f32, height as f32) / 2.)
+}
+
+fn auto_center(
+  auto_center: Option<Res<AutoCenter>>,
+  config: Res<RenderConfig>,
+  mut readbacks: EventReader<FrameReadback>,
+  mut request: ResMut<ReadbackRequest>,
+  mut pending: Local<bool>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let Some(auto_center) = auto_center else {
+    readbacks.clear();
+    *pending = false;
+    return;
+  };
+
+  for readback in readbacks.read() {
+    *pending = false;
+    let Some(offset) = detail_offset(config.size, &readback.iterations) else {
+      continue;
+    };
+    // The view may have moved since the frame was rendered, so move by the
+    // frame's offset rather than toward its target.
+    let target = readback.uniforms.texel_offset_to_complex(&config, offset);
+    uniforms.center += (target - readback.uniforms.center) * auto_center.strength;
+  }
+
+  // Keep one readback in flight, so requests don't pile up while the
+  // pipelines load.
+  if !*pending {
+    request.request();
+    *pending = true;
+  }
+}
@@ -0,0 +1,156 @@
+//! Adapting the iteration cap to the view's content.
+//!
+//! How many iterations a view needs depends on what's in it more than on
+//! how deep it is: a view of flat exterior needs few, while one straddling
+//! the boundary of a deep minibrot needs many. With an [`AutoIterations`]
+//! resource present, a frame is read back every
+//! [`AutoIterations::interval`] seconds and the escape iterations of every
+//! few of its texels are checked for how many escaped close to the cap.
+//! That is the fraction of detail the cap is about to cut short, and
+//! `max_iterations` is raised while it's above [`AutoIterations::target`]
+//! and lowered while it's well below.
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    event::EventReader,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Local, Res, ResMut},
+  },
+  log::debug,
+  time::Time,
+};
+
+use crate::{
+  Uniforms,
+  export::{self, FrameReadback, ReadbackRequest},
+  iterations::IterationSteps,
+};
+
+/// Spacing of the texels sampled, in texels.
+const SAMPLE_STRIDE: usize = 4;
+/// Escapes past this fraction of the cap count as close to it.
+const CAP_BAND: f32 = 0.9;
+/// The cap is only lowered once the fraction near it drops below this
+/// fraction of the target, so it doesn't flip between two values.
+const LOWER_BELOW: f32 = 0.25;
+/// Factors the cap is raised and lowered by per adjustment.
+const RAISE_FACTOR: f32 = 1.5;
+const LOWER_FACTOR: f32 = 0.8;
+
+/// Adjusts `max_iterations` to the view while present. Each adjustment reads
+/// a frame back, which stalls the GPU briefly, so they are spaced out by
+/// `interval`.
+#[derive(Resource, Clone, Debug)]
+pub struct AutoIterations {
+  /// Fraction of the escaped texels sampled that may escape close to the
+  /// cap before it's raised.
+  pub target: f32,
+  /// Seconds between adjustments.
+  pub interval: f32,
+  /// Bounds of the cap.
+  pub min_iterations: u32,
+  pub max_iterations: u32,
+}
+
+impl Default for AutoIterations {
+  fn default() -> Self {
+    Self {
+      target: 0.01,
+      interval: 0.5,
+      min_iterations: 100,
+      max_iterations: 100_000,
+    }
+  }
+}
+
+impl AutoIterations {
+  /// Keeps the fraction of texels escaping close to the cap at `target`.
+  pub fn with_target(mut self, target: f32) -> Self {
+    self.target = target;
+    self
+  }
+}
+
+/// The fraction of every `SAMPLE_STRIDE`th texel, of those that escaped,
+/// that escaped close to `max_iterations`, or `None` if none escaped.
+pub fn near_cap_fraction(iterations: &[f32], max_iterations: u32) -> Option<f32> {
+  let band = CAP_BAND * max_iterations as f32;
+  let escaped = iterations
+    .iter()
+    .step_by(SAMPLE_STRIDE)
+    .filter(|&&d| d >= 0.);
+  let (count, near) = escaped.fold((0, 0), |(count, near), &d| {
+    (count + 1, near + (d >= band) as u32)
+  });
+  (count > 0).then(|| near as f32 / count as f32)
+}
+
+/// When the next frame is read back.
+#[derive(Default)]
+struct Sampling {
+  /// Whether a readback is in flight.
+  pending: bool,
+  /// Seconds since the last readback was requested.
+  since: f32,
+}
+
+pub(crate) struct AutoIterationsPlugin;
+
+impl Plugin for AutoIterationsPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(Update, auto_iterations.after(export::receive_readbacks));
+  }
+}
+
+fn auto_iterations(
+  auto_iterations: Option<Res<AutoIterations>>,
+  steps: Res<IterationSteps>,
+  time: Res<Time>,
+  mut readbacks: EventReader<FrameReadback>,
+  mut request: ResMut<ReadbackRequest>,
+  mut sampling: Local<Sampling>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let Some(auto_iterations) = auto_iterations else {
+    readbacks.clear();
+    *sampling = Sampling::default();
+    return;
+  };
+
+  for readback in readbacks.read() {
+    sampling.pending = false;
+    // A reveal or a step key changed the cap since, and wins.
+    let cap = readback.uniforms.max_iterations;
+    if steps.revealing() || uniforms.max_iterations != cap {
+      continue;
+    }
+    let Some(fraction) = near_cap_fraction(&readback.iterations, cap) else {
+      continue;
+    };
+    let adjusted = if fraction > auto_iterations.target {
+      (cap as f32 * RAISE_FACTOR) as u32
+    } else if fraction < auto_iterations.target * LOWER_BELOW {
+      (cap as f32 * LOWER_FACTOR) as u32
+    } else {
+      cap
+    };
+    let adjusted = adjusted
+      .min(auto_iterations.max_iterations)
+      .max(auto_iterations.min_iterations);
+    if adjusted != cap {
+      debug!(
+        "{:.2}% of escapes near the cap of {cap}, adjusting it to {adjusted}",
+        fraction * 100.
+      );
+      uniforms.max_iterations = adjusted;
+    }
+  }
+
+  sampling.since += time.delta_secs();
+  if !sampling.pending && sampling.since >= auto_iterations.interval {
+    request.request();
+    *sampling = Sampling { pending: true, since: 0. };
+  }
+}
@@ -0,0 +1,78 @@
+//! Driving the view from stdin.
+//!
+//! Every line of stdin is a JSON object with any subset of the fields of a
+//! [`View`], applied on top of the current view as soon as it arrives:
+//!
+//! ```json
+//! {"center": [-0.7436, 0.1318], "scale": 0.001}
+//! {"rotation": 0.5}
+//! ```
+//!
+//! Together with [`Action::DumpView`](crate::keybinds::Action::DumpView)
+//! this lets an external program both read and steer the view. Lines are
+//! read on a background thread, since reading stdin blocks.
+
+use std::io::{self, BufRead};
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    resource::Resource,
+    system::{Res, ResMut},
+  },
+  log::warn,
+};
+use crossbeam_channel::Receiver;
+use serde_json::Value;
+
+use crate::{Uniforms, view::View};
+
+#[derive(Resource)]
+struct StdinLines(Receiver<String>);
+
+pub(crate) struct ControlPlugin;
+
+impl Plugin for ControlPlugin {
+  fn build(&self, app: &mut App) {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || {
+      for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+          break;
+        };
+        if sender.send(line).is_err() {
+          break;
+        }
+      }
+    });
+
+    app
+      .insert_resource(StdinLines(receiver))
+      .add_systems(Update, apply_stdin_views);
+  }
+}
+
+fn apply_stdin_views(lines: Res<StdinLines>, mut uniforms: ResMut<Uniforms>) {
+  for line in lines.0.try_iter() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    match merge_view(View::from_uniforms(&uniforms), &line) {
+      Ok(view) => view.apply_to(&mut uniforms),
+      Err(err) => warn!("Skipping invalid view update {line:?}: {err}"),
+    }
+  }
+}
+
+/// Overwrites the fields of `view` present in the JSON object `update`.
+fn merge_view(view: View, update: &str) -> serde_json::Result<View> {
+  let Value::Object(update) = serde_json::from_str(update)? else {
+    return Err(serde::de::Error::custom("expected a JSON object"));
+  };
+  let mut merged = serde_json::to_value(view)?;
+  merged
+    .as_object_mut()
+    .expect("views serialize to objects")
+    .extend(update);
+  serde_json::from_value(merged)
+}
@@ -0,0 +1,146 @@
+//! On-demand dumps of the render world's state.
+//!
+//! [`Action::DumpRenderState`] logs the render graph, the state of every
+//! compute pipeline variant and which of the resources the compute node
+//! needs are present. A black screen usually comes down to a pipeline that
+//! failed to compile or a resource that was never inserted, and the dump
+//! shows which.
+
+use std::fmt::Write;
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    resource::Resource,
+    system::{Local, Res, ResMut},
+    world::World,
+  },
+  log::info,
+  render::{
+    Render, RenderApp, RenderSystems,
+    extract_resource::{ExtractResource, ExtractResourcePlugin},
+    render_graph::RenderGraph,
+    render_resource::{CachedComputePipelineId, CachedPipelineState, PipelineCache},
+  },
+};
+
+use crate::{
+  MandelbrotImageBindGroups, MandelbrotImages, MandelbrotPipeline, PipelineKey,
+  export::FrameComplete,
+  keybinds::{Action, ActionInput},
+};
+
+/// Bumped for every dump the main world wants. The render world dumps once
+/// each time it changes.
+#[derive(Resource, Clone, Copy, Default, ExtractResource)]
+struct RenderDumpRequest(u32);
+
+pub(crate) struct DiagnosticsPlugin;
+
+impl Plugin for DiagnosticsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<RenderDumpRequest>()
+      .add_plugins(ExtractResourcePlugin::<RenderDumpRequest>::default())
+      .add_systems(Update, request_render_dump);
+    app
+      .sub_app_mut(RenderApp)
+      .add_systems(Render, dump_render_state.after(RenderSystems::Render));
+  }
+}
+
+fn request_render_dump(input: ActionInput, mut request: ResMut<RenderDumpRequest>) {
+  if input.just_pressed(Action::DumpRenderState) {
+    request.0 += 1;
+  }
+}
+
+/// Appends the nodes of `graph` and its sub graphs to `out`, each with the
+/// nodes it runs before.
+fn describe_graph(out: &mut String, graph: &RenderGraph, depth: usize) {
+  let indent = "  ".repeat(depth);
+  for node in graph.iter_nodes() {
+    let _ = write!(out, "\n{indent}{:?} ({})", node.label, node.type_name);
+    let next: Vec<_> = node
+      .edges
+      .output_edges()
+      .iter()
+      .map(|edge| format!("{:?}", edge.get_input_node()))
+      .collect();
+    if !next.is_empty() {
+      let _ = write!(out, " -> {}", next.join(", "));
+    }
+  }
+  for (label, sub_graph) in graph.iter_sub_graphs() {
+    let _ = write!(out, "\n{indent}sub graph {label:?}:");
+    describe_graph(out, sub_graph, depth + 1);
+  }
+}
+
+fn describe_pipeline(pipeline_cache: &PipelineCache, id: CachedComputePipelineId) -> String {
+  match pipeline_cache.get_compute_pipeline_state(id) {
+    CachedPipelineState::Queued => "queued".to_owned(),
+    CachedPipelineState::Creating(_) => "compiling".to_owned(),
+    CachedPipelineState::Ok(_) => "ready".to_owned(),
+    CachedPipelineState::Err(err) => format!("failed: {err}"),
+  }
+}
+
+fn dump_render_state(world: &World, request: Res<RenderDumpRequest>, mut handled: Local<u32>) {
+  if request.0 == *handled {
+    return;
+  }
+  *handled = request.0;
+
+  let mut out = String::from("Render graph:");
+  describe_graph(&mut out, world.resource::<RenderGraph>(), 1);
+
+  out += "\nMandelbrot pipelines:";
+  match world.get_resource::<MandelbrotPipeline>() {
+    Some(pipeline) => {
+      let pipeline_cache = world.resource::<PipelineCache>();
+      let current = world.get_resource::<PipelineKey>().copied();
+      for (key, variant) in &pipeline.variants {
+        let marker = if Some(*key) == current {
+          " (current)"
+        } else {
+          ""
+        };
+        let _ = write!(
+          out,
+          "\n  {key:?}{marker}: iterate {}, recolor {}",
+          describe_pipeline(pipeline_cache, variant.iterate),
+          describe_pipeline(pipeline_cache, variant.recolor),
+        );
+        if let Some(detail) = variant.detail {
+          let _ = write!(
+            out,
+            ", detail {}",
+            describe_pipeline(pipeline_cache, detail)
+          );
+        }
+        if let Some(orbits) = variant.orbits {
+          let _ = write!(
+            out,
+            ", orbits {}",
+            describe_pipeline(pipeline_cache, orbits)
+          );
+        }
+      }
+      if pipeline.variants.is_empty() {
+        out += "\n  none queued";
+      }
+    }
+    None => out += "\n  MandelbrotPipeline missing",
+  }
+
+  let present = |present: bool| if present { "present" } else { "missing" };
+  let _ = write!(
+    out,
+    "\nMandelbrotImages: {}\nBind group: {}\nFrame complete: {}",
+    present(world.contains_resource::<MandelbrotImages>()),
+    present(world.contains_resource::<MandelbrotImageBindGroups>()),
+    world.contains_resource::<FrameComplete>(),
+  );
+  info!("{out}");
+}
@@ -0,0 +1,171 @@
+//! Estimating the fractal dimension of the boundary in view.
+//!
+//! [`Action::MeasureDimension`] reads back the next complete frame and
+//! estimates the box-counting dimension of the set's boundary within it,
+//! shown in the status line until the view changes. The boundary of the
+//! Mandelbrot set has dimension 2, but a frame only resolves it down to a
+//! texel and only as far as the iteration cap lets points escape, so the
+//! estimate is rough: it depends on the view, the resolution and the
+//! iteration count, and mostly tells a wispy region from a smooth one.
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    event::EventReader,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Res, ResMut},
+  },
+  log::info,
+};
+
+use crate::{
+  RenderConfig, Uniforms,
+  export::{self, FrameReadback, ReadbackRequest},
+  keybinds::{Action, ActionInput},
+};
+
+/// Boxes are counted at sizes from 1 texel up to this fraction of the
+/// shorter side of the frame, so even the largest size has enough boxes to
+/// count.
+const MAX_BOX_FRACTION: usize = 8;
+/// Box sizes needed for a meaningful fit.
+const MIN_BOX_SIZES: usize = 3;
+
+/// The last measurement, and whether one is on its way.
+#[derive(Resource, Default)]
+pub(crate) struct BoundaryDimension {
+  pending: bool,
+  /// The uniforms of the frame measured, with its estimate, or `None` if it
+  /// had no boundary to measure.
+  measured: Option<(Uniforms, Option<f32>)>,
+}
+
+impl BoundaryDimension {
+  /// Describes the measurement for the status line, while it matches the
+  /// view in `uniforms`.
+  pub(crate) fn describe(&self, uniforms: &Uniforms) -> Option<String> {
+    if self.pending {
+      return Some("measuring boundary dimension".to_owned());
+    }
+    match self.measured {
+      Some((measured, estimate)) if measured.iterates_like(uniforms) => Some(match estimate {
+        Some(dimension) => format!("boundary dimension ~{dimension:.3}"),
+        None => "no boundary in view".to_owned(),
+      }),
+      _ => None,
+    }
+  }
+}
+
+pub(crate) struct DimensionPlugin;
+
+impl Plugin for DimensionPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<BoundaryDimension>().add_systems(
+      Update,
+      (
+        request_measurement.before(export::receive_readbacks),
+        measure.after(export::receive_readbacks),
+      ),
+    );
+  }
+}
+
+/// Estimates the box-counting dimension of the set's boundary in a `size`
+/// frame of escape iterations, or `None` if the frame has too little
+/// boundary to measure.
+///
+/// Boundary texels are those inside the set with a 4-neighbor outside it.
+/// The boxes of each power-of-two size containing a boundary texel are
+/// counted, and the dimension is the slope of a least-squares fit of the
+/// logarithm of the count against the logarithm of the inverse box size.
+pub fn boundary_dimension((width, height): (u32, u32), iterations: &[f32]) -> Option<f32> {
+  let (width, height) = (width as usize, height as usize);
+  let inside = |x: usize, y: usize| iterations[y * width + x] == -1.;
+  let boundary: Vec<(usize, usize)> = (0..height)
+    .flat_map(|y| (0..width).map(move |x| (x, y)))
+    .filter(|&(x, y)| {
+      inside(x, y)
+        && ((x > 0 && !inside(x - 1, y))
+          || (x + 1 < width && !inside(x + 1, y))
+          || (y > 0 && !inside(x, y - 1))
+          || (y + 1 < height && !inside(x, y + 1)))
+    })
+    .collect();
+
+  let max_size = width.min(height) / MAX_BOX_FRACTION;
+  let points: Vec<(f32, f32)> = (0..)
+    .map(|power| 1usize << power)
+    .take_while(|&size| size <= max_size)
+    .map(|size| {
+      let columns = width.div_ceil(size);
+      let mut boxes = vec![false; columns * height.div_ceil(size)];
+      for &(x, y) in &boundary {
+        boxes[(y / size) * columns + x / size] = true;
+      }
+      let count = boxes.iter().filter(|&&filled| filled).count();
+      (-(size as f32).ln(), count as f32)
+    })
+    .filter(|&(_, count)| count > 0.)
+    .map(|(log_inverse_size, count)| (log_inverse_size, count.ln()))
+    .collect();
+  if points.len() < MIN_BOX_SIZES {
+    return None;
+  }
+
+  let n = points.len() as f32;
+  let mean_x = points.iter().map(|&(x, _)| x).sum::<f32>() / n;
+  let mean_y = points.iter().map(|&(_, y)| y).sum::<f32>() / n;
+  let (covariance, variance) = points.iter().fold((0., 0.), |(cov, var), &(x, y)| {
+    (
+      cov + (x - mean_x) * (y - mean_y),
+      var + (x - mean_x) * (x - mean_x),
+    )
+  });
+  // A boundary of a few texels fills about as many boxes at every size, a
+  // slope near 0 that says nothing about its dimension.
+  let slope = covariance / variance;
+  (slope > 0.).then_some(slope)
+}
+
+fn request_measurement(
+  input: ActionInput,
+  mut dimension: ResMut<BoundaryDimension>,
+  mut request: ResMut<ReadbackRequest>,
+) {
+  if input.just_pressed(Action::MeasureDimension) && !dimension.pending {
+    dimension.pending = true;
+    request.request();
+  }
+}
+
+fn measure(
+  mut readbacks: EventReader<FrameReadback>,
+  config: Res<RenderConfig>,
+  mut dimension: ResMut<BoundaryDimension>,
+) {
+  let Some(readback) = readbacks.read().last() else {
+    return;
+  };
+  // Readbacks asked for by exports and the like aren't measured.
+  if !dimension.pending {
+    return;
+  }
+
+  let estimate = boundary_dimension(config.size, &readback.iterations);
+  match estimate {
+    Some(estimate) => info!(
+      "Estimated boundary dimension {estimate:.3} at center {} {:+}i, scale {}, {} iterations",
+      readback.uniforms.center.x,
+      readback.uniforms.center.y,
+      readback.uniforms.scale,
+      readback.uniforms.max_iterations
+    ),
+    None => info!("Too little of the boundary in view to estimate its dimension"),
+  }
+  *dimension = BoundaryDimension {
+    pending: false,
+    measured: Some((readback.uniforms, estimate)),
+  };
+}
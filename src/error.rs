@@ -0,0 +1,107 @@
+//! Errors while setting up or rendering the fractal.
+//!
+//! A failure the renderer can't work around stops the renderer rather than
+//! the app: the texture keeps its last frame, the error is logged and a
+//! [`MandelbrotFailure`] event carries it in the main world, wherever it
+//! happened, so a host can react. Transient failures, like a buffer that
+//! couldn't be mapped this frame, are logged and retried on a later one.
+
+use std::fmt;
+
+use bevy::{
+  app::{App, First, Plugin},
+  ecs::{
+    event::{Event, EventWriter},
+    resource::Resource,
+    system::Res,
+  },
+  log::error,
+  render::RenderApp,
+};
+use crossbeam_channel::{Receiver, Sender};
+
+#[derive(Clone, Debug)]
+pub enum MandelbrotError {
+  /// The texture passed to
+  /// [`with_target`](crate::MandelbrotComputePlugin::with_target) can't be
+  /// rendered into, for the given reason. The plugin renders into a texture
+  /// of its own instead.
+  InvalidTarget(String),
+  /// The shader lacks an entry point a pass dispatches.
+  MissingEntryPoint {
+    shader: String,
+    entry_point: &'static str,
+  },
+  /// A compute pipeline failed to compile.
+  Pipeline { shader: String, message: String },
+  /// A GPU buffer couldn't be mapped for the CPU.
+  BufferMap(String),
+  /// Waiting for the GPU to finish its work failed.
+  Poll(String),
+}
+
+impl fmt::Display for MandelbrotError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::InvalidTarget(reason) => {
+        write!(
+          f,
+          "The target passed to `with_target` can't be rendered into: {reason}"
+        )
+      }
+      Self::MissingEntryPoint { shader, entry_point } => {
+        write!(
+          f,
+          "{shader} has no `{entry_point}` entry point, which the compute passes need"
+        )
+      }
+      Self::Pipeline { shader, message } => write!(f, "Initializing {shader}:\n{message}"),
+      Self::BufferMap(err) => write!(f, "Failed to map buffer: {err}"),
+      Self::Poll(err) => write!(f, "Failed to wait for render device: {err}"),
+    }
+  }
+}
+
+impl std::error::Error for MandelbrotError {}
+
+/// Sent in the main world for each failure that stopped the renderer, or
+/// part of it.
+#[derive(Event, Clone, Debug)]
+pub struct MandelbrotFailure(pub MandelbrotError);
+
+/// Reports failures from either world to the main one.
+#[derive(Resource, Clone)]
+pub(crate) struct FailureSender(Sender<MandelbrotError>);
+
+impl FailureSender {
+  pub(crate) fn report(&self, error: MandelbrotError) {
+    // The receiver lives as long as the app.
+    let _ = self.0.send(error);
+  }
+}
+
+#[derive(Resource)]
+struct FailureReceiver(Receiver<MandelbrotError>);
+
+pub(crate) struct ErrorPlugin;
+
+impl Plugin for ErrorPlugin {
+  fn build(&self, app: &mut App) {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    app
+      .insert_resource(FailureSender(sender.clone()))
+      .insert_resource(FailureReceiver(receiver))
+      .add_event::<MandelbrotFailure>()
+      .add_systems(First, receive_failures);
+    app
+      .sub_app_mut(RenderApp)
+      .insert_resource(FailureSender(sender));
+  }
+}
+
+fn receive_failures(receiver: Res<FailureReceiver>, mut failures: EventWriter<MandelbrotFailure>) {
+  for failure in receiver.0.try_iter() {
+    error!("{failure}");
+    failures.write(MandelbrotFailure(failure));
+  }
+}
@@ -0,0 +1,442 @@
+//! Exporting the raw iteration data of a frame.
+//!
+//! The iteration and distance buffers only live on the GPU, so an export asks
+//! the render world to read them back after the next complete frame. The
+//! render world copies both into staging buffers, waits for the copy and sends
+//! the bytes back over a channel, where the main world decodes them into a
+//! [`FrameReadback`] event for the export, and anything else that asked, to
+//! use.
+//!
+//! EXR files hold two 32-bit float channels:
+//!
+//! - `iterations`: the smooth escape iteration of each pixel, the value the
+//!   palette is looked up from. It is -1 for points inside the set and -2 for
+//!   highlighted non-finite orbits. Under integer iteration storage the
+//!   values are whole numbers.
+//! - `distance`: the estimated distance from each pixel to the set, in
+//!   complex-plane units, and 0 for points inside the set. It is unsigned,
+//!   as the estimate only holds outside the set. This is the same distance
+//!   [`ColoringMode::Distance`](crate::ColoringMode::Distance) writes to the
+//!   texture.
+//!
+//! The view is recorded in the `center`, `scale`, `rotation`,
+//! `max_iterations` and `julia_c` attributes, with the same meaning as in
+//! [`View`](crate::view::View), and the fractal in the `mode` attribute,
+//! `mandelbrot` or `julia`. `julia_c` is only used by Julia sets.
+//!
+//! Stereo views keep the left eye's pixels first in the buffers, so
+//! exports, and readbacks indexed by position, see the left eye.
+//!
+//! With [`ExrExport::report`], a JSON summary of the escape iterations is
+//! written next to the file, with the extension `.json`: the iteration cap,
+//! the fractions of pixels inside the set (at the cap) and non-finite, and
+//! the minimum, maximum, mean and percentiles of the escape iterations of
+//! the other pixels. A view whose upper percentiles crowd the cap, or that
+//! has a lot at the cap, is likely cut short by it, while one whose maximum
+//! is far below it could iterate less.
+
+use std::{fs, path::PathBuf};
+
+use bevy::{
+  app::{App, AppExit, Plugin, Update},
+  ecs::{
+    event::{Event, EventReader, EventWriter},
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Commands, Local, Res, ResMut, SystemParam},
+  },
+  log::{error, info},
+  render::{
+    Render, RenderApp, RenderSystems,
+    extract_resource::{ExtractResource, ExtractResourcePlugin},
+    render_resource::{
+      Buffer, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, MapMode, PollType,
+    },
+    renderer::{RenderDevice, RenderQueue},
+  },
+};
+use crossbeam_channel::{Receiver, Sender};
+use exr::{
+  image::{Encoding, Image, Layer, SpecificChannels},
+  meta::attribute::{AttributeValue, LayerAttributes, Text},
+};
+use serde::Serialize;
+
+use crate::{
+  DispatchedUniforms, FractalMode, IterationStorage, MandelbrotPipeline, RenderConfig, Uniforms,
+  error::MandelbrotError,
+};
+
+// Integer counts the shader records for special points.
+const INTEGER_INTERIOR: u32 = 0xffff;
+const INTEGER_NON_FINITE: u32 = 0xfffe;
+
+/// Requests that the iteration data of the next complete frame be written to
+/// an EXR file. The request is removed once the file is written.
+#[derive(Resource, Clone, Debug)]
+pub struct ExrExport {
+  pub path: PathBuf,
+  /// Exit the app once the file is written.
+  pub exit: bool,
+  /// Also write a summary of the escape iterations next to the file. See
+  /// the module docs.
+  pub report: bool,
+}
+
+/// Counts the readbacks the main world wants. The render world reads back
+/// once each time the count changes.
+#[derive(Resource, Clone, Copy, Default, ExtractResource)]
+pub(crate) struct ReadbackRequest {
+  requested: u32,
+  /// The count as of the latest readback to arrive.
+  answered: u32,
+}
+
+impl ReadbackRequest {
+  /// Asks for a [`FrameReadback`] of the next complete frame.
+  pub(crate) fn request(&mut self) {
+    self.requested += 1;
+  }
+
+  /// Whether a requested readback hasn't arrived yet.
+  pub(crate) fn pending(&self) -> bool {
+    self.requested != self.answered
+  }
+}
+
+/// The decoded buffers of a complete frame, sent after a readback request.
+#[derive(Event)]
+pub(crate) struct FrameReadback {
+  /// The uniforms the frame was rendered with.
+  pub uniforms: Uniforms,
+  /// Escape iterations, decoded as described in the module docs.
+  pub iterations: Vec<f32>,
+  pub distances: Vec<f32>,
+}
+
+/// Set in the render world while the compute pipelines have loaded and the
+/// view has been iterated in full, so a readback never sees a frame that was
+/// never dispatched or is still filling in under a compute budget.
+#[derive(Resource)]
+pub(crate) struct FrameComplete;
+
+/// The buffers of one frame, as read back from the GPU.
+struct Readback {
+  /// The request count the readback answers.
+  request: u32,
+  uniforms: Uniforms,
+  iterations: Vec<u8>,
+  distances: Vec<u8>,
+}
+
+#[derive(Resource)]
+struct ReadbackSender(Sender<Readback>);
+
+#[derive(Resource)]
+struct ReadbackReceiver(Receiver<Readback>);
+
+pub(crate) struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+  fn build(&self, app: &mut App) {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    app
+      .init_resource::<ReadbackRequest>()
+      .insert_resource(ReadbackReceiver(receiver))
+      .add_event::<FrameReadback>()
+      .add_plugins(ExtractResourcePlugin::<ReadbackRequest>::default())
+      .add_systems(
+        Update,
+        (request_export, receive_readbacks, write_export).chain(),
+      );
+    app
+      .sub_app_mut(RenderApp)
+      .insert_resource(ReadbackSender(sender))
+      .add_systems(Render, read_back.after(RenderSystems::Render));
+  }
+}
+
+fn request_export(export: Option<Res<ExrExport>>, mut request: ResMut<ReadbackRequest>) {
+  if export.is_some_and(|export| export.is_added()) {
+    request.request();
+  }
+}
+
+pub(crate) fn receive_readbacks(
+  receiver: Res<ReadbackReceiver>,
+  config: Res<RenderConfig>,
+  mut request: ResMut<ReadbackRequest>,
+  mut readbacks: EventWriter<FrameReadback>,
+) {
+  for readback in receiver.0.try_iter() {
+    request.answered = readback.request;
+    readbacks.write(FrameReadback {
+      uniforms: readback.uniforms,
+      iterations: decode_iterations(&config, &readback.iterations),
+      distances: words(&readback.distances).map(f32::from_bits).collect(),
+    });
+  }
+}
+
+fn write_export(
+  mut commands: Commands,
+  mut readbacks: EventReader<FrameReadback>,
+  export: Option<Res<ExrExport>>,
+  config: Res<RenderConfig>,
+  mut exit: EventWriter<AppExit>,
+) {
+  let Some(readback) = readbacks.read().last() else {
+    return;
+  };
+  let Some(export) = export else {
+    return;
+  };
+
+  match write_exr(
+    &export,
+    config.size,
+    &readback.uniforms,
+    &readback.iterations,
+    &readback.distances,
+  ) {
+    Ok(()) => info!("Exported iteration data to {}", export.path.display()),
+    Err(err) => error!("Failed to write {}: {err}", export.path.display()),
+  }
+  if export.report {
+    let path = export.path.with_extension("json");
+    let pixels = config.size.0 as usize * config.size.1 as usize;
+    let report = IterationReport::new(&readback.uniforms, &readback.iterations[..pixels]);
+    let json = serde_json::to_string_pretty(&report).expect("reports always serialize");
+    match fs::write(&path, json) {
+      Ok(()) => info!("Wrote the iteration report to {}", path.display()),
+      Err(err) => error!("Failed to write {}: {err}", path.display()),
+    }
+  }
+
+  commands.remove_resource::<ExrExport>();
+  if export.exit {
+    exit.write(AppExit::Success);
+  }
+}
+
+fn write_exr(
+  export: &ExrExport,
+  (width, height): (u32, u32),
+  uniforms: &Uniforms,
+  iterations: &[f32],
+  distances: &[f32],
+) -> exr::error::Result<()> {
+  let (width, height) = (width as usize, height as usize);
+  let channels = SpecificChannels::build()
+    .with_channel("iterations")
+    .with_channel("distance")
+    .with_pixel_fn(|position: exr::math::Vec2<usize>| {
+      let index = position.y() * width + position.x();
+      (iterations[index], distances[index])
+    });
+
+  let mut attributes = LayerAttributes::named("mandelbrot");
+  let center = exr::math::Vec2(uniforms.center.x, uniforms.center.y);
+  let julia_c = exr::math::Vec2(uniforms.julia_c.x, uniforms.julia_c.y);
+  let mode = if uniforms.mode == FractalMode::Julia as u32 {
+    "julia"
+  } else {
+    "mandelbrot"
+  };
+  for (name, value) in [
+    ("center", AttributeValue::FloatVec2(center)),
+    ("scale", AttributeValue::F32(uniforms.scale)),
+    ("rotation", AttributeValue::F32(uniforms.rotation)),
+    // EXR has no unsigned attributes.
+    (
+      "max_iterations",
+      AttributeValue::I32(uniforms.max_iterations.try_into().unwrap_or(i32::MAX)),
+    ),
+    ("mode", AttributeValue::Text(Text::from(mode))),
+    ("julia_c", AttributeValue::FloatVec2(julia_c)),
+  ] {
+    attributes.other.insert(Text::from(name), value);
+  }
+
+  let layer = Layer::new(
+    (width, height),
+    attributes,
+    Encoding::FAST_LOSSLESS,
+    channels,
+  );
+  Image::from_layer(layer).write().to_file(&export.path)
+}
+
+/// The distribution of escape iterations over a frame, as written next to an
+/// export.
+#[derive(Serialize)]
+struct IterationReport {
+  max_iterations: u32,
+  pixels: usize,
+  /// Fraction of pixels that reached the cap without escaping.
+  interior_fraction: f32,
+  /// Fraction of pixels whose orbits overflowed under
+  /// [`NonFinite::Highlight`](crate::NonFinite::Highlight).
+  non_finite_fraction: f32,
+  /// Statistics of the pixels that escaped, absent if none did.
+  escaped: Option<EscapeStats>,
+}
+
+#[derive(Serialize)]
+struct EscapeStats {
+  min: f32,
+  max: f32,
+  mean: f32,
+  p10: f32,
+  p50: f32,
+  p90: f32,
+  p99: f32,
+}
+
+impl IterationReport {
+  fn new(uniforms: &Uniforms, iterations: &[f32]) -> Self {
+    let fraction = |value: f32| {
+      let count = iterations.iter().filter(|&&d| d == value).count();
+      count as f32 / iterations.len().max(1) as f32
+    };
+    let mut escaped: Vec<f32> = iterations.iter().copied().filter(|&d| d >= 0.).collect();
+    escaped.sort_by(f32::total_cmp);
+    Self {
+      max_iterations: uniforms.max_iterations,
+      pixels: iterations.len(),
+      interior_fraction: fraction(-1.),
+      non_finite_fraction: fraction(-2.),
+      escaped: EscapeStats::new(&escaped),
+    }
+  }
+}
+
+impl EscapeStats {
+  /// Statistics of `sorted`, which must be in ascending order.
+  fn new(sorted: &[f32]) -> Option<Self> {
+    let (&min, &max) = (sorted.first()?, sorted.last()?);
+    // The nearest rank percentile.
+    let percentile = |p: f32| {
+      let rank = (p / 100. * sorted.len() as f32).ceil() as usize;
+      sorted[rank.clamp(1, sorted.len()) - 1]
+    };
+    let sum: f64 = sorted.iter().map(|&d| d as f64).sum();
+    Some(Self {
+      min,
+      max,
+      mean: (sum / sorted.len() as f64) as f32,
+      p10: percentile(10.),
+      p50: percentile(50.),
+      p90: percentile(90.),
+      p99: percentile(99.),
+    })
+  }
+}
+
+/// Little-endian 32-bit words of a buffer.
+fn words(bytes: &[u8]) -> impl Iterator<Item = u32> + '_ {
+  bytes
+    .chunks_exact(4)
+    .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+}
+
+/// Decodes the iteration buffer the way `load_iteration` in the shader does.
+fn decode_iterations(config: &RenderConfig, bytes: &[u8]) -> Vec<f32> {
+  match config.iteration_storage {
+    IterationStorage::Smooth => words(bytes).map(f32::from_bits).collect(),
+    IterationStorage::Integer => words(bytes)
+      .flat_map(|word| [word & 0xffff, word >> 16])
+      .take(config.size.0 as usize * config.size.1 as usize)
+      .map(|count| match count {
+        INTEGER_INTERIOR => -1.,
+        INTEGER_NON_FINITE => -2.,
+        count => count as f32,
+      })
+      .collect(),
+  }
+}
+
+/// The buffers a readback copies from, and the device it copies them with.
+#[derive(SystemParam)]
+struct ReadbackSource<'w> {
+  pipeline: Res<'w, MandelbrotPipeline>,
+  render_device: Res<'w, RenderDevice>,
+  render_queue: Res<'w, RenderQueue>,
+}
+
+fn read_back(
+  source: ReadbackSource,
+  request: Res<ReadbackRequest>,
+  complete: Option<Res<FrameComplete>>,
+  dispatched: Res<DispatchedUniforms>,
+  sender: Res<ReadbackSender>,
+  mut handled: Local<u32>,
+) {
+  if request.requested == *handled || complete.is_none() {
+    return;
+  }
+  // The data was iterated under the uniforms of the dispatch, which lag the
+  // extracted ones by a frame.
+  let Some(uniforms) = dispatched.current else {
+    return;
+  };
+
+  let ReadbackSource { pipeline, render_device, render_queue } = source;
+  let buffers = read_buffer(&render_device, &render_queue, &pipeline.iterations_buffer).and_then(
+    |iterations| {
+      let distances = read_buffer(&render_device, &render_queue, &pipeline.distances_buffer)?;
+      Ok((iterations, distances))
+    },
+  );
+  // A failed readback is retried with the next frame.
+  let (iterations, distances) = match buffers {
+    Ok(buffers) => buffers,
+    Err(err) => {
+      error!("Reading back the frame: {err}");
+      return;
+    }
+  };
+  *handled = request.requested;
+  let readback = Readback {
+    request: request.requested,
+    uniforms,
+    iterations,
+    distances,
+  };
+  // The receiver lives as long as the app.
+  let _ = sender.0.send(readback);
+}
+
+/// Copies `buffer` into a staging buffer and waits for its contents.
+pub(crate) fn read_buffer(
+  render_device: &RenderDevice,
+  render_queue: &RenderQueue,
+  buffer: &Buffer,
+) -> Result<Vec<u8>, MandelbrotError> {
+  let staging = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Readback"),
+    size: buffer.size(),
+    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  });
+  let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor::default());
+  encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+  render_queue.submit([encoder.finish()]);
+
+  let slice = staging.slice(..);
+  let (sender, receiver) = crossbeam_channel::bounded(1);
+  slice.map_async(MapMode::Read, move |r| {
+    let _ = sender.send(r);
+  });
+  render_device
+    .poll(PollType::Wait)
+    .map_err(|err| MandelbrotError::Poll(err.to_string()))?;
+  // The callback has run once the wait returns.
+  receiver
+    .try_recv()
+    .map_err(|err| MandelbrotError::BufferMap(err.to_string()))?
+    .map_err(|err| MandelbrotError::BufferMap(err.to_string()))?;
+  let data = slice.get_mapped_range().to_vec();
+  staging.unmap();
+  Ok(data)
+}
@@ -0,0 +1,51 @@
+//! Pausing the compute passes while the primary window is unfocused.
+//!
+//! The view can't change without input, so re-rendering it in a background
+//! window only burns power. The texture keeps the last frame while paused,
+//! and the first frame after focus returns renders it again.
+//!
+//! A held frame (see the `hold` module) pauses them for good, focused or
+//! not.
+
+use bevy::{
+  app::{App, Plugin, PostUpdate},
+  ecs::{
+    query::With,
+    resource::Resource,
+    system::{Res, ResMut, Single},
+  },
+  render::extract_resource::{ExtractResource, ExtractResourcePlugin},
+  window::{PrimaryWindow, Window},
+};
+
+use crate::{RenderConfig, export::ReadbackRequest, hold::Held};
+
+/// Whether the compute passes are dispatched this frame.
+#[derive(Resource, Clone, Copy, ExtractResource)]
+pub(crate) struct RenderActive(pub bool);
+
+pub(crate) struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .insert_resource(RenderActive(true))
+      .add_plugins(ExtractResourcePlugin::<RenderActive>::default())
+      .add_systems(PostUpdate, track_focus);
+  }
+}
+
+fn track_focus(
+  window: Single<&Window, With<PrimaryWindow>>,
+  config: Res<RenderConfig>,
+  request: Res<ReadbackRequest>,
+  held: Res<Held>,
+  mut active: ResMut<RenderActive>,
+) {
+  // A pending readback, like an export's, needs a fresh frame even if the
+  // window never gets focus.
+  let active_now = !held.0 && (window.focused || !config.pause_unfocused || request.pending());
+  if active.0 != active_now {
+    active.0 = active_now;
+  }
+}
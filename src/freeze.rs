@@ -0,0 +1,68 @@
+//! Freezing the iteration data to work on coloring alone.
+//!
+//! While frozen, the view is pinned to where it was when
+//! [`Action::ToggleFreeze`] was pressed, and the compute node skips the
+//! iterate pass, keeping the iteration and distance buffers of the pinned
+//! view. Coloring controls stay live and only rerun the recolor pass, so they
+//! respond instantly however expensive the view is to iterate. Unfreezing
+//! releases the view and iterates again.
+
+use bevy::{
+  app::{App, Plugin, PostUpdate, Update},
+  ecs::{
+    resource::Resource,
+    system::{Local, Res, ResMut},
+  },
+  log::info,
+  render::extract_resource::{ExtractResource, ExtractResourcePlugin},
+};
+
+use crate::{
+  Uniforms,
+  keybinds::{Action, ActionInput},
+};
+
+/// Whether the iteration data is frozen.
+#[derive(Resource, Clone, Copy, Default, ExtractResource)]
+pub(crate) struct Frozen(pub bool);
+
+pub(crate) struct FreezePlugin;
+
+impl Plugin for FreezePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Frozen>()
+      .add_plugins(ExtractResourcePlugin::<Frozen>::default())
+      .add_systems(Update, toggle_freeze)
+      .add_systems(PostUpdate, pin_iteration);
+  }
+}
+
+fn toggle_freeze(input: ActionInput, mut frozen: ResMut<Frozen>) {
+  if input.just_pressed(Action::ToggleFreeze) {
+    frozen.0 = !frozen.0;
+    if frozen.0 {
+      info!("Iteration data frozen, only coloring can change");
+    } else {
+      info!("Iteration data unfrozen");
+    }
+  }
+}
+
+/// Undoes any change to what the iterate pass depends on while frozen,
+/// whether it came from navigation, a loaded view or stdin.
+fn pin_iteration(
+  frozen: Res<Frozen>,
+  mut pinned: Local<Option<Uniforms>>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  if !frozen.0 {
+    *pinned = None;
+    return;
+  }
+
+  let pinned = *pinned.get_or_insert(*uniforms);
+  if !uniforms.iterates_like(&pinned) {
+    *uniforms = uniforms.with_iteration_of(&pinned);
+  }
+}
@@ -0,0 +1,221 @@
+//! Jumping to typed coordinates.
+//!
+//! [`Action::EnterCoordinates`] opens a one-line prompt at the top of the
+//! view. Typing the real and imaginary parts of a center, optionally followed
+//! by a zoom, and pressing Enter jumps there:
+//!
+//! ```text
+//! -0.743643887037151, 0.131825904205330, 1e5
+//! ```
+//!
+//! Numbers are separated by spaces or commas and may use scientific
+//! notation. Any number of digits is accepted, though the view rounds them
+//! to `f32`. Invalid input leaves the view where it is and says what's
+//! wrong; Escape closes the prompt. While the prompt is open, keys type into
+//! it instead of triggering their shortcuts.
+
+use bevy::{
+  app::{App, Plugin, PreUpdate, Update},
+  color::Color,
+  ecs::{
+    component::Component,
+    entity::Entity,
+    event::EventReader,
+    query::With,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Commands, Query, Res, ResMut},
+  },
+  input::{
+    ButtonInput, ButtonState, InputSystems,
+    keyboard::{Key, KeyCode, KeyboardInput},
+  },
+  log::info,
+  math::Vec2,
+  prelude::{Text2d, TextColor, TextFont},
+  transform::components::Transform,
+};
+
+use crate::{
+  DISPLAY_FACTOR, INITIAL_SCALE, RenderConfig, Uniforms,
+  keybinds::{Action, Keybinds},
+};
+
+/// Font size of the prompt, in screen pixels.
+const PROMPT_SIZE: f32 = 16.;
+/// Distance from the top of the view to the middle of the prompt, in screen
+/// pixels.
+const PROMPT_MARGIN: f32 = 20.;
+
+/// The state of the coordinate prompt.
+#[derive(Resource, Default)]
+struct CoordinateEntry {
+  open: bool,
+  input: String,
+  /// Why the last submitted input was rejected, until it is edited.
+  error: Option<String>,
+}
+
+/// The text showing the prompt.
+#[derive(Component)]
+struct PromptText;
+
+pub(crate) struct GotoPlugin;
+
+impl Plugin for GotoPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<CoordinateEntry>()
+      // Right after input is read, so the keys it swallows are gone before
+      // any shortcut sees them.
+      .add_systems(PreUpdate, edit_entry.after(InputSystems))
+      .add_systems(Update, show_prompt);
+  }
+}
+
+/// A parsed jump target.
+#[derive(Clone, Copy, Debug)]
+struct Jump {
+  center: Vec2,
+  /// Zoom relative to the initial view, if given.
+  zoom: Option<f32>,
+}
+
+impl Jump {
+  fn apply_to(self, uniforms: &mut Uniforms) {
+    uniforms.center = self.center;
+    if let Some(zoom) = self.zoom {
+      uniforms.scale = INITIAL_SCALE / zoom;
+    }
+  }
+}
+
+/// Parses `re im [zoom]`, separated by whitespace or commas.
+fn parse_jump(input: &str) -> Result<Jump, String> {
+  // Parsing straight to f32 rounds long inputs correctly, however many
+  // digits they carry.
+  let numbers = input
+    .split(|c: char| c.is_whitespace() || c == ',')
+    .filter(|word| !word.is_empty())
+    .map(|word| {
+      word
+        .parse::<f32>()
+        .map_err(|_| format!("\"{word}\" is not a number"))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  let (center, zoom) = match numbers[..] {
+    [re, im] => (Vec2::new(re, im), None),
+    [re, im, zoom] => (Vec2::new(re, im), Some(zoom)),
+    _ => {
+      return Err(format!(
+        "expected a real part, an imaginary part and optionally a zoom, got {} numbers",
+        numbers.len()
+      ));
+    }
+  };
+
+  if !center.is_finite() {
+    return Err("the center is out of range".to_owned());
+  }
+  if zoom.is_some_and(|zoom| !(zoom.is_finite() && zoom > 0.)) {
+    return Err("the zoom must be a positive number".to_owned());
+  }
+  Ok(Jump { center, zoom })
+}
+
+fn edit_entry(
+  mut keys: ResMut<ButtonInput<KeyCode>>,
+  keybinds: Res<Keybinds>,
+  mut typed: EventReader<KeyboardInput>,
+  mut entry: ResMut<CoordinateEntry>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  if !entry.open {
+    // The key that opens the prompt isn't typed into it.
+    typed.clear();
+    if keys.just_pressed(keybinds.key(Action::EnterCoordinates)) {
+      *entry = CoordinateEntry { open: true, ..Default::default() };
+      keys.reset_all();
+    }
+    return;
+  }
+
+  for event in typed.read() {
+    if event.state != ButtonState::Pressed {
+      continue;
+    }
+    match &event.logical_key {
+      Key::Enter => match parse_jump(&entry.input) {
+        Ok(jump) => {
+          info!("Jumping to {:?}", jump);
+          jump.apply_to(&mut uniforms);
+          entry.open = false;
+          break;
+        }
+        Err(err) => entry.error = Some(err),
+      },
+      Key::Escape => {
+        entry.open = false;
+        break;
+      }
+      Key::Backspace => {
+        entry.input.pop();
+        entry.error = None;
+      }
+      Key::Space => {
+        entry.input.push(' ');
+        entry.error = None;
+      }
+      Key::Character(text) => {
+        entry.input.push_str(text);
+        entry.error = None;
+      }
+      _ => {}
+    }
+  }
+  keys.reset_all();
+}
+
+fn show_prompt(
+  mut commands: Commands,
+  entry: Res<CoordinateEntry>,
+  config: Res<RenderConfig>,
+  mut prompts: Query<(Entity, &mut Text2d, &mut TextColor), With<PromptText>>,
+) {
+  if !entry.open {
+    for (entity, ..) in &prompts {
+      commands.entity(entity).despawn();
+    }
+    return;
+  }
+  if !entry.is_changed() && !prompts.is_empty() {
+    return;
+  }
+
+  let mut text = format!("Go to (re im [zoom]): {}_", entry.input);
+  let color = match &entry.error {
+    Some(err) => {
+      text += &format!("  ({err})");
+      Color::srgb(1., 0.4, 0.4)
+    }
+    None => Color::WHITE,
+  };
+  match prompts.iter_mut().next() {
+    Some((_, mut prompt, mut prompt_color)) => {
+      prompt.0 = text;
+      prompt_color.0 = color;
+    }
+    None => {
+      // The sprite is centered on the camera, so the top of the view is half
+      // its height up.
+      let top = (config.size.1 * DISPLAY_FACTOR) as f32 / 2.;
+      commands.spawn((
+        PromptText,
+        Text2d::new(text),
+        TextFont::from_font_size(PROMPT_SIZE),
+        TextColor(color),
+        Transform::from_xyz(0., top - PROMPT_MARGIN, 2.),
+      ));
+    }
+  }
+}
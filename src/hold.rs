@@ -0,0 +1,98 @@
+//! Rendering the view once and holding the finished frame.
+//!
+//! With a [`Hold`] resource present, the app renders the view it starts
+//! with, then stops dispatching the compute passes for good, as an
+//! unfocused window does, and the texture keeps showing the finished frame.
+//! Input no longer changes the view, and the app only wakes up for window
+//! events. This suits wallpapers and other set-and-forget displays, where
+//! rendering the same frame over and over only burns power.
+//!
+//! The frame counts as finished once a readback of it arrives, as for the
+//! exports, so under a compute budget the whole view has filled in by then.
+
+use bevy::{
+  app::{App, Plugin, PostUpdate, Update},
+  ecs::{
+    event::EventReader,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Commands, Local, Res, ResMut},
+  },
+  log::info,
+  winit::WinitSettings,
+};
+use bytemuck::bytes_of;
+
+use crate::{
+  Uniforms,
+  export::{self, FrameReadback, ReadbackRequest},
+};
+
+/// Renders the starting view once and holds it while present.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Hold {
+  /// Iteration cap the frame is rendered with. `None` keeps the view's.
+  pub max_iterations: Option<u32>,
+}
+
+/// Whether the frame has finished and is being held.
+#[derive(Resource, Clone, Copy, Default)]
+pub(crate) struct Held(pub bool);
+
+pub(crate) struct HoldPlugin;
+
+impl Plugin for HoldPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Held>()
+      .add_systems(
+        Update,
+        (
+          start_hold.before(export::receive_readbacks),
+          finish_hold.after(export::receive_readbacks),
+        ),
+      )
+      .add_systems(PostUpdate, pin_view);
+  }
+}
+
+fn start_hold(
+  hold: Option<Res<Hold>>,
+  mut request: ResMut<ReadbackRequest>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let Some(hold) = hold.filter(|hold| hold.is_added()) else {
+    return;
+  };
+  if let Some(max_iterations) = hold.max_iterations {
+    uniforms.max_iterations = max_iterations;
+  }
+  request.request();
+}
+
+fn finish_hold(
+  mut commands: Commands,
+  hold: Option<Res<Hold>>,
+  mut readbacks: EventReader<FrameReadback>,
+  mut held: ResMut<Held>,
+) {
+  if readbacks.read().last().is_none() || hold.is_none() || held.0 {
+    return;
+  }
+  held.0 = true;
+  commands.insert_resource(WinitSettings::desktop_app());
+  info!("Frame finished, holding it");
+}
+
+/// Undoes any change to the held view, whether it came from input, a loaded
+/// view or stdin.
+fn pin_view(held: Res<Held>, mut pinned: Local<Option<Uniforms>>, mut uniforms: ResMut<Uniforms>) {
+  if !held.0 {
+    return;
+  }
+
+  let pinned = *pinned.get_or_insert(*uniforms);
+  if bytes_of(&*uniforms) != bytes_of(&pinned) {
+    *uniforms = pinned;
+  }
+}
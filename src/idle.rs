@@ -0,0 +1,82 @@
+//! Capping the frame rate while the view is idle.
+//!
+//! The fractal is static most of the time, yet the app otherwise renders as
+//! fast as vsync allows. With [`RenderConfig::max_idle_fps`] set, once a
+//! frame leaves the view unchanged with no key or button held, the event
+//! loop switches to a reactive update mode that waits up to the capped
+//! frame time for the next frame, and wakes early for any input. Any change
+//! to the view, including inertia, crossfades and stdin updates, restores
+//! the previous update mode on the next frame.
+
+use std::time::Duration;
+
+use bevy::{
+  app::{App, Last, Plugin},
+  ecs::system::{Local, Res, ResMut},
+  input::{ButtonInput, keyboard::KeyCode, mouse::MouseButton},
+  winit::{UpdateMode, WinitSettings},
+};
+use bytemuck::bytes_of;
+
+use crate::{RenderConfig, Uniforms, hold::Held};
+
+pub(crate) struct IdlePlugin;
+
+impl Plugin for IdlePlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(Last, cap_idle_frame_rate);
+  }
+}
+
+/// What the cap keeps between frames.
+#[derive(Default)]
+struct IdleState {
+  /// The view shown last frame.
+  shown: Option<Uniforms>,
+  /// The update mode the cap replaced while the view is idle.
+  replaced: Option<WinitSettings>,
+}
+
+fn cap_idle_frame_rate(
+  config: Res<RenderConfig>,
+  uniforms: Res<Uniforms>,
+  keys: Res<ButtonInput<KeyCode>>,
+  buttons: Res<ButtonInput<MouseButton>>,
+  held: Res<Held>,
+  mut state: Local<IdleState>,
+  mut settings: ResMut<WinitSettings>,
+) {
+  let Some(max_fps) = config.max_idle_fps else {
+    return;
+  };
+  // A held frame sets its own update mode for good.
+  if held.0 {
+    return;
+  }
+
+  // The frame count rises and the history buffers swap every frame without
+  // changing the image, as does the animation clock unless it cycles the
+  // palette.
+  let mut view = *uniforms;
+  if !config.color_cycle {
+    view.time = 0.;
+  }
+  view.history_parity = 0;
+  view.frame = 0;
+  let changed = state
+    .shown
+    .is_none_or(|shown| bytes_of(&shown) != bytes_of(&view));
+  state.shown = Some(view);
+  let idle =
+    !changed && keys.get_pressed().next().is_none() && buttons.get_pressed().next().is_none();
+
+  if !idle {
+    if let Some(replaced) = state.replaced.take() {
+      *settings = replaced;
+    }
+  } else if state.replaced.is_none() {
+    let mode = UpdateMode::reactive_low_power(Duration::from_secs_f32(1. / max_fps));
+    state.replaced = Some(settings.clone());
+    *settings = WinitSettings { focused_mode: mode, unfocused_mode: mode };
+  }
+}
@@ -0,0 +1,121 @@
+//! Stepping the iteration cap.
+//!
+//! The step keys raise or lower `max_iterations` by a fixed increment. A
+//! reveal drops the cap to one step and climbs back to where it was on its
+//! own, showing how the set's detail emerges as iterations are added.
+//!
+//! Lowering the cap iterates the whole frame again. Raising it does too,
+//! unless [`RenderConfig::warm_start`](crate::RenderConfig::warm_start)
+//! keeps the orbits between frames: then only the points still iterating
+//! are iterated further, from where the previous cap stopped them, so each
+//! step of a reveal costs just the iterations it adds.
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    resource::Resource,
+    system::{Res, ResMut},
+  },
+  time::Time,
+};
+
+use crate::{
+  Uniforms,
+  keybinds::{Action, ActionInput},
+};
+
+/// Tuning for iteration stepping and reveals.
+#[derive(Resource, Clone, Debug)]
+pub struct IterationSteps {
+  /// Iterations added or removed per press of a step key, and per step of a
+  /// reveal.
+  pub step: u32,
+  /// Steps per second of a reveal.
+  pub reveal_rate: f32,
+  reveal: Option<Reveal>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Reveal {
+  /// The cap the reveal climbs to.
+  target: u32,
+  /// Fraction of the way to the next step.
+  progress: f32,
+}
+
+impl Default for IterationSteps {
+  fn default() -> Self {
+    Self { step: 50, reveal_rate: 4., reveal: None }
+  }
+}
+
+impl IterationSteps {
+  /// Whether a reveal is climbing.
+  pub fn revealing(&self) -> bool {
+    self.reveal.is_some()
+  }
+}
+
+pub(crate) struct IterationsPlugin;
+
+impl Plugin for IterationsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<IterationSteps>()
+      .add_systems(Update, (step_iterations, advance_reveal));
+  }
+}
+
+fn step_iterations(
+  input: ActionInput,
+  mut steps: ResMut<IterationSteps>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  if input.just_pressed(Action::MoreIterations) {
+    steps.reveal = None;
+    uniforms.max_iterations = uniforms.max_iterations.saturating_add(steps.step);
+  }
+  if input.just_pressed(Action::FewerIterations) {
+    steps.reveal = None;
+    uniforms.max_iterations = uniforms.max_iterations.saturating_sub(steps.step).max(1);
+  }
+  if input.just_pressed(Action::RevealIterations) {
+    // Pressing it again mid-reveal skips to the end.
+    match steps.reveal.take() {
+      Some(reveal) => uniforms.max_iterations = reveal.target,
+      None => {
+        steps.reveal = Some(Reveal {
+          target: uniforms.max_iterations,
+          progress: 0.,
+        });
+        uniforms.max_iterations = steps.step.min(uniforms.max_iterations).max(1);
+      }
+    }
+  }
+}
+
+fn advance_reveal(
+  time: Res<Time>,
+  mut steps: ResMut<IterationSteps>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let step = steps.step;
+  let rate = steps.reveal_rate;
+  let Some(reveal) = &mut steps.reveal else {
+    return;
+  };
+
+  reveal.progress += rate * time.delta_secs();
+  let whole_steps = reveal.progress.floor();
+  if whole_steps < 1. {
+    return;
+  }
+  reveal.progress -= whole_steps;
+  let climbed = uniforms
+    .max_iterations
+    .saturating_add(step.saturating_mul(whole_steps as u32));
+  uniforms.max_iterations = climbed.min(reveal.target);
+  if uniforms.max_iterations == reveal.target {
+    steps.reveal = None;
+  }
+}
@@ -0,0 +1,84 @@
+//! Switching between the Mandelbrot set and its Julia sets.
+//!
+//! Every point of the Mandelbrot set is the constant of a Julia set, so
+//! [`Action::ToggleJulia`] opens the Julia set of the point under the cursor,
+//! or of the center of the view when the cursor is outside the window.
+//! Pressing it again returns to the Mandelbrot view it was opened from.
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    query::With,
+    system::{Local, Res, ResMut, Single},
+  },
+  log::info,
+  math::Vec2,
+  window::{PrimaryWindow, Window},
+};
+
+use crate::{
+  FractalMode, INITIAL_SCALE, RenderConfig, Uniforms,
+  keybinds::{Action, ActionInput},
+};
+
+/// Half-height of the view a Julia set opens with, which fits the whole set.
+const JULIA_SCALE: f32 = 1.5;
+
+/// The part of the uniforms that frames the view.
+#[derive(Clone, Copy)]
+struct Framing {
+  center: Vec2,
+  scale: f32,
+  rotation: f32,
+}
+
+pub(crate) struct JuliaPlugin;
+
+impl Plugin for JuliaPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(Update, toggle_julia);
+  }
+}
+
+fn toggle_julia(
+  input: ActionInput,
+  window: Single<&Window, With<PrimaryWindow>>,
+  config: Res<RenderConfig>,
+  mut mandelbrot_framing: Local<Option<Framing>>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  if !input.just_pressed(Action::ToggleJulia) {
+    return;
+  }
+
+  if uniforms.mode == FractalMode::Julia as u32 {
+    // A Julia view loaded from a file has no Mandelbrot view to go back to,
+    // so frame its constant instead.
+    let framing = mandelbrot_framing.take().unwrap_or(Framing {
+      center: uniforms.julia_c,
+      scale: INITIAL_SCALE,
+      rotation: 0.,
+    });
+    uniforms.mode = FractalMode::Mandelbrot as u32;
+    uniforms.center = framing.center;
+    uniforms.scale = framing.scale;
+    uniforms.rotation = framing.rotation;
+    return;
+  }
+
+  let c = match window.cursor_position() {
+    Some(position) => uniforms.window_to_complex(&config, &window, position),
+    None => uniforms.center,
+  };
+  *mandelbrot_framing = Some(Framing {
+    center: uniforms.center,
+    scale: uniforms.scale,
+    rotation: uniforms.rotation,
+  });
+  uniforms.mode = FractalMode::Julia as u32;
+  uniforms.julia_c = c;
+  uniforms.center = Vec2::ZERO;
+  uniforms.scale = JULIA_SCALE;
+  uniforms.rotation = 0.;
+  info!("Julia set of {} {:+}i", c.x, c.y);
+}
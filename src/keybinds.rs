@@ -0,0 +1,290 @@
+//! Remappable keyboard shortcuts.
+//!
+//! Systems ask whether an [`Action`] was pressed through [`ActionInput`]
+//! instead of checking hardcoded keys, so every shortcut can be remapped from
+//! a `keybinds.toml` file of `action = "KeyCode"` entries, e.g.
+//!
+//! ```toml
+//! cycle_mapping_curve = "KeyN"
+//! ```
+
+use std::{collections::HashMap, fs, path::Path};
+
+use bevy::{
+  ecs::{
+    resource::Resource,
+    system::{Res, SystemParam},
+  },
+  input::{ButtonInput, keyboard::KeyCode},
+  log::warn,
+};
+
+/// Something the user can do with a keyboard shortcut.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+  CycleMappingCurve,
+  ToggleShading,
+  RotateLight,
+  PanLeft,
+  PanRight,
+  PanUp,
+  PanDown,
+  ZoomIn,
+  ZoomOut,
+  RotateLeft,
+  RotateRight,
+  DumpView,
+  SaveView,
+  LoadView,
+  TogglePause,
+  ToggleCrosshair,
+  ToggleBoundary,
+  CycleBackground,
+  StepZoomIn,
+  StepZoomOut,
+  ToggleGlow,
+  ToggleJulia,
+  ToggleFreeze,
+  ToggleGrid,
+  MoreIterations,
+  FewerIterations,
+  RevealIterations,
+  EnterCoordinates,
+  ToggleIterationCue,
+  DumpRenderState,
+  ToggleBulbs,
+  TogglePrecision,
+  MeasureDimension,
+  NextLandmark,
+  ToggleProjection,
+  CycleWorkgroupShape,
+  ToggleOrbitDensity,
+  /// Held to slow navigation down.
+  SlowModifier,
+  /// Held to speed navigation up.
+  FastModifier,
+}
+
+impl Action {
+  const ALL: [Self; 39] = [
+    Self::CycleMappingCurve,
+    Self::ToggleShading,
+    Self::RotateLight,
+    Self::PanLeft,
+    Self::PanRight,
+    Self::PanUp,
+    Self::PanDown,
+    Self::ZoomIn,
+    Self::ZoomOut,
+    Self::RotateLeft,
+    Self::RotateRight,
+    Self::DumpView,
+    Self::SaveView,
+    Self::LoadView,
+    Self::TogglePause,
+    Self::ToggleCrosshair,
+    Self::ToggleBoundary,
+    Self::CycleBackground,
+    Self::StepZoomIn,
+    Self::StepZoomOut,
+    Self::ToggleGlow,
+    Self::ToggleJulia,
+    Self::ToggleFreeze,
+    Self::ToggleGrid,
+    Self::MoreIterations,
+    Self::FewerIterations,
+    Self::RevealIterations,
+    Self::EnterCoordinates,
+    Self::ToggleIterationCue,
+    Self::DumpRenderState,
+    Self::ToggleBulbs,
+    Self::TogglePrecision,
+    Self::MeasureDimension,
+    Self::NextLandmark,
+    Self::ToggleProjection,
+    Self::CycleWorkgroupShape,
+    Self::ToggleOrbitDensity,
+    Self::SlowModifier,
+    Self::FastModifier,
+  ];
+
+  /// The name of the action in `keybinds.toml`.
+  fn name(self) -> &'static str {
+    match self {
+      Self::CycleMappingCurve => "cycle_mapping_curve",
+      Self::ToggleShading => "toggle_shading",
+      Self::RotateLight => "rotate_light",
+      Self::PanLeft => "pan_left",
+      Self::PanRight => "pan_right",
+      Self::PanUp => "pan_up",
+      Self::PanDown => "pan_down",
+      Self::ZoomIn => "zoom_in",
+      Self::ZoomOut => "zoom_out",
+      Self::RotateLeft => "rotate_left",
+      Self::RotateRight => "rotate_right",
+      Self::DumpView => "dump_view",
+      Self::SaveView => "save_view",
+      Self::LoadView => "load_view",
+      Self::TogglePause => "toggle_pause",
+      Self::ToggleCrosshair => "toggle_crosshair",
+      Self::ToggleBoundary => "toggle_boundary",
+      Self::CycleBackground => "cycle_background",
+      Self::StepZoomIn => "step_zoom_in",
+      Self::StepZoomOut => "step_zoom_out",
+      Self::ToggleGlow => "toggle_glow",
+      Self::ToggleJulia => "toggle_julia",
+      Self::ToggleFreeze => "toggle_freeze",
+      Self::ToggleGrid => "toggle_grid",
+      Self::MoreIterations => "more_iterations",
+      Self::FewerIterations => "fewer_iterations",
+      Self::RevealIterations => "reveal_iterations",
+      Self::EnterCoordinates => "enter_coordinates",
+      Self::ToggleIterationCue => "toggle_iteration_cue",
+      Self::DumpRenderState => "dump_render_state",
+      Self::ToggleBulbs => "toggle_bulbs",
+      Self::TogglePrecision => "toggle_precision",
+      Self::MeasureDimension => "measure_dimension",
+      Self::NextLandmark => "next_landmark",
+      Self::ToggleProjection => "toggle_projection",
+      Self::CycleWorkgroupShape => "cycle_workgroup_shape",
+      Self::ToggleOrbitDensity => "toggle_orbit_density",
+      Self::SlowModifier => "slow_modifier",
+      Self::FastModifier => "fast_modifier",
+    }
+  }
+
+  fn default_key(self) -> KeyCode {
+    match self {
+      Self::CycleMappingCurve => KeyCode::KeyM,
+      Self::ToggleShading => KeyCode::KeyL,
+      Self::RotateLight => KeyCode::KeyK,
+      Self::PanLeft => KeyCode::ArrowLeft,
+      Self::PanRight => KeyCode::ArrowRight,
+      Self::PanUp => KeyCode::ArrowUp,
+      Self::PanDown => KeyCode::ArrowDown,
+      Self::ZoomIn => KeyCode::KeyZ,
+      Self::ZoomOut => KeyCode::KeyX,
+      Self::RotateLeft => KeyCode::KeyQ,
+      Self::RotateRight => KeyCode::KeyE,
+      Self::DumpView => KeyCode::KeyU,
+      Self::SaveView => KeyCode::F5,
+      Self::LoadView => KeyCode::F9,
+      Self::TogglePause => KeyCode::Space,
+      Self::ToggleCrosshair => KeyCode::KeyC,
+      Self::ToggleBoundary => KeyCode::KeyB,
+      Self::CycleBackground => KeyCode::KeyG,
+      Self::StepZoomIn => KeyCode::Equal,
+      Self::StepZoomOut => KeyCode::Minus,
+      Self::ToggleGlow => KeyCode::KeyH,
+      Self::ToggleJulia => KeyCode::KeyJ,
+      Self::ToggleFreeze => KeyCode::KeyF,
+      Self::ToggleGrid => KeyCode::KeyR,
+      Self::MoreIterations => KeyCode::BracketRight,
+      Self::FewerIterations => KeyCode::BracketLeft,
+      Self::RevealIterations => KeyCode::KeyI,
+      Self::EnterCoordinates => KeyCode::KeyO,
+      Self::ToggleIterationCue => KeyCode::KeyV,
+      Self::DumpRenderState => KeyCode::F3,
+      Self::ToggleBulbs => KeyCode::KeyP,
+      Self::TogglePrecision => KeyCode::KeyN,
+      Self::MeasureDimension => KeyCode::KeyD,
+      Self::NextLandmark => KeyCode::KeyT,
+      Self::ToggleProjection => KeyCode::KeyY,
+      Self::CycleWorkgroupShape => KeyCode::KeyW,
+      Self::ToggleOrbitDensity => KeyCode::KeyA,
+      Self::SlowModifier => KeyCode::ShiftLeft,
+      Self::FastModifier => KeyCode::ControlLeft,
+    }
+  }
+}
+
+/// The key bound to every [`Action`].
+#[derive(Resource, Clone, Debug)]
+pub struct Keybinds(HashMap<Action, KeyCode>);
+
+impl Default for Keybinds {
+  fn default() -> Self {
+    Self(
+      Action::ALL
+        .into_iter()
+        .map(|action| (action, action.default_key()))
+        .collect(),
+    )
+  }
+}
+
+impl Keybinds {
+  /// Loads keybinds from a TOML file, keeping the default for every action
+  /// the file doesn't mention. A missing file yields the defaults; invalid
+  /// entries are warned about and skipped.
+  pub fn load(path: impl AsRef<Path>) -> Self {
+    let path = path.as_ref();
+    let mut keybinds = Self::default();
+    let Ok(text) = fs::read_to_string(path) else {
+      return keybinds;
+    };
+    let table = match text.parse::<toml::Table>() {
+      Ok(table) => table,
+      Err(err) => {
+        warn!("Ignoring {}: {err}", path.display());
+        return keybinds;
+      }
+    };
+
+    for (name, value) in table {
+      let Some(action) = Action::ALL.into_iter().find(|action| action.name() == name) else {
+        warn!("{}: unknown action \"{name}\"", path.display());
+        continue;
+      };
+      let key: Result<KeyCode, _> = value.try_into();
+      match key {
+        Ok(key) => {
+          keybinds.0.insert(action, key);
+        }
+        Err(err) => warn!("{}: invalid key for \"{name}\": {err}", path.display()),
+      }
+    }
+
+    keybinds.warn_conflicts();
+    keybinds
+  }
+
+  /// The key bound to `action`.
+  pub fn key(&self, action: Action) -> KeyCode {
+    self.0[&action]
+  }
+
+  fn warn_conflicts(&self) {
+    for (i, a) in Action::ALL.iter().enumerate() {
+      for b in &Action::ALL[i + 1..] {
+        if self.key(*a) == self.key(*b) {
+          warn!(
+            "\"{}\" and \"{}\" are both bound to {:?}",
+            a.name(),
+            b.name(),
+            self.key(*a)
+          );
+        }
+      }
+    }
+  }
+}
+
+/// Keyboard state queried by [`Action`] rather than by key.
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+  keys: Res<'w, ButtonInput<KeyCode>>,
+  keybinds: Res<'w, Keybinds>,
+}
+
+impl ActionInput<'_> {
+  /// Whether the key bound to `action` was pressed this frame.
+  pub fn just_pressed(&self, action: Action) -> bool {
+    self.keys.just_pressed(self.keybinds.key(action))
+  }
+
+  /// Whether the key bound to `action` is held down.
+  pub fn pressed(&self, action: Action) -> bool {
+    self.keys.pressed(self.keybinds.key(action))
+  }
+}
@@ -0,0 +1,277 @@
+//! Importing Kalles Fraktaler parameter files.
+//!
+//! `.kfr` files are lines of `Key: value` pairs, and many fractal viewers
+//! read and write them to share locations:
+//!
+//! ```text
+//! Re: -0.743643887037151
+//! Im: 0.131825904205330
+//! Zoom: 1E3
+//! Iterations: 5000
+//! ```
+//!
+//! The center, zoom, iteration cap and rotation are imported. The center
+//! may carry far more digits than an `f32`, and is rounded to the nearest
+//! one, as views are rendered in `f32` alone. Deep locations, the ones Kalles
+//! Fraktaler is usually used for, can't be reproduced: a zoom past what `f32`
+//! resolves around the center fails the import rather than opening a
+//! shallower view. Fields that change the fractal itself, like a power other than 2,
+//! can't be rendered and fail the import. Fields known to only describe how
+//! Kalles Fraktaler colors and computes the image don't change the location
+//! and are skipped, with a note saying which. Any other field fails the
+//! import, since there's no telling what it would have changed.
+
+use std::{f64::consts::PI, fmt};
+
+use bevy::{log::info, math::Vec2};
+
+use crate::{RenderConfig, Uniforms, view::View};
+
+/// Fields whose value changes the fractal, with the only value that can be
+/// rendered.
+const FIXED_FIELDS: [(&str, f64); 3] = [
+  ("Power", 2.),
+  ("FractalType", 0.),
+  // Degrees of skew, 360 for none.
+  ("Ratio", 360.),
+];
+
+/// Fields that only describe how Kalles Fraktaler colors, textures or
+/// computes the image, or how its window was laid out.
+const COSMETIC_FIELDS: [&str; 36] = [
+  "ApproxLowTolerance",
+  "ApproxTerms",
+  "AutoApproxTerms",
+  "BlendMC",
+  "ColorMethod",
+  "ColorOffset",
+  "ColorPhaseStrength",
+  "Colors",
+  "Differences",
+  "GlitchLowTolerance",
+  "InteriorChecking",
+  "InteriorColor",
+  "IterDiv",
+  "MaxReferences",
+  "MultiColor",
+  "MultiColors",
+  "Period",
+  "RadiusScale",
+  "Slopes",
+  "SlopeAngle",
+  "SlopePower",
+  "SlopeRatio",
+  "Smooth",
+  "SmoothMethod",
+  "TextureEnabled",
+  "TextureFile",
+  "TextureMerge",
+  "TexturePower",
+  "TextureRatio",
+  "UseNanoMB1",
+  "UseNanoMB2",
+  "Version",
+  "WindowBottom",
+  "WindowHeight",
+  "WindowWidth",
+  "ZoomSize",
+];
+
+/// Why a parameter file couldn't be imported.
+#[derive(Debug)]
+pub enum KfrError {
+  /// A line isn't a `Key: value` pair.
+  Malformed(String),
+  /// A field the location needs is missing.
+  MissingField(&'static str),
+  InvalidValue {
+    field: String,
+    value: String,
+  },
+  /// The zoom is deeper than `f32` resolves around the center, which allows
+  /// at most `max`.
+  TooDeep {
+    zoom: f64,
+    max: f64,
+  },
+  /// A field has a value that changes the fractal in a way that can't be
+  /// rendered, or isn't a field this importer knows.
+  Unsupported {
+    field: String,
+    value: String,
+  },
+}
+
+impl fmt::Display for KfrError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Malformed(line) => write!(f, "expected `Key: value`, got \"{line}\""),
+      Self::MissingField(field) => write!(f, "missing the `{field}` field"),
+      Self::InvalidValue { field, value } => {
+        write!(f, "`{field}` has the invalid value \"{value}\"")
+      }
+      Self::TooDeep { zoom, max } => {
+        write!(f, "zoom {zoom:e} is past the {max:e} f32 resolves here")
+      }
+      Self::Unsupported { field, value } => {
+        write!(f, "`{field}: {value}` isn't supported")
+      }
+    }
+  }
+}
+
+impl std::error::Error for KfrError {}
+
+/// Parses the value of `field` as a number.
+fn number(field: &str, value: &str) -> Result<f64, KfrError> {
+  value.parse().map_err(|_| KfrError::InvalidValue {
+    field: field.to_owned(),
+    value: value.to_owned(),
+  })
+}
+
+/// Reads the view a parameter file describes, on top of `base` for
+/// everything the file doesn't cover, to be rendered under `config`.
+pub fn parse_kfr(text: &str, base: View, config: &RenderConfig) -> Result<View, KfrError> {
+  let (mut re, mut im, mut zoom) = (None, None, None);
+  let mut view = View { julia_c: None, ..base };
+  let mut skipped = Vec::new();
+
+  for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+    let (field, value) = line
+      .split_once(':')
+      .ok_or_else(|| KfrError::Malformed(line.to_owned()))?;
+    let (field, value) = (field.trim(), value.trim());
+    match field {
+      "Re" => re = Some(number(field, value)?),
+      "Im" => im = Some(number(field, value)?),
+      "Zoom" => zoom = Some(number(field, value)?),
+      "Iterations" => {
+        view.max_iterations = value.parse().map_err(|_| KfrError::InvalidValue {
+          field: field.to_owned(),
+          value: value.to_owned(),
+        })?;
+      }
+      "Rotate" => view.rotation = (number(field, value)? * PI / 180.) as f32,
+      _ => match FIXED_FIELDS.iter().find(|(name, _)| *name == field) {
+        Some(&(_, supported)) => {
+          if number(field, value)? != supported {
+            return Err(KfrError::Unsupported {
+              field: field.to_owned(),
+              value: value.to_owned(),
+            });
+          }
+        }
+        None if COSMETIC_FIELDS.contains(&field) => skipped.push(field),
+        None => {
+          return Err(KfrError::Unsupported {
+            field: field.to_owned(),
+            value: value.to_owned(),
+          });
+        }
+      },
+    }
+  }
+
+  let re = re.ok_or(KfrError::MissingField("Re"))?;
+  let im = im.ok_or(KfrError::MissingField("Im"))?;
+  let zoom = zoom.ok_or(KfrError::MissingField("Zoom"))?;
+  // Kalles Fraktaler's zoom 1 spans 2 units either side of the center.
+  let scale = (2. / zoom) as f32;
+  if !(scale.is_normal() && scale > 0.) {
+    return Err(KfrError::Unsupported {
+      field: "Zoom".to_owned(),
+      value: zoom.to_string(),
+    });
+  }
+  view.center = Vec2::new(re as f32, im as f32);
+  view.scale = scale;
+  let mut uniforms = Uniforms::new(config);
+  view.apply_to(&mut uniforms);
+  let min_scale = uniforms.min_scale(config);
+  if scale < min_scale {
+    return Err(KfrError::TooDeep { zoom, max: 2. / min_scale as f64 });
+  }
+
+  if !skipped.is_empty() {
+    info!(
+      "Skipped parameters that don't affect the location: {}",
+      skipped.join(", ")
+    );
+  }
+  Ok(view)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EXAMPLE: &str = "\
+Re: -0.743643887037151
+Im: 0.131825904205330
+Zoom: 1E3
+Iterations: 5000
+";
+
+  #[test]
+  fn imports_example() {
+    let view = parse_kfr(EXAMPLE, View::default(), &RenderConfig::default()).unwrap();
+    assert_eq!(
+      view.center,
+      Vec2::new(-0.743643887037151, 0.131825904205330)
+    );
+    assert_eq!(view.scale, 2e-3);
+    assert_eq!(view.max_iterations, 5000);
+    assert_eq!(view.rotation, View::default().rotation);
+  }
+
+  #[test]
+  fn skips_cosmetic_fields() {
+    let text = format!("{EXAMPLE}ColorMethod: 7\nSmooth: 1\n");
+    assert!(parse_kfr(&text, View::default(), &RenderConfig::default()).is_ok());
+  }
+
+  #[test]
+  fn rejects_unknown_fields() {
+    let text = format!("{EXAMPLE}SeedR: 0.25\n");
+    let err = parse_kfr(&text, View::default(), &RenderConfig::default()).unwrap_err();
+    assert!(matches!(err, KfrError::Unsupported { field, .. } if field == "SeedR"));
+  }
+
+  #[test]
+  fn rejects_missing_re() {
+    let text = "Im: 0.1\nZoom: 1\n";
+    let err = parse_kfr(text, View::default(), &RenderConfig::default()).unwrap_err();
+    assert!(matches!(err, KfrError::MissingField("Re")));
+  }
+
+  #[test]
+  fn rejects_other_powers() {
+    let text = format!("{EXAMPLE}Power: 3\n");
+    let err = parse_kfr(&text, View::default(), &RenderConfig::default()).unwrap_err();
+    assert!(
+      matches!(err, KfrError::Unsupported { field, value } if field == "Power" && value == "3")
+    );
+  }
+
+  #[test]
+  fn rejects_unparsable_zoom() {
+    let text = "Re: 0\nIm: 0\nZoom: deep\n";
+    let err = parse_kfr(text, View::default(), &RenderConfig::default()).unwrap_err();
+    assert!(matches!(err, KfrError::InvalidValue { field, .. } if field == "Zoom"));
+  }
+
+  #[test]
+  fn rejects_zoom_past_precision() {
+    let text = "Re: -0.75\nIm: 0.1\nZoom: 1E20\n";
+    let err = parse_kfr(text, View::default(), &RenderConfig::default()).unwrap_err();
+    assert!(matches!(err, KfrError::TooDeep { zoom, .. } if zoom == 1e20));
+  }
+
+  #[test]
+  fn rejects_zoom_past_f32() {
+    let text = "Re: 0\nIm: 0\nZoom: 1E50\n";
+    let err = parse_kfr(text, View::default(), &RenderConfig::default()).unwrap_err();
+    assert!(matches!(err, KfrError::Unsupported { field, .. } if field == "Zoom"));
+  }
+}
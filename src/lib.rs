@@ -0,0 +1,2537 @@
+pub mod autocenter;
+pub mod autoiterations;
+mod control;
+mod diagnostics;
+pub mod dimension;
+pub mod error;
+pub mod export;
+mod focus;
+mod freeze;
+mod goto;
+pub mod hold;
+mod idle;
+pub mod iterations;
+mod julia;
+pub mod keybinds;
+pub mod kfr;
+pub mod mesh;
+pub mod navigation;
+mod orbits;
+pub mod overlay;
+pub mod palette;
+pub mod precision;
+pub mod screensaver;
+mod status;
+mod temporal;
+pub mod tour;
+pub mod transfer;
+pub mod transition;
+pub mod view;
+pub mod workgroups;
+
+use std::{
+  borrow::Cow,
+  collections::HashMap,
+  f32::consts::{FRAC_PI_4, PI, TAU},
+  sync::atomic::{AtomicBool, Ordering},
+};
+
+use bevy::{
+  app::{App, Plugin, PostUpdate, PreStartup, Update},
+  asset::{AssetEvent, AssetServer, Assets, Handle, RenderAssetUsages},
+  color::{ColorToComponents, LinearRgba},
+  ecs::{
+    event::EventReader,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Commands, Local, Res, ResMut},
+    world::World,
+  },
+  image::{Image, ImageSampler},
+  log::{error, info, info_span, warn},
+  math::{Vec2, Vec3, Vec4},
+  prelude::default,
+  render::{
+    Render, RenderApp, RenderStartup, RenderSystems,
+    extract_resource::{ExtractResource, ExtractResourcePlugin},
+    render_asset::RenderAssets,
+    render_graph::{self, RenderGraph, RenderLabel},
+    render_resource::{
+      BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Buffer,
+      BufferDescriptor, BufferInitDescriptor, BufferUsages, CachedComputePipelineId,
+      CachedPipelineState, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor,
+      MapMode, PipelineCache, PollType, ShaderStages, ShaderType, StorageTextureAccess,
+      TextureFormat, TextureSampleType, TextureUsages, TextureViewDescriptor, TextureViewDimension,
+      binding_types::{
+        storage_buffer_read_only_sized, storage_buffer_sized, texture_2d, texture_storage_2d,
+        texture_storage_2d_array, uniform_buffer,
+      },
+    },
+    renderer::{RenderAdapter, RenderAdapterInfo, RenderContext, RenderDevice, RenderQueue},
+    texture::GpuImage,
+  },
+  shader::{PipelineCacheError, Shader, ShaderDefVal, Source},
+  time::Time,
+  window::Window,
+};
+use bytemuck::{Pod, Zeroable, bytes_of, cast_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  autocenter::AutoCenterPlugin,
+  autoiterations::AutoIterationsPlugin,
+  control::ControlPlugin,
+  diagnostics::DiagnosticsPlugin,
+  dimension::DimensionPlugin,
+  error::{ErrorPlugin, FailureSender, MandelbrotError},
+  export::{ExportPlugin, ExrExport, FrameComplete},
+  focus::{FocusPlugin, RenderActive},
+  freeze::{FreezePlugin, Frozen},
+  goto::GotoPlugin,
+  hold::HoldPlugin,
+  idle::IdlePlugin,
+  iterations::IterationsPlugin,
+  julia::JuliaPlugin,
+  keybinds::{Action, ActionInput, Keybinds},
+  mesh::MeshExportPlugin,
+  navigation::NavigationPlugin,
+  orbits::{MAX_ORBIT_SEEDS, ORBIT_WORKGROUPS_PER_SEED, OrbitProgress, OrbitSeeds, OrbitsPlugin},
+  overlay::OverlayPlugin,
+  palette::{MAX_SKY_END, Palette, PalettePlugin, Sky},
+  precision::PrecisionPlugin,
+  screensaver::ScreensaverPlugin,
+  status::StatusPlugin,
+  temporal::TemporalPlugin,
+  tour::TourPlugin,
+  transfer::{MAX_TRANSFER_OPS, Transfer, TransferOp, TransferPlugin},
+  transition::TransitionPlugin,
+  view::{View, ViewPlugin},
+  workgroups::{GpuTimer, WorkgroupShape, WorkgroupsPlugin},
+};
+
+/// Default compute shader, relative to the asset folder.
+const SHADER_ASSET_PATH: &str = "mandelbrot.wgsl";
+/// Entry points the compute passes dispatch, which a custom shader has to
+/// define along with the bind group layout of the default one.
+const ENTRY_POINTS: [&str; 2] = ["iterate", "recolor"];
+/// Entry point of the detail pass, required with
+/// [`RenderConfig::detail_pass`].
+const DETAIL_ENTRY_POINT: &str = "detail";
+/// Entry point of the pass tracing the orbit density layer, required once
+/// it's turned on. See the `orbits` module.
+const ORBITS_ENTRY_POINT: &str = "orbits";
+/// Indirect dispatch arguments of the detail pass as reset before each
+/// iterated frame: no workgroups in x, one in y and z, and no pixels listed.
+const DETAIL_ARGS_RESET: [u32; 4] = [0, 1, 1, 0];
+
+/// Asset path of the compute shader.
+#[derive(Resource, Clone)]
+struct ShaderPath(String);
+
+/// How many screen pixels each texel of the compute texture covers.
+pub const DISPLAY_FACTOR: u32 = 1;
+/// Format of the compute texture, on adapters that can write it from a
+/// compute shader.
+pub const TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
+/// Formats the compute texture falls back to, in order, on adapters that
+/// can't bind [`TEXTURE_FORMAT`] as a storage texture. Each keeps less of
+/// the color's precision than the one before.
+const FALLBACK_TEXTURE_FORMATS: [TextureFormat; 2] =
+  [TextureFormat::Rgba16Float, TextureFormat::Rgba8Unorm];
+/// Largest supported workgroup side. WebGPU only guarantees 256 invocations
+/// per workgroup.
+const MAX_WORKGROUP_SIZE: u32 = 16;
+/// Largest supported compute budget, the workgroups WebGPU guarantees in one
+/// dimension of a dispatch.
+const MAX_DISPATCH_WORKGROUPS: u32 = 65535;
+
+/// How per-pixel iteration results are kept between the iterate and recolor
+/// passes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IterationStorage {
+  /// Smooth (fractional) iteration values, one `f32` per pixel.
+  #[default]
+  Smooth = 0,
+  /// Raw integer iteration counts, one `u16` per pixel packed two to a word.
+  /// Halves the memory of [`IterationStorage::Smooth`], but can't produce
+  /// [`ColoringMode::Smooth`] images.
+  Integer = 1,
+}
+
+impl IterationStorage {
+  /// Size in bytes of the iteration buffer for `pixels` pixels.
+  fn buffer_size(self, pixels: u64) -> u64 {
+    match self {
+      Self::Smooth => pixels * 4,
+      Self::Integer => pixels.div_ceil(2) * 4,
+    }
+  }
+}
+
+/// How iteration values are mapped to colors by the recolor pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColoringMode {
+  /// Continuous coloring from the fractional escape iteration.
+  #[default]
+  Smooth = 0,
+  /// Discrete bands, one per whole iteration.
+  Banded = 1,
+  /// Hue from the angle of `z` where the orbit escaped, value from the
+  /// escape iteration. The exterior splits into lobes along each band,
+  /// the decomposition look.
+  Angle = 2,
+  /// No colors, but the distance estimate of each pixel for use as a
+  /// distance field elsewhere. Red holds the estimated distance to the set
+  /// in complex-plane units and green the same distance in texels. Both are
+  /// unsigned and 0 inside the set, which alpha tells apart from the
+  /// boundary: it is 1 outside the set and 0 inside. Needs a float texture
+  /// format to be useful, see [`MandelbrotImages::format`].
+  Distance = 3,
+  /// Hue from the direction of the orbit's average position before it
+  /// escaped, saturation from its distance from 0, and value from the
+  /// escape iteration. The average varies smoothly across escape bands, so
+  /// colors flow along the filaments instead of banding around the set.
+  Average = 4,
+}
+
+/// How the second fractal is composited over the view when blending. See
+/// [`RenderConfig::blend`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+  /// Sum of the colors, which brightens.
+  #[default]
+  Add = 0,
+  /// Product of the colors, which darkens.
+  Multiply = 1,
+  /// Inverse product of the inverted colors, which brightens without
+  /// clipping as quickly as [`BlendMode::Add`].
+  Screen = 2,
+}
+
+/// How points whose orbit overflowed to infinity or NaN are treated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFinite {
+  /// Color them like points inside the set.
+  #[default]
+  Interior = 0,
+  /// Color them as escaping at the iteration the overflow happened.
+  Escaped = 1,
+  /// Paint them with [`RenderConfig::non_finite_color`], to find them when
+  /// debugging.
+  Highlight = 2,
+}
+
+/// How the alpha channel of the compute texture is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+  /// Every texel is fully opaque.
+  #[default]
+  Opaque = 0,
+  /// The interior of the set is transparent; color is not premultiplied.
+  /// This is what the sprite the binary draws expects.
+  Straight = 1,
+  /// The interior of the set is transparent and color is premultiplied by
+  /// alpha, for host materials that blend premultiplied textures.
+  Premultiplied = 2,
+}
+
+/// Filter used when the compute texture is sampled for display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayFilter {
+  /// Blocky magnification, each texel drawn as a solid square.
+  Nearest,
+  /// Bilinear filtering. Smoother when the sprite is scaled to a size other
+  /// than the texture's.
+  #[default]
+  Linear,
+}
+
+impl DisplayFilter {
+  fn sampler(self) -> ImageSampler {
+    match self {
+      Self::Nearest => ImageSampler::nearest(),
+      Self::Linear => ImageSampler::linear(),
+    }
+  }
+}
+
+/// Order in which workgroups are assigned to tiles of the texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DispatchOrder {
+  /// Row by row.
+  #[default]
+  Linear = 0,
+  /// Along a Z-order curve, which keeps consecutive workgroups on nearby
+  /// tiles. The dispatch is padded to a power-of-two square of tiles, and the
+  /// padding workgroups exit immediately.
+  Morton = 1,
+}
+
+/// Static configuration of the compute pipeline, fixed when the plugin is
+/// built.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct RenderConfig {
+  /// Dimensions of the compute texture, in texels.
+  pub size: (u32, u32),
+  /// Width of a texel relative to its height on the display the output is
+  /// meant for. Values other than 1 render an anamorphic image, which looks
+  /// correctly proportioned once stretched by the target.
+  pub pixel_aspect: f32,
+  /// Side of the square workgroups the compute passes are dispatched in, at
+  /// most 16. [`Action::CycleWorkgroupShape`] tries other shapes at runtime.
+  pub workgroup_size: u32,
+  /// Representation of the iteration buffer.
+  pub iteration_storage: IterationStorage,
+  /// Coloring applied by the recolor pass.
+  pub coloring_mode: ColoringMode,
+  /// Treatment of orbits that overflow.
+  pub non_finite: NonFinite,
+  /// Color of overflowing points under [`NonFinite::Highlight`].
+  pub non_finite_color: LinearRgba,
+  /// Alpha convention of the compute texture. Materials sampling
+  /// [`MandelbrotImages::texture`] should blend accordingly.
+  pub alpha_mode: AlphaMode,
+  /// Sampler filter of [`MandelbrotImages::texture`].
+  pub display_filter: DisplayFilter,
+  /// Assignment of workgroups to tiles of the texture.
+  pub dispatch_order: DispatchOrder,
+  /// Iterate only the upper half of views of the Mandelbrot set that are
+  /// symmetric about the real axis, and mirror it into the lower half.
+  pub symmetry: bool,
+  /// Stop the iterate pass at a quarter of the iteration cap and finish the
+  /// points still iterating in a second pass, dispatched indirectly over a
+  /// list of just those pixels. Pays off in views with a lot of interior,
+  /// where most invocations of a uniform dispatch would idle.
+  pub detail_pass: bool,
+  /// Cap on the workgroups the iterate pass dispatches per frame. A view
+  /// then fills in over several frames, in dispatch order, and starts over
+  /// when it changes, which bounds the GPU time of a frame however deep the
+  /// view iterates. `None` iterates the whole view every frame.
+  pub compute_budget: Option<u32>,
+  /// Keep each pixel's orbit between frames, so a view whose iteration cap
+  /// only grew resumes the orbits still iterating where they stopped, and
+  /// skips the ones that escaped. Costs 32 bytes per pixel.
+  pub warm_start: bool,
+  /// Cycle the palette along the escape iterations with the
+  /// [`AnimationClock`], which [`Action::TogglePause`] holds on a frame.
+  pub color_cycle: bool,
+  /// Stop dispatching the compute passes while the primary window is
+  /// unfocused.
+  pub pause_unfocused: bool,
+  /// Frame rate cap while the view is idle. `None` leaves idle frames
+  /// uncapped.
+  pub max_idle_fps: Option<f32>,
+  /// Apply JSON view updates read from stdin, one per line.
+  pub stdin_control: bool,
+  /// Color escaped points near the set blend towards when the glow is on.
+  /// Alpha scales the strength of the glow.
+  pub glow_color: LinearRgba,
+  /// Distance from the set at which the glow has faded to about a third, in
+  /// texels.
+  pub glow_radius: f32,
+  /// Weight of the previous frame in each panned frame, from 0 (off) to
+  /// below 1. See the `temporal` module.
+  pub temporal_blend: f32,
+  /// How far escaped points the iteration cap cut short are desaturated
+  /// when the iteration cue is on, from 0 to 1.
+  pub iteration_cue: f32,
+  /// Also iterate the Julia set of the view's constant, or the Mandelbrot
+  /// set in Julia views, and composite its colors over the view's. `None`
+  /// renders a single fractal.
+  pub blend: Option<BlendMode>,
+  /// Weight of the blended fractal, from 0 (invisible) to 1.
+  pub blend_mix: f32,
+  /// Projection the view starts in. [`Action::ToggleProjection`] switches
+  /// it at runtime.
+  pub projection: Projection,
+  /// Colors of the far exterior the view starts with, set apart from the
+  /// palette. `None` maps the whole range onto the palette.
+  pub sky: Option<Sky>,
+  /// Render a view per eye into the two layers of a texture array, the left
+  /// eye's first, each offset sideways by half this separation between the
+  /// eyes, in view heights. `None` renders a single view. See
+  /// [`MandelbrotImages::texture`].
+  pub stereo: Option<f32>,
+}
+
+impl Default for RenderConfig {
+  fn default() -> Self {
+    Self {
+      size: (1280 / DISPLAY_FACTOR, 720 / DISPLAY_FACTOR),
+      pixel_aspect: 1.,
+      workgroup_size: 8,
+      iteration_storage: IterationStorage::default(),
+      coloring_mode: ColoringMode::default(),
+      non_finite: NonFinite::default(),
+      non_finite_color: LinearRgba::rgb(1., 0., 1.),
+      alpha_mode: AlphaMode::default(),
+      display_filter: DisplayFilter::default(),
+      dispatch_order: DispatchOrder::default(),
+      symmetry: true,
+      detail_pass: false,
+      compute_budget: None,
+      warm_start: false,
+      color_cycle: false,
+      pause_unfocused: true,
+      max_idle_fps: None,
+      stdin_control: false,
+      glow_color: LinearRgba::rgb(1., 0.7, 0.3),
+      glow_radius: 8.,
+      temporal_blend: 0.,
+      iteration_cue: 0.6,
+      blend: None,
+      blend_mix: 0.5,
+      projection: Projection::default(),
+      sky: None,
+      stereo: None,
+    }
+  }
+}
+
+impl RenderConfig {
+  /// Resolves combinations of options that can't be honored together.
+  fn validated(mut self) -> Self {
+    if self.iteration_storage == IterationStorage::Integer
+      && self.coloring_mode == ColoringMode::Smooth
+    {
+      warn!("Smooth coloring needs smooth iteration storage, falling back to banded coloring");
+      self.coloring_mode = ColoringMode::Banded;
+    }
+    if !(self.pixel_aspect.is_finite() && self.pixel_aspect > 0.) {
+      warn!("Pixel aspect {} is invalid, using 1", self.pixel_aspect);
+      self.pixel_aspect = 1.;
+    }
+    if !(1..=MAX_WORKGROUP_SIZE).contains(&self.workgroup_size) {
+      let workgroup_size = self.workgroup_size.clamp(1, MAX_WORKGROUP_SIZE);
+      warn!(
+        "Workgroup size {} is unsupported, using {workgroup_size}",
+        self.workgroup_size
+      );
+      self.workgroup_size = workgroup_size;
+    }
+    if !(0. ..MAX_TEMPORAL_BLEND).contains(&self.temporal_blend) {
+      let temporal_blend = self.temporal_blend.clamp(0., MAX_TEMPORAL_BLEND);
+      warn!(
+        "Temporal blend {} is out of range, using {temporal_blend}",
+        self.temporal_blend
+      );
+      self.temporal_blend = temporal_blend;
+    }
+    if !(0. ..=1.).contains(&self.iteration_cue) {
+      let iteration_cue = self.iteration_cue.clamp(0., 1.);
+      warn!(
+        "Iteration cue strength {} is out of range, using {iteration_cue}",
+        self.iteration_cue
+      );
+      self.iteration_cue = iteration_cue;
+    }
+    if !(0. ..=1.).contains(&self.blend_mix) {
+      let blend_mix = self.blend_mix.clamp(0., 1.);
+      warn!(
+        "Blend mix {} is out of range, using {blend_mix}",
+        self.blend_mix
+      );
+      self.blend_mix = blend_mix;
+    }
+    if let Some(budget) = self.compute_budget
+      && !(1..=MAX_DISPATCH_WORKGROUPS).contains(&budget)
+    {
+      let clamped = budget.clamp(1, MAX_DISPATCH_WORKGROUPS);
+      warn!("Compute budget of {budget} workgroups is unsupported, using {clamped}");
+      self.compute_budget = Some(clamped);
+    }
+    if let Some(sky) = &mut self.sky
+      && !(sky.end > 0. && sky.end <= MAX_SKY_END)
+    {
+      let end = if sky.end > 0. { MAX_SKY_END } else { 0. };
+      warn!("Sky end {} is out of range, using {end}", sky.end);
+      sky.end = end;
+    }
+    if let Some(max_fps) = self.max_idle_fps
+      && !(max_fps.is_finite() && max_fps > 0.)
+    {
+      warn!("Idle frame rate cap {max_fps} is invalid, leaving idle frames uncapped");
+      self.max_idle_fps = None;
+    }
+    if let Some(separation) = self.stereo
+      && !(separation.is_finite() && separation >= 0.)
+    {
+      warn!("Eye separation {separation} is invalid, rendering a single view");
+      self.stereo = None;
+    }
+    if self.stereo.is_some() {
+      if self.temporal_blend > 0. {
+        warn!("Temporal blending can't follow stereo views, turning it off");
+        self.temporal_blend = 0.;
+      }
+      if self.compute_budget.is_some() {
+        warn!("A compute budget can't split stereo views, iterating them whole");
+        self.compute_budget = None;
+      }
+    }
+    if self.warm_start && (self.detail_pass || self.compute_budget.is_some()) {
+      warn!("Warm starts can't resume a split iterate pass, turning them off");
+      self.warm_start = false;
+    }
+    self
+  }
+
+  /// Layers of the compute texture, one per eye in stereo.
+  fn layers(&self) -> u32 {
+    if self.stereo.is_some() { 2 } else { 1 }
+  }
+
+  /// Pixels across all layers of the compute texture.
+  fn pixels(&self) -> u64 {
+    self.size.0 as u64 * self.size.1 as u64 * self.layers() as u64
+  }
+
+  /// Workgroups of `shape` to dispatch to cover the texture.
+  fn workgroups(&self, shape: WorkgroupShape) -> (u32, u32) {
+    let tiles = (
+      self.size.0.div_ceil(shape.width),
+      self.size.1.div_ceil(shape.height),
+    );
+    match self.dispatch_order {
+      DispatchOrder::Linear => tiles,
+      DispatchOrder::Morton => {
+        let side = tiles.0.max(tiles.1).next_power_of_two();
+        (side, side)
+      }
+    }
+  }
+}
+
+/// Curve applied to the normalized iteration value before palette lookup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingCurve {
+  Linear = 0,
+  /// Generally the most even spread of detail.
+  #[default]
+  Sqrt = 1,
+  /// Brings out faint exterior detail far from the set.
+  Log = 2,
+  CubeRoot = 3,
+}
+
+impl MappingCurve {
+  /// All curves, in the order they are cycled through.
+  const ALL: [Self; 4] = [Self::Linear, Self::Sqrt, Self::Log, Self::CubeRoot];
+}
+
+/// The fractal drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FractalMode {
+  /// Each point is the constant c of z^2 + c, iterated from z = 0.
+  #[default]
+  Mandelbrot = 0,
+  /// Each point is the starting z of z^2 + c for a fixed c.
+  Julia = 1,
+}
+
+/// How texels are mapped to the complex plane.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Projection {
+  /// The plane as it is.
+  #[default]
+  Cartesian = 0,
+  /// Log-polar coordinates about `center`: the angle about it grows to the
+  /// right, one turn across the view, and the distance from it grows
+  /// exponentially upward. Zooming shifts the image vertically and rotating
+  /// shifts it horizontally, so detail looks alike at every depth.
+  LogPolar = 1,
+}
+
+/// Upper bound of [`RenderConfig::temporal_blend`], short of 1 so the
+/// current frame always shows through.
+const MAX_TEMPORAL_BLEND: f32 = 0.95;
+
+/// Iteration cap at startup.
+pub const DEFAULT_MAX_ITERATIONS: u32 = 2000;
+
+/// Half-height of the view at startup, which is defined as 1x zoom.
+const INITIAL_SCALE: f32 = 1.25;
+
+/// Number of f32 steps a texel must span for the view to render cleanly.
+/// Below this, neighboring texels round to the same few complex values and the
+/// image turns blocky.
+const PRECISION_MARGIN: f32 = 4.;
+
+/// Default width of the band drawn in boundary mode, in texels.
+const BOUNDARY_WIDTH: f32 = 1.5;
+
+#[derive(Resource, Clone, Copy, Debug, Pod, Zeroable, ShaderType, ExtractResource)]
+#[repr(C)]
+struct Uniforms {
+  /// Seconds of [`AnimationClock`] time since startup.
+  time: f32,
+  iteration_storage: u32,
+  coloring_mode: u32,
+  mapping_curve: u32,
+  /// Nonzero to apply relief shading to the palette color.
+  shading: u32,
+  /// Direction the relief light comes from, in radians.
+  light_angle: f32,
+  /// The complex number at the center of the view.
+  center: Vec2,
+  /// Half the height of the view in the complex plane.
+  scale: f32,
+  non_finite: u32,
+  alpha_mode: u32,
+  /// Counterclockwise rotation of the view, in radians.
+  rotation: f32,
+  non_finite_color: Vec4,
+  /// Nonzero to draw only a band along the boundary of the set.
+  boundary: u32,
+  /// Width of the boundary band, in texels.
+  boundary_width: f32,
+  dispatch_order: u32,
+  /// The style being crossfaded out while `transition` is below 1.
+  previous_mapping_curve: u32,
+  previous_shading: u32,
+  previous_boundary: u32,
+  /// Progress of the crossfade to the current style, from 0 to 1.
+  transition: f32,
+  /// Nonzero to blend escaped points near the set towards `glow_color`.
+  glow: u32,
+  glow_color: Vec4,
+  /// Falloff distance of the glow, in texels.
+  glow_radius: f32,
+  /// Amplitude of the coordinate warp, zero to leave the plane undistorted.
+  warp_amp: f32,
+  /// Frequency of the coordinate warp, per unit of the complex plane.
+  warp_freq: f32,
+  /// Width of a texel relative to its height on the target display.
+  pixel_aspect: f32,
+  /// Offsets of the red, green and blue channels along the palette.
+  channel_phase: Vec3,
+  /// Which fractal is drawn, a [`FractalMode`].
+  mode: u32,
+  /// The constant of the Julia set drawn in [`FractalMode::Julia`].
+  julia_c: Vec2,
+  /// Whether the iterate pass may mirror symmetric views. The shader checks
+  /// that the view is symmetric itself.
+  use_symmetry: u32,
+  /// Iterations after which a point is taken to be in the set. Escape
+  /// iterations are mapped onto the palette relative to it.
+  max_iterations: u32,
+  /// Offset in texels from each pixel to where its point was in the
+  /// previous frame.
+  history_offset: Vec2,
+  /// Weight of the previous frame, 0 when it can't be reprojected.
+  history_blend: f32,
+  /// Which half of the history buffer this frame writes. The other half
+  /// holds the previous frame.
+  history_parity: u32,
+  /// Nonzero to desaturate escaped points where the iteration cap is
+  /// cutting detail short.
+  iteration_cue: u32,
+  /// Strength of the iteration cue, from 0 to 1.
+  iteration_cue_strength: f32,
+  /// Frames since startup, wrapping. Seeds the shader's random numbers,
+  /// since `time` stands still while paused.
+  frame: u32,
+  /// How the blended fractal is composited, a [`BlendMode`].
+  blend_mode: u32,
+  /// Weight of the blended fractal, from 0 to 1.
+  blend_mix: f32,
+  /// How texels map to the complex plane, a [`Projection`].
+  projection: u32,
+  /// Fraction of the mapped escape range colored by the sky rather than the
+  /// palette, 0 without a sky.
+  sky_end: f32,
+  /// Separation between the eyes of a stereo view, in view heights.
+  eye_separation: f32,
+  /// Sky colors at the start and the end of its range.
+  sky_color: Vec4,
+  sky_edge_color: Vec4,
+}
+
+impl Uniforms {
+  fn new(config: &RenderConfig) -> Self {
+    Self {
+      time: 0.,
+      iteration_storage: config.iteration_storage as u32,
+      coloring_mode: config.coloring_mode as u32,
+      mapping_curve: MappingCurve::default() as u32,
+      shading: 0,
+      light_angle: FRAC_PI_4,
+      center: Vec2::new(-0.5, 0.),
+      scale: INITIAL_SCALE,
+      non_finite: config.non_finite as u32,
+      alpha_mode: config.alpha_mode as u32,
+      rotation: 0.,
+      non_finite_color: config.non_finite_color.to_vec4(),
+      boundary: 0,
+      boundary_width: BOUNDARY_WIDTH,
+      dispatch_order: config.dispatch_order as u32,
+      previous_mapping_curve: MappingCurve::default() as u32,
+      previous_shading: 0,
+      previous_boundary: 0,
+      transition: 1.,
+      glow: 0,
+      glow_color: config.glow_color.to_vec4(),
+      glow_radius: config.glow_radius,
+      warp_amp: 0.,
+      warp_freq: 1.,
+      pixel_aspect: config.pixel_aspect,
+      channel_phase: Vec3::ZERO,
+      mode: FractalMode::Mandelbrot as u32,
+      julia_c: Vec2::ZERO,
+      use_symmetry: config.symmetry as u32,
+      max_iterations: DEFAULT_MAX_ITERATIONS,
+      history_offset: Vec2::ZERO,
+      history_blend: 0.,
+      history_parity: 0,
+      iteration_cue: 0,
+      iteration_cue_strength: config.iteration_cue,
+      frame: 0,
+      blend_mode: config.blend.unwrap_or_default() as u32,
+      blend_mix: config.blend_mix,
+      projection: config.projection as u32,
+      sky_end: 0.,
+      eye_separation: config.stereo.unwrap_or(0.),
+      sky_color: Vec4::ZERO,
+      sky_edge_color: Vec4::ZERO,
+    }
+    .with_sky(config.sky)
+  }
+
+  /// Colors the far exterior with `sky`, or with the palette for `None`.
+  fn with_sky(mut self, sky: Option<Sky>) -> Self {
+    self.sky_end = sky.map_or(0., |sky| sky.end);
+    if let Some(sky) = sky {
+      self.sky_color = sky.color.to_vec4();
+      self.sky_edge_color = sky.edge_color.to_vec4();
+    }
+    self
+  }
+
+  /// The sky the uniforms color with, if any.
+  fn sky(&self) -> Option<Sky> {
+    (self.sky_end > 0.).then(|| Sky {
+      end: self.sky_end,
+      color: LinearRgba::from_vec4(self.sky_color),
+      edge_color: LinearRgba::from_vec4(self.sky_edge_color),
+    })
+  }
+
+  /// Height of one texel in the complex plane, regardless of the texture's
+  /// aspect ratio. Texels are `pixel_aspect` times as wide.
+  fn texel_size(&self, config: &RenderConfig) -> f32 {
+    2. * self.scale / config.size.1 as f32
+  }
+
+  /// Whether the view is in [`Projection::LogPolar`].
+  fn log_polar(&self) -> bool {
+    self.projection == Projection::LogPolar as u32
+  }
+
+  /// Change in the logarithm of the distance from the center per texel of
+  /// height in log-polar views. Matches `log_polar_step` in the shader.
+  fn log_polar_step(&self, config: &RenderConfig) -> f32 {
+    TAU / (config.size.0 as f32 * self.pixel_aspect)
+  }
+
+  /// Maps an offset from the center of the texture, in texels growing right
+  /// and down, to the complex plane. Matches `pixel_to_complex` in the
+  /// shader, in the left eye of stereo views, which is the one displayed.
+  fn texel_offset_to_complex(&self, config: &RenderConfig, texels: Vec2) -> Vec2 {
+    let center = self.left_eye_center();
+    if self.log_polar() {
+      let step = self.log_polar_step(config);
+      let angle = self.rotation + texels.x * self.pixel_aspect * step;
+      let radius = self.scale * (-texels.y * step).exp();
+      return center + Vec2::from_angle(angle) * radius;
+    }
+    // Texture rows grow downward, the imaginary axis grows upward.
+    let texels = Vec2::new(texels.x * self.pixel_aspect, -texels.y);
+    let offset = texels * self.texel_size(config);
+    center + Vec2::from_angle(self.rotation).rotate(offset)
+  }
+
+  /// The center of the view seen from the left eye, half the eye separation
+  /// to the left of `center`. Matches `eye_center` in the shader.
+  fn left_eye_center(&self) -> Vec2 {
+    let shift = Vec2::new(-self.eye_separation * self.scale, 0.);
+    self.center + Vec2::from_angle(self.rotation).rotate(shift)
+  }
+
+  /// Whether texels are too small for f32 to tell apart, which happens past
+  /// zooms of around 1e5x.
+  fn precision_limited(&self, config: &RenderConfig) -> bool {
+    // The spacing of f32 values near the largest coordinate in view.
+    let magnitude = self.center.abs().max_element() + self.scale;
+    self.texel_size(config) < PRECISION_MARGIN * magnitude * f32::EPSILON
+  }
+
+  /// The smallest scale around `center` that isn't
+  /// [`precision_limited`](Self::precision_limited), which is set by the 24
+  /// bits of an f32 mantissa.
+  fn min_scale(&self, config: &RenderConfig) -> f32 {
+    // Solves 2 * scale / height = margin * (|center| + scale) * epsilon.
+    let spacing = PRECISION_MARGIN * f32::EPSILON;
+    let center = self.center.abs().max_element();
+    spacing * center / (2. / config.size.1 as f32 - spacing)
+  }
+
+  /// These uniforms with the fields the iterate pass depends on taken from
+  /// `other`, so they iterate like it but color like themselves.
+  fn with_iteration_of(&self, other: &Self) -> Self {
+    Self {
+      center: other.center,
+      scale: other.scale,
+      rotation: other.rotation,
+      warp_amp: other.warp_amp,
+      warp_freq: other.warp_freq,
+      mode: other.mode,
+      julia_c: other.julia_c,
+      max_iterations: other.max_iterations,
+      projection: other.projection,
+      eye_separation: other.eye_separation,
+      ..*self
+    }
+  }
+
+  /// Whether the iterate pass computes the same data under `other`, which
+  /// may still color it differently.
+  fn iterates_like(&self, other: &Self) -> bool {
+    bytes_of(&self.with_iteration_of(other)) == bytes_of(self)
+  }
+
+  /// Whether the iterate pass can resume the orbits it iterated under
+  /// `self` to compute the data of `other`: the same view, with a cap no
+  /// lower.
+  fn resumes_to(&self, other: &Self) -> bool {
+    let capped = Self {
+      max_iterations: other.max_iterations,
+      ..*self
+    };
+    other.max_iterations >= self.max_iterations && capped.iterates_like(other)
+  }
+
+  /// The corners of the axis-aligned box around the (possibly rotated) view
+  /// in the complex plane.
+  fn view_bounds(&self, config: &RenderConfig) -> (Vec2, Vec2) {
+    let half_size = Vec2::new(config.size.0 as f32, config.size.1 as f32) / 2.;
+    let corners = [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)]
+      .map(|(x, y)| self.texel_offset_to_complex(config, half_size * Vec2::new(x, y)));
+    let min = corners.into_iter().reduce(Vec2::min).unwrap();
+    let max = corners.into_iter().reduce(Vec2::max).unwrap();
+    (min, max)
+  }
+
+  /// Maps a window position to the complex plane.
+  fn window_to_complex(&self, config: &RenderConfig, window: &Window, position: Vec2) -> Vec2 {
+    let texels = (position - window.size() / 2.) / DISPLAY_FACTOR as f32;
+    self.texel_offset_to_complex(config, texels)
+  }
+}
+
+/// Prints the uniforms and the view bounds they imply to stdout. Floats are
+/// printed in their shortest round-trip form, so the view can be
+/// reconstructed exactly from the output.
+fn dump_view(input: ActionInput, config: Res<RenderConfig>, uniforms: Res<Uniforms>) {
+  if !input.just_pressed(Action::DumpView) {
+    return;
+  }
+
+  let (min, max) = uniforms.view_bounds(&config);
+  println!("{:#?}", *uniforms);
+  println!("zoom: {}x", INITIAL_SCALE / uniforms.scale);
+  println!("real: [{}, {}]", min.x, max.x);
+  println!("imag: [{}, {}]", min.y, max.y);
+}
+
+/// The time driving animations in the shader. Unlike [`Time`], it can be
+/// paused to hold the animation on a single frame.
+#[derive(Resource, Default)]
+pub struct AnimationClock {
+  /// While set, the shader sees a constant time.
+  pub paused: bool,
+  elapsed: f32,
+}
+
+impl AnimationClock {
+  /// Moves the clock `dt` seconds on, unless it's paused.
+  fn advance(&mut self, dt: f32) {
+    if !self.paused {
+      self.elapsed += dt;
+    }
+  }
+}
+
+fn advance_time(
+  input: ActionInput,
+  time: Res<Time>,
+  mut clock: ResMut<AnimationClock>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  if input.just_pressed(Action::TogglePause) {
+    clock.paused = !clock.paused;
+  }
+  clock.advance(time.delta_secs());
+  uniforms.time = clock.elapsed;
+  uniforms.frame = uniforms.frame.wrapping_add(1);
+}
+
+/// Warns once each time the view zooms past what f32 can resolve.
+fn warn_precision(config: Res<RenderConfig>, uniforms: Res<Uniforms>, mut limited: Local<bool>) {
+  let now_limited = uniforms.precision_limited(&config);
+  if now_limited && !*limited {
+    warn!(
+      "Zoomed past f32 precision at {}x, the image will look blocky",
+      INITIAL_SCALE / uniforms.scale
+    );
+  }
+  *limited = now_limited;
+}
+
+fn cycle_mapping_curve(input: ActionInput, mut uniforms: ResMut<Uniforms>) {
+  if input.just_pressed(Action::CycleMappingCurve) {
+    uniforms.mapping_curve = (uniforms.mapping_curve + 1) % MappingCurve::ALL.len() as u32;
+    info!(
+      "Mapping curve: {:?}",
+      MappingCurve::ALL[uniforms.mapping_curve as usize]
+    );
+  }
+}
+
+/// How far each press of [`Action::RotateLight`] turns the relief light.
+const LIGHT_STEP: f32 = PI / 12.;
+
+fn adjust_shading(input: ActionInput, mut uniforms: ResMut<Uniforms>) {
+  if input.just_pressed(Action::ToggleShading) {
+    uniforms.shading ^= 1;
+  }
+  if input.just_pressed(Action::RotateLight) {
+    uniforms.light_angle = (uniforms.light_angle + LIGHT_STEP) % TAU;
+  }
+}
+
+fn toggle_boundary(input: ActionInput, mut uniforms: ResMut<Uniforms>) {
+  if input.just_pressed(Action::ToggleBoundary) {
+    uniforms.boundary ^= 1;
+  }
+}
+
+fn toggle_glow(input: ActionInput, mut uniforms: ResMut<Uniforms>) {
+  if input.just_pressed(Action::ToggleGlow) {
+    uniforms.glow ^= 1;
+  }
+}
+
+fn toggle_iteration_cue(input: ActionInput, mut uniforms: ResMut<Uniforms>) {
+  if input.just_pressed(Action::ToggleIterationCue) {
+    uniforms.iteration_cue ^= 1;
+  }
+}
+
+/// A render target provided by the host app through
+/// [`MandelbrotComputePlugin::with_target`].
+#[derive(Resource)]
+struct HostTarget(Handle<Image>);
+
+/// Usages the compute texture needs: written by the compute passes, and
+/// sampled by whatever displays it.
+const TEXTURE_USAGES: TextureUsages =
+  TextureUsages::STORAGE_BINDING.union(TextureUsages::TEXTURE_BINDING);
+
+/// The format the compute texture is created in, chosen from
+/// [`TEXTURE_FORMAT`] and its fallbacks once the adapter is known.
+#[derive(Resource, Clone, Copy)]
+struct OutputFormat(TextureFormat);
+
+impl OutputFormat {
+  /// The first of [`TEXTURE_FORMAT`] and [`FALLBACK_TEXTURE_FORMATS`] the
+  /// adapter can both write from a compute shader and sample.
+  fn choose(adapter: &RenderAdapter) -> Self {
+    let supported = |format: TextureFormat| {
+      adapter
+        .get_texture_format_features(format)
+        .allowed_usages
+        .contains(TEXTURE_USAGES)
+    };
+    if supported(TEXTURE_FORMAT) {
+      return Self(TEXTURE_FORMAT);
+    }
+    match FALLBACK_TEXTURE_FORMATS
+      .into_iter()
+      .find(|&format| supported(format))
+    {
+      Some(format) => {
+        warn!(
+          "The adapter can't write {TEXTURE_FORMAT:?} storage textures, falling back to {format:?}"
+        );
+        Self(format)
+      }
+      // Nothing to fall back to, so let pipeline creation report it.
+      None => {
+        warn!(
+          "The adapter can't write {TEXTURE_FORMAT:?} storage textures, nor any of \
+           {FALLBACK_TEXTURE_FORMATS:?}"
+        );
+        Self(TEXTURE_FORMAT)
+      }
+    }
+  }
+}
+
+/// Checks that a host-provided target can be rendered into in place of the
+/// texture [`create_target_image`] would create.
+fn validate_host_target(
+  image: &Image,
+  config: &RenderConfig,
+  format: OutputFormat,
+) -> Result<(), String> {
+  let descriptor = &image.texture_descriptor;
+  if descriptor.format != format.0 {
+    return Err(format!(
+      "its format is {:?}, expected {:?}",
+      descriptor.format, format.0
+    ));
+  }
+  if !descriptor.usage.contains(TEXTURE_USAGES) {
+    return Err(format!(
+      "its usages are {:?}, expected at least {TEXTURE_USAGES:?}",
+      descriptor.usage
+    ));
+  }
+  let size = (descriptor.size.width, descriptor.size.height);
+  if size != config.size {
+    return Err(format!(
+      "it is {}x{}, but the plugin renders at {}x{}",
+      size.0, size.1, config.size.0, config.size.1
+    ));
+  }
+  let layers = descriptor.size.depth_or_array_layers;
+  if layers != config.layers() {
+    return Err(format!(
+      "it has {layers} layers, but the plugin renders {}",
+      config.layers()
+    ));
+  }
+  Ok(())
+}
+
+/// Creates the compute target texture, or adopts the host's, and publishes
+/// its handle through [`MandelbrotImages`]. A host texture that can't be
+/// rendered into is reported, and replaced with one of the plugin's own.
+fn create_target_image(
+  mut commands: Commands,
+  config: Res<RenderConfig>,
+  format: Res<OutputFormat>,
+  palette: Res<Palette>,
+  host_target: Option<Res<HostTarget>>,
+  failures: Res<FailureSender>,
+  mut images: ResMut<Assets<Image>>,
+) {
+  let palette = images.add(palette.image());
+  if let Some(HostTarget(handle)) = host_target.as_deref() {
+    let validated = match images.get(handle) {
+      Some(image) => validate_host_target(image, &config, *format),
+      None => Err("it must be added to `Assets<Image>` before startup".to_owned()),
+    };
+    match validated {
+      Ok(()) => {
+        commands.insert_resource(MandelbrotImages {
+          texture: handle.clone(),
+          palette,
+          format: format.0,
+        });
+        return;
+      }
+      Err(reason) => failures.report(MandelbrotError::InvalidTarget(reason)),
+    }
+  }
+
+  let (width, height) = config.size;
+  let layers = config.layers();
+  let mut image = Image::new_target_texture(width, height, format.0);
+  image.texture_descriptor.size.depth_or_array_layers = layers;
+  // The compute passes bind every layer, but samplers of the texture see
+  // the first, the left eye in stereo.
+  image.texture_view_descriptor = Some(TextureViewDescriptor {
+    dimension: Some(TextureViewDimension::D2),
+    array_layer_count: Some(1),
+    ..default()
+  });
+  // Nothing is dispatched until the pipelines compile, so upload zeroed
+  // texels rather than leaving the texture uninitialized: frames before the
+  // first dispatch show transparent black over the clear color on every
+  // backend.
+  let texel_size = format
+    .0
+    .block_copy_size(None)
+    .expect("the compute texture format is uncompressed") as usize;
+  image.data = Some(vec![0; config.pixels() as usize * texel_size]);
+  image.sampler = config.display_filter.sampler();
+  image.asset_usage = RenderAssetUsages::RENDER_WORLD;
+  image.texture_descriptor.usage = TextureUsages::COPY_DST | TEXTURE_USAGES;
+  let image = images.add(image);
+
+  commands.insert_resource(MandelbrotImages {
+    texture: image,
+    palette,
+    format: format.0,
+  });
+}
+
+/// The texture the fractal is rendered into.
+///
+/// The texture is created with `TEXTURE_BINDING` usage, so besides the
+/// sprite the binary draws it on, it can be sampled by any material or mesh
+/// in a host app. In stereo it has a layer per eye, and the image's default
+/// view shows the left one; a `D2Array` view of it shows both. With
+/// [`MandelbrotComputePlugin::with_target`], it is the host's texture
+/// instead. The handle is stable for the lifetime of the app: the
+/// image asset is only ever modified in place, never replaced, so a handle
+/// cloned once stays valid across resizes.
+#[derive(Resource, Clone, ExtractResource)]
+pub struct MandelbrotImages {
+  texture: Handle<Image>,
+  /// The lookup texture baked from the [`Palette`].
+  palette: Handle<Image>,
+  format: TextureFormat,
+}
+
+impl MandelbrotImages {
+  /// Handle to the texture the compute shader writes into.
+  pub fn texture(&self) -> &Handle<Image> {
+    &self.texture
+  }
+
+  /// The format of [`Self::texture`], [`TEXTURE_FORMAT`] unless the adapter
+  /// can't write it from a compute shader and a lower precision format
+  /// stands in.
+  pub fn format(&self) -> TextureFormat {
+    self.format
+  }
+}
+
+#[derive(Resource)]
+struct MandelbrotImageBindGroups(BindGroup);
+
+fn prepare_bind_group(
+  mut commands: Commands,
+  config: Res<RenderConfig>,
+  pipeline: Res<MandelbrotPipeline>,
+  gpu_images: Res<RenderAssets<GpuImage>>,
+  game_of_life_images: Res<MandelbrotImages>,
+  render_device: Res<RenderDevice>,
+) {
+  // The images are prepared on the first frame after they're added.
+  let (Some(view), Some(palette)) = (
+    gpu_images.get(&game_of_life_images.texture),
+    gpu_images.get(&game_of_life_images.palette),
+  ) else {
+    return;
+  };
+  // The image's own view only shows the first layer of a stereo texture.
+  let layers = config.stereo.map(|_| {
+    view.texture.create_view(&TextureViewDescriptor {
+      dimension: Some(TextureViewDimension::D2Array),
+      ..default()
+    })
+  });
+  let bind_group_0 = render_device.create_bind_group(
+    "Mandelbrot",
+    &pipeline.texture_bind_group_layout,
+    &BindGroupEntries::sequential((
+      layers.as_ref().unwrap_or(&view.texture_view),
+      pipeline.uniform_buffer.as_entire_buffer_binding(),
+      pipeline.iterations_buffer.as_entire_buffer_binding(),
+      pipeline.distances_buffer.as_entire_buffer_binding(),
+      &palette.texture_view,
+      pipeline.history_buffer.as_entire_buffer_binding(),
+      pipeline.detail_pixels_buffer.as_entire_buffer_binding(),
+      pipeline.detail_args_buffer.as_entire_buffer_binding(),
+      pipeline.angles_buffer.as_entire_buffer_binding(),
+      pipeline.dispatch_base_buffer.as_entire_buffer_binding(),
+      pipeline.blend_iterations_buffer.as_entire_buffer_binding(),
+      pipeline.orbit_density_buffer.as_entire_buffer_binding(),
+      pipeline.orbit_seeds_buffer.as_entire_buffer_binding(),
+      pipeline.orbit_averages_buffer.as_entire_buffer_binding(),
+      pipeline.transfer_buffer.as_entire_buffer_binding(),
+      pipeline.orbit_states_buffer.as_entire_buffer_binding(),
+    )),
+  );
+  commands.insert_resource(MandelbrotImageBindGroups(bind_group_0));
+}
+
+/// The uniforms this frame's dispatch sees. Uniforms reach the GPU a frame
+/// after they're extracted, as [`update_uniforms`] writes them once the
+/// frame is rendered, so these are the ones extracted last frame.
+#[derive(Resource, Default)]
+pub(crate) struct DispatchedUniforms {
+  /// `None` on the first frame, before any uniforms were written.
+  pub(crate) current: Option<Uniforms>,
+  /// The uniforms extracted this frame, dispatched next frame.
+  next: Option<Uniforms>,
+}
+
+fn track_dispatched_uniforms(uniforms: Res<Uniforms>, mut dispatched: ResMut<DispatchedUniforms>) {
+  dispatched.current = dispatched.next.replace(*uniforms);
+}
+
+fn update_uniforms(
+  pipeline: Res<MandelbrotPipeline>,
+  uniform_data: Res<Uniforms>,
+  render_device: Res<RenderDevice>,
+) {
+  static MAPPED: AtomicBool = AtomicBool::new(false);
+
+  let uniform_data = *uniform_data;
+  let buffer = pipeline.mapped_uniform_buffer.clone();
+  if MAPPED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+
+  // Maps the buffer so it can be read on the cpu
+  pipeline
+    .mapped_uniform_buffer
+    .slice(..)
+    .map_async(MapMode::Write, move |r| match r {
+      // This will execute once the gpu is ready, so after the call to poll()
+      Ok(_) => {
+        buffer
+          .slice(..)
+          .get_mapped_range_mut()
+          .copy_from_slice(bytes_of(&uniform_data));
+
+        buffer.unmap();
+        MAPPED.store(false, Ordering::SeqCst);
+      }
+      // Leave the previous uniforms in place and try again next frame.
+      Err(err) => {
+        error!("{}", MandelbrotError::BufferMap(err.to_string()));
+        MAPPED.store(false, Ordering::SeqCst);
+      }
+    });
+
+  if let Err(err) = render_device.poll(PollType::Wait) {
+    error!("{}", MandelbrotError::Poll(err.to_string()));
+  }
+}
+
+/// Renders the fractal into the texture exposed through [`MandelbrotImages`].
+#[derive(Default)]
+pub struct MandelbrotComputePlugin {
+  config: RenderConfig,
+  shader_path: Option<String>,
+  target: Option<Handle<Image>>,
+  view: Option<View>,
+}
+
+impl MandelbrotComputePlugin {
+  /// Renders into a texture owned by the host app instead of creating one.
+  /// The image must be in `Assets<Image>` by the time startup runs, be
+  /// [`TEXTURE_FORMAT`] (or the fallback the startup log names on adapters
+  /// that can't write it), have at least `STORAGE_BINDING | TEXTURE_BINDING`
+  /// usage and match the plugin's size. Otherwise a
+  /// [`MandelbrotFailure`](error::MandelbrotFailure) says which doesn't
+  /// hold, and the plugin renders into a texture of its own. Its sampler and
+  /// contents are left as the host made them.
+  pub fn with_target(mut self, target: Handle<Image>) -> Self {
+    self.target = Some(target);
+    self
+  }
+
+  /// Starts at `view` instead of the initial one.
+  pub fn with_view(mut self, view: View) -> Self {
+    self.view = Some(view);
+    self
+  }
+
+  /// The configuration so far.
+  pub fn config(&self) -> &RenderConfig {
+    &self.config
+  }
+
+  /// The view the plugin starts at, as configured so far.
+  pub fn view(&self) -> View {
+    self
+      .view
+      .unwrap_or_else(|| View::from_uniforms(&Uniforms::new(&self.config)))
+  }
+
+  /// Renders with a custom compute shader instead of the default one. The
+  /// path is an asset path, relative to the asset folder unless absolute.
+  /// The shader must define the `iterate` and `recolor` entry points over
+  /// the default shader's bindings.
+  pub fn with_shader(mut self, path: impl Into<String>) -> Self {
+    self.shader_path = Some(path.into());
+    self
+  }
+
+  /// Sets the dimensions of the compute texture, in texels.
+  pub fn with_size(mut self, size: (u32, u32)) -> Self {
+    self.config.size = size;
+    self
+  }
+
+  /// Sets the width of a texel relative to its height on the target display.
+  pub fn with_pixel_aspect(mut self, pixel_aspect: f32) -> Self {
+    self.config.pixel_aspect = pixel_aspect;
+    self
+  }
+
+  /// Sets the side of the workgroups the compute passes run in.
+  pub fn with_workgroup_size(mut self, workgroup_size: u32) -> Self {
+    self.config.workgroup_size = workgroup_size;
+    self
+  }
+
+  /// Selects how iteration results are stored between passes.
+  pub fn with_iteration_storage(mut self, iteration_storage: IterationStorage) -> Self {
+    self.config.iteration_storage = iteration_storage;
+    self
+  }
+
+  /// Selects how iteration results are colored.
+  pub fn with_coloring_mode(mut self, coloring_mode: ColoringMode) -> Self {
+    self.config.coloring_mode = coloring_mode;
+    self
+  }
+
+  /// Selects how orbits that overflow to infinity or NaN are colored.
+  pub fn with_non_finite(mut self, non_finite: NonFinite) -> Self {
+    self.config.non_finite = non_finite;
+    self
+  }
+
+  /// Sets the color and radius of the glow around the set.
+  pub fn with_glow(mut self, color: LinearRgba, radius: f32) -> Self {
+    self.config.glow_color = color;
+    self.config.glow_radius = radius;
+    self
+  }
+
+  /// Blends panned frames with the previous one, weighted by `blend`.
+  pub fn with_temporal_blend(mut self, blend: f32) -> Self {
+    self.config.temporal_blend = blend;
+    self
+  }
+
+  /// Sets how far [`Action::ToggleIterationCue`] desaturates points the
+  /// iteration cap cut short, from 0 to 1.
+  pub fn with_iteration_cue(mut self, strength: f32) -> Self {
+    self.config.iteration_cue = strength;
+    self
+  }
+
+  /// Selects the projection the view starts in.
+  pub fn with_projection(mut self, projection: Projection) -> Self {
+    self.config.projection = projection;
+    self
+  }
+
+  /// Colors the far exterior with `sky` instead of the start of the
+  /// palette.
+  pub fn with_sky(mut self, sky: Option<Sky>) -> Self {
+    self.config.sky = sky;
+    self
+  }
+
+  /// Renders a view per eye into a texture array, the eyes `separation`
+  /// view heights apart. See [`RenderConfig::stereo`].
+  pub fn with_stereo(mut self, separation: Option<f32>) -> Self {
+    self.config.stereo = separation;
+    self
+  }
+
+  /// Blends a second fractal over the view. See [`RenderConfig::blend`].
+  pub fn with_blend(mut self, blend: Option<BlendMode>, mix: f32) -> Self {
+    self.config.blend = blend;
+    self.config.blend_mix = mix;
+    self
+  }
+
+  /// Sets the color used by [`NonFinite::Highlight`].
+  pub fn with_non_finite_color(mut self, color: LinearRgba) -> Self {
+    self.config.non_finite_color = color;
+    self
+  }
+
+  /// Selects the alpha convention of the compute texture.
+  pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+    self.config.alpha_mode = alpha_mode;
+    self
+  }
+
+  /// Sets the filter the compute texture is sampled with for display.
+  pub fn with_display_filter(mut self, display_filter: DisplayFilter) -> Self {
+    self.config.display_filter = display_filter;
+    self
+  }
+
+  /// Selects the order workgroups are assigned to tiles of the texture.
+  pub fn with_dispatch_order(mut self, dispatch_order: DispatchOrder) -> Self {
+    self.config.dispatch_order = dispatch_order;
+    self
+  }
+
+  /// Sets whether views symmetric about the real axis are iterated only
+  /// once per mirrored pair of rows.
+  pub fn with_symmetry(mut self, symmetry: bool) -> Self {
+    self.config.symmetry = symmetry;
+    self
+  }
+
+  /// Sets whether points still iterating partway are finished in a separate
+  /// detail pass. See [`RenderConfig::detail_pass`].
+  pub fn with_detail_pass(mut self, detail_pass: bool) -> Self {
+    self.config.detail_pass = detail_pass;
+    self
+  }
+
+  /// Caps the workgroups the iterate pass dispatches per frame. See
+  /// [`RenderConfig::compute_budget`].
+  pub fn with_compute_budget(mut self, compute_budget: Option<u32>) -> Self {
+    self.config.compute_budget = compute_budget;
+    self
+  }
+
+  /// Sets whether orbits are kept to resume when the iteration cap grows.
+  /// See [`RenderConfig::warm_start`].
+  pub fn with_warm_start(mut self, warm_start: bool) -> Self {
+    self.config.warm_start = warm_start;
+    self
+  }
+
+  /// Sets whether the palette cycles. See [`RenderConfig::color_cycle`].
+  pub fn with_color_cycle(mut self, color_cycle: bool) -> Self {
+    self.config.color_cycle = color_cycle;
+    self
+  }
+
+  /// Sets whether rendering pauses while the primary window is unfocused.
+  pub fn with_pause_unfocused(mut self, pause_unfocused: bool) -> Self {
+    self.config.pause_unfocused = pause_unfocused;
+    self
+  }
+
+  /// Caps the frame rate while the view is idle, to save power.
+  pub fn with_max_idle_fps(mut self, max_idle_fps: Option<f32>) -> Self {
+    self.config.max_idle_fps = max_idle_fps;
+    self
+  }
+
+  /// Applies newline-delimited JSON view updates read from stdin.
+  pub fn with_stdin_control(mut self, stdin_control: bool) -> Self {
+    self.config.stdin_control = stdin_control;
+    self
+  }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct MandelbrotLabel;
+
+impl Plugin for MandelbrotComputePlugin {
+  fn build(&self, app: &mut App) {
+    let config = self.config.validated();
+    let mut uniforms = Uniforms::new(&config);
+    if let Some(view) = &self.view {
+      view.apply_to(&mut uniforms);
+    }
+    let shader_path = ShaderPath(
+      self
+        .shader_path
+        .clone()
+        .unwrap_or_else(|| SHADER_ASSET_PATH.to_owned()),
+    );
+    app
+      .insert_resource(config)
+      .insert_resource(shader_path.clone())
+      .insert_resource(uniforms)
+      .insert_resource(WorkgroupShape::square(config.workgroup_size))
+      .init_resource::<Keybinds>()
+      .init_resource::<AnimationClock>()
+      .init_resource::<PipelineKey>()
+      // Split in two, since plugin tuples hold at most 15.
+      .add_plugins((
+        (
+          AutoCenterPlugin,
+          AutoIterationsPlugin,
+          DiagnosticsPlugin,
+          DimensionPlugin,
+          ErrorPlugin,
+          ExportPlugin,
+          FocusPlugin,
+          FreezePlugin,
+          GotoPlugin,
+          HoldPlugin,
+          IdlePlugin,
+          IterationsPlugin,
+        ),
+        (
+          JuliaPlugin,
+          MeshExportPlugin,
+          NavigationPlugin,
+          OrbitsPlugin,
+          OverlayPlugin,
+          PalettePlugin,
+          PrecisionPlugin,
+          ScreensaverPlugin,
+          StatusPlugin,
+          TemporalPlugin,
+          TourPlugin,
+          TransferPlugin,
+          TransitionPlugin,
+          ViewPlugin,
+          WorkgroupsPlugin,
+        ),
+      ))
+      .add_plugins((
+        ExtractResourcePlugin::<MandelbrotImages>::default(),
+        ExtractResourcePlugin::<Uniforms>::default(),
+        ExtractResourcePlugin::<PipelineKey>::default(),
+      ))
+      .add_systems(PreStartup, create_target_image)
+      .add_systems(
+        PostUpdate,
+        update_pipeline_key.after(transition::advance_transition),
+      )
+      .add_systems(Update, check_entry_points)
+      .add_systems(
+        Update,
+        (
+          advance_time,
+          cycle_mapping_curve,
+          adjust_shading,
+          toggle_boundary,
+          toggle_glow,
+          toggle_iteration_cue,
+          warn_precision,
+          dump_view,
+        ),
+      );
+    if config.stdin_control {
+      app.add_plugins(ControlPlugin);
+    }
+    if let Some(target) = &self.target {
+      app.insert_resource(HostTarget(target.clone()));
+    }
+    let render_app = app.sub_app_mut(RenderApp);
+    render_app
+      .insert_resource(config)
+      .insert_resource(shader_path)
+      .init_resource::<DispatchedUniforms>()
+      .add_systems(RenderStartup, (log_adapter, init_mandelbrot_pipeline))
+      .add_systems(
+        Render,
+        (
+          track_dispatched_uniforms.in_set(RenderSystems::Prepare),
+          queue_pipeline_variant.in_set(RenderSystems::Queue),
+          prepare_bind_group.in_set(RenderSystems::PrepareBindGroups),
+          update_uniforms.after(RenderSystems::Render),
+        ),
+      );
+
+    let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+    render_graph.add_node(MandelbrotLabel, MandelbrotNode::default());
+    render_graph.add_node_edge(MandelbrotLabel, bevy::render::graph::CameraDriverLabel);
+  }
+
+  fn finish(&self, app: &mut App) {
+    // The adapter is only known once the render plugin has finished.
+    let format = app
+      .world()
+      .get_resource::<RenderAdapter>()
+      .map_or(OutputFormat(TEXTURE_FORMAT), OutputFormat::choose);
+    app.insert_resource(format);
+    app.sub_app_mut(RenderApp).insert_resource(format);
+  }
+}
+
+/// Shader features compiled into a pipeline variant. The shader's branches
+/// for features that are off are removed by the preprocessor, so the common
+/// case doesn't pay for them.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, ExtractResource)]
+struct PipelineKey {
+  /// Track the orbit derivative for boundary mode, the glow, distance
+  /// output and exports.
+  distance_estimate: bool,
+  relief_shading: bool,
+  /// The shape the iterate and recolor passes are compiled for.
+  workgroup: WorkgroupShape,
+  /// Draw the orbit density layer.
+  orbit_density: bool,
+  /// Map escape iterations to the palette with a [`Transfer`] function.
+  transfer: bool,
+}
+
+impl PipelineKey {
+  fn shader_defs(self, config: &RenderConfig, format: OutputFormat) -> Vec<ShaderDefVal> {
+    let mut defs = vec![
+      ShaderDefVal::UInt("WORKGROUP_WIDTH".into(), self.workgroup.width),
+      ShaderDefVal::UInt("WORKGROUP_HEIGHT".into(), self.workgroup.height),
+    ];
+    match format.0 {
+      TextureFormat::Rgba16Float => defs.push("OUTPUT_RGBA16FLOAT".into()),
+      TextureFormat::Rgba8Unorm => defs.push("OUTPUT_RGBA8UNORM".into()),
+      _ => {}
+    }
+    if self.distance_estimate {
+      defs.push("DISTANCE_ESTIMATE".into());
+    }
+    if self.relief_shading {
+      defs.push("RELIEF_SHADING".into());
+    }
+    if config.detail_pass {
+      defs.push("DETAIL_PASS".into());
+    }
+    if config.coloring_mode == ColoringMode::Angle {
+      defs.push("ANGLE_COLORING".into());
+    }
+    if config.coloring_mode == ColoringMode::Average {
+      defs.push("ORBIT_AVERAGE".into());
+    }
+    if config.compute_budget.is_some() {
+      defs.push("COMPUTE_BUDGET".into());
+    }
+    if config.warm_start {
+      defs.push("WARM_START".into());
+    }
+    if config.color_cycle {
+      defs.push("COLOR_CYCLE".into());
+    }
+    if config.blend.is_some() {
+      defs.push("BLEND_MODES".into());
+    }
+    if self.orbit_density {
+      defs.push("ORBIT_DENSITY".into());
+    }
+    if self.transfer {
+      defs.push("TRANSFER_FUNCTION".into());
+    }
+    if config.stereo.is_some() {
+      defs.push("STEREO".into());
+    }
+    defs
+  }
+}
+
+/// Picks the pipeline variant for the frame. A style being crossfaded out
+/// keeps its features on until the crossfade ends.
+fn update_pipeline_key(
+  config: Res<RenderConfig>,
+  uniforms: Res<Uniforms>,
+  export: Option<Res<ExrExport>>,
+  workgroup: Res<WorkgroupShape>,
+  orbit_seeds: Res<OrbitSeeds>,
+  transfer: Option<Res<Transfer>>,
+  mut key: ResMut<PipelineKey>,
+) {
+  let fading = uniforms.transition < 1.;
+  let boundary = uniforms.boundary != 0 || (fading && uniforms.previous_boundary != 0);
+  let shading = uniforms.shading != 0 || (fading && uniforms.previous_shading != 0);
+  let new_key = PipelineKey {
+    distance_estimate: boundary
+      || uniforms.glow != 0
+      || export.is_some()
+      || config.coloring_mode == ColoringMode::Distance,
+    relief_shading: shading,
+    workgroup: *workgroup,
+    // Orbits are traced through a single view.
+    orbit_density: orbit_seeds.enabled && config.stereo.is_none(),
+    transfer: transfer.is_some(),
+  };
+  if *key != new_key {
+    *key = new_key;
+  }
+}
+
+/// The compute pipelines of one [`PipelineKey`].
+struct PipelineVariant {
+  iterate: CachedComputePipelineId,
+  recolor: CachedComputePipelineId,
+  /// Only queued with [`RenderConfig::detail_pass`].
+  detail: Option<CachedComputePipelineId>,
+  /// Only queued with [`PipelineKey::orbit_density`].
+  orbits: Option<CachedComputePipelineId>,
+}
+
+impl PipelineVariant {
+  /// Whether all pipelines are ready to dispatch, or why one never will be.
+  fn loaded(
+    &self,
+    pipeline_cache: &PipelineCache,
+    shader_path: &ShaderPath,
+  ) -> Result<bool, MandelbrotError> {
+    let mut loaded = true;
+    let optional = self.detail.into_iter().chain(self.orbits);
+    for id in [self.iterate, self.recolor].into_iter().chain(optional) {
+      match pipeline_cache.get_compute_pipeline_state(id) {
+        CachedPipelineState::Ok(_) => {}
+        // If the shader hasn't loaded yet, just wait.
+        CachedPipelineState::Err(PipelineCacheError::ShaderNotLoaded(_)) => loaded = false,
+        CachedPipelineState::Err(err) => {
+          return Err(MandelbrotError::Pipeline {
+            shader: shader_path.0.clone(),
+            message: err.to_string(),
+          });
+        }
+        _ => loaded = false,
+      }
+    }
+    Ok(loaded)
+  }
+}
+
+#[derive(Resource)]
+struct MandelbrotPipeline {
+  texture_bind_group_layout: BindGroupLayout,
+  shader: Handle<Shader>,
+  /// Variants compiled so far, queued the first time their key is used.
+  variants: HashMap<PipelineKey, PipelineVariant>,
+  uniform_buffer: Buffer,
+  mapped_uniform_buffer: Buffer,
+  iterations_buffer: Buffer,
+  distances_buffer: Buffer,
+  history_buffer: Buffer,
+  detail_pixels_buffer: Buffer,
+  detail_args_buffer: Buffer,
+  detail_args_reset_buffer: Buffer,
+  angles_buffer: Buffer,
+  /// The first workgroup of the frame's iterate dispatch under
+  /// [`RenderConfig::compute_budget`].
+  dispatch_base_buffer: Buffer,
+  blend_iterations_buffer: Buffer,
+  /// Visits of each pixel by the traced orbits, then their running maximum.
+  orbit_density_buffer: Buffer,
+  orbit_seeds_buffer: Buffer,
+  orbit_averages_buffer: Buffer,
+  /// Instructions of the [`Transfer`] function, written when it changes.
+  transfer_buffer: Buffer,
+  /// Each pixel's orbit under [`RenderConfig::warm_start`].
+  orbit_states_buffer: Buffer,
+}
+
+/// Logs the GPU the compute passes run on, which is the first thing to know
+/// when performance or precision differs between machines.
+fn log_adapter(
+  adapter_info: Res<RenderAdapterInfo>,
+  render_device: Res<RenderDevice>,
+  format: Res<OutputFormat>,
+) {
+  let limits = render_device.limits();
+  info!(
+    "Rendering with {} on {:?} ({:?}), max compute workgroup size {}x{}, max texture size {}, \
+     writing {:?}",
+    adapter_info.name,
+    adapter_info.backend,
+    adapter_info.device_type,
+    limits.max_compute_workgroup_size_x,
+    limits.max_compute_workgroup_size_y,
+    limits.max_texture_dimension_2d,
+    format.0,
+  );
+}
+
+/// Reports a clear error as soon as the shader loads if it lacks an entry
+/// point, rather than a pipeline validation error once it's compiled.
+fn check_entry_points(
+  mut events: EventReader<AssetEvent<Shader>>,
+  shaders: Res<Assets<Shader>>,
+  config: Res<RenderConfig>,
+  shader_path: Res<ShaderPath>,
+  failures: Res<FailureSender>,
+) {
+  for event in events.read() {
+    let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+      continue;
+    };
+    let Some(shader) = shaders
+      .get(*id)
+      .filter(|shader| shader.path == shader_path.0)
+    else {
+      continue;
+    };
+    let Source::Wgsl(source) = &shader.source else {
+      continue;
+    };
+    let detail = config.detail_pass.then_some(DETAIL_ENTRY_POINT);
+    for entry_point in ENTRY_POINTS.into_iter().chain(detail) {
+      if !source.contains(&format!("fn {entry_point}(")) {
+        failures.report(MandelbrotError::MissingEntryPoint {
+          shader: shader_path.0.clone(),
+          entry_point,
+        });
+      }
+    }
+  }
+}
+
+fn init_mandelbrot_pipeline(
+  mut commands: Commands,
+  render_device: Res<RenderDevice>,
+  asset_server: Res<AssetServer>,
+  config: Res<RenderConfig>,
+  format: Res<OutputFormat>,
+  shader_path: Res<ShaderPath>,
+) {
+  let uniforms = Uniforms::new(&config);
+  let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+    label: Some("Uniforms"),
+    contents: bytes_of(&uniforms),
+    usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+  });
+  let mapped_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+    label: Some("Mapped uniforms"),
+    contents: bytes_of(&uniforms),
+    usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
+  });
+
+  // Iteration results written by the iterate pass and consumed by the recolor
+  // pass.
+  let iterations_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Iterations"),
+    size: config.iteration_storage.buffer_size(config.pixels()),
+    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+    mapped_at_creation: false,
+  });
+  // Distance estimates written alongside the iteration results, one f32 per
+  // pixel.
+  let distances_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Distances"),
+    size: config.pixels() * 4,
+    usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+    mapped_at_creation: false,
+  });
+  // The final colors of the last two frames, for temporal blending. It's
+  // never read while blending is off, so then it shrinks to one texel.
+  let history_texels = if config.temporal_blend > 0. {
+    2 * config.size.0 as u64 * config.size.1 as u64
+  } else {
+    1
+  };
+  let history_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("History"),
+    size: history_texels * 16,
+    usage: BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+  // The pixels the iterate pass leaves to the detail pass, and the indirect
+  // dispatch arguments it counts them into. Without the detail pass the list
+  // is never written, so it shrinks to one entry.
+  let detail_pixels = if config.detail_pass {
+    config.pixels()
+  } else {
+    1
+  };
+  let detail_pixels_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Detail pixels"),
+    size: detail_pixels * 4,
+    usage: BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+  let detail_args_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+    label: Some("Detail dispatch"),
+    contents: bytes_of(&DETAIL_ARGS_RESET),
+    usage: BufferUsages::STORAGE | BufferUsages::INDIRECT | BufferUsages::COPY_DST,
+  });
+  let detail_args_reset_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+    label: Some("Detail dispatch reset"),
+    contents: bytes_of(&DETAIL_ARGS_RESET),
+    usage: BufferUsages::COPY_SRC,
+  });
+  // The escape angle of each pixel, for angle coloring. Without it the
+  // angles are never written, so the buffer shrinks to one entry.
+  let angle_pixels = if config.coloring_mode == ColoringMode::Angle {
+    config.pixels()
+  } else {
+    1
+  };
+  let angles_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Angles"),
+    size: angle_pixels * 4,
+    usage: BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+
+  let dispatch_base_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Dispatch base"),
+    size: 4,
+    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  });
+
+  // The divergence of the blended fractal, one f32 per pixel. Without
+  // blending it's never written, so it shrinks to one entry.
+  let blend_pixels = if config.blend.is_some() {
+    config.pixels()
+  } else {
+    1
+  };
+  let blend_iterations_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Blend iterations"),
+    size: blend_pixels * 4,
+    usage: BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+
+  // Orbit visits of each pixel and their maximum, and the seeds the orbits
+  // start from. The layer is toggled at runtime, so both are always
+  // allocated.
+  let orbit_density_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Orbit density"),
+    size: (config.size.0 as u64 * config.size.1 as u64 + 1) * 4,
+    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  });
+  let orbit_seeds_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Orbit seeds"),
+    size: size_of::<[Vec2; MAX_ORBIT_SEEDS]>() as u64,
+    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  });
+
+  // The orbit average of each pixel, two f32s, for average coloring. Like
+  // the angles, it shrinks to one entry in other modes.
+  let average_pixels = if config.coloring_mode == ColoringMode::Average {
+    config.pixels()
+  } else {
+    1
+  };
+  let orbit_averages_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Orbit averages"),
+    size: average_pixels * 8,
+    usage: BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+
+  let transfer_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Transfer function"),
+    size: size_of::<[TransferOp; MAX_TRANSFER_OPS]>() as u64,
+    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  });
+
+  // Each pixel's orbit, 32 bytes laid out as `OrbitState` in the shader.
+  // Without warm starts it's never read, so it shrinks to one entry.
+  let orbit_state_pixels = if config.warm_start {
+    config.pixels()
+  } else {
+    1
+  };
+  let orbit_states_buffer = render_device.create_buffer(&BufferDescriptor {
+    label: Some("Orbit states"),
+    size: orbit_state_pixels * 32,
+    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  });
+
+  // Stereo views are written to the layers of an array.
+  let output = if config.stereo.is_some() {
+    texture_storage_2d_array(format.0, StorageTextureAccess::WriteOnly)
+  } else {
+    texture_storage_2d(format.0, StorageTextureAccess::WriteOnly)
+  };
+  let texture_bind_group_layout = render_device.create_bind_group_layout(
+    "Mandelbrot",
+    &BindGroupLayoutEntries::sequential(
+      ShaderStages::COMPUTE,
+      (
+        output,
+        uniform_buffer::<Uniforms>(false),
+        storage_buffer_sized(false, None),
+        storage_buffer_sized(false, None),
+        texture_2d(TextureSampleType::Float { filterable: false }),
+        storage_buffer_sized(false, None),
+        storage_buffer_sized(false, None),
+        storage_buffer_sized(false, None),
+        storage_buffer_sized(false, None),
+        storage_buffer_read_only_sized(false, None),
+        storage_buffer_sized(false, None),
+        storage_buffer_sized(false, None),
+        storage_buffer_read_only_sized(false, None),
+        storage_buffer_sized(false, None),
+        storage_buffer_read_only_sized(false, None),
+        storage_buffer_sized(false, None),
+      ),
+    ),
+  );
+
+  commands.insert_resource(MandelbrotPipeline {
+    texture_bind_group_layout,
+    shader: asset_server.load(shader_path.0.clone()),
+    variants: HashMap::new(),
+    uniform_buffer: buffer,
+    mapped_uniform_buffer: mapped_buffer,
+    iterations_buffer,
+    distances_buffer,
+    history_buffer,
+    detail_pixels_buffer,
+    detail_args_buffer,
+    detail_args_reset_buffer,
+    angles_buffer,
+    dispatch_base_buffer,
+    blend_iterations_buffer,
+    orbit_density_buffer,
+    orbit_seeds_buffer,
+    orbit_averages_buffer,
+    transfer_buffer,
+    orbit_states_buffer,
+  });
+}
+
+/// Queues the pipelines of the frame's variant if they haven't been yet.
+fn queue_pipeline_variant(
+  mut pipeline: ResMut<MandelbrotPipeline>,
+  key: Res<PipelineKey>,
+  config: Res<RenderConfig>,
+  format: Res<OutputFormat>,
+  pipeline_cache: Res<PipelineCache>,
+) {
+  let pipeline = &mut *pipeline;
+  pipeline.variants.entry(*key).or_insert_with(|| {
+    let queue = |entry_point: &'static str| {
+      pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+        label: Some(format!("Mandelbrot {entry_point}").into()),
+        layout: vec![pipeline.texture_bind_group_layout.clone()],
+        shader: pipeline.shader.clone(),
+        shader_defs: key.shader_defs(&config, *format),
+        entry_point: Some(Cow::from(entry_point)),
+        ..default()
+      })
+    };
+    PipelineVariant {
+      iterate: queue("iterate"),
+      recolor: queue("recolor"),
+      detail: config.detail_pass.then(|| queue(DETAIL_ENTRY_POINT)),
+      orbits: key.orbit_density.then(|| queue(ORBITS_ENTRY_POINT)),
+    }
+  });
+}
+
+enum MandelbrotState {
+  Loading,
+  /// Dispatching the variant of this key, the latest one that loaded.
+  Update(PipelineKey),
+}
+
+struct MandelbrotNode {
+  state: MandelbrotState,
+  /// Whether the frame's variant failed to compile, reported once and until
+  /// a variant loads again.
+  failed: bool,
+  /// While the iteration data is frozen, the variant whose iterate pass
+  /// filled the buffers. Frozen frames only iterate again for a new variant,
+  /// whose recolor pass may need data the old one didn't write.
+  frozen_key: Option<PipelineKey>,
+  /// Whether this frame runs the iterate pass.
+  iterate: bool,
+  /// Whether this frame starts iterating a view from scratch.
+  clear: bool,
+  budget: BudgetProgress,
+  warm: WarmStart,
+  orbits: OrbitProgress,
+}
+
+impl Default for MandelbrotNode {
+  fn default() -> Self {
+    Self {
+      state: MandelbrotState::Loading,
+      failed: false,
+      frozen_key: None,
+      iterate: true,
+      clear: true,
+      budget: BudgetProgress::default(),
+      warm: WarmStart::default(),
+      orbits: OrbitProgress::default(),
+    }
+  }
+}
+
+/// How far the iterate pass got through a view under
+/// [`RenderConfig::compute_budget`].
+#[derive(Default)]
+struct BudgetProgress {
+  /// The uniforms and variant of the view being filled in.
+  view: Option<(Uniforms, Option<PipelineKey>)>,
+  /// Workgroups of the view dispatched so far, in dispatch order.
+  done: u32,
+  /// The first and the number of workgroups dispatched this frame.
+  base: u32,
+  count: u32,
+}
+
+impl BudgetProgress {
+  /// Plans this frame's share of the view, starting over if it changed.
+  /// `requested` is whether the iteration data may change at all.
+  fn advance(
+    &mut self,
+    dispatched: Option<Uniforms>,
+    shown: Option<PipelineKey>,
+    requested: bool,
+    budget: u32,
+    total: u32,
+  ) -> bool {
+    let Some(uniforms) = dispatched else {
+      return false;
+    };
+    let changed = self
+      .view
+      .is_none_or(|(view, key)| key != shown || !view.iterates_like(&uniforms));
+    let restart = requested && changed;
+    if restart {
+      self.view = Some((uniforms, shown));
+      self.done = 0;
+    }
+    self.base = self.done;
+    self.count = if shown.is_some() {
+      budget.min(total - self.done)
+    } else {
+      0
+    };
+    self.done += self.count;
+    restart
+  }
+
+  fn complete(&self, total: u32) -> bool {
+    self.done == total
+  }
+}
+
+/// The view whose orbits are kept under [`RenderConfig::warm_start`].
+#[derive(Default)]
+struct WarmStart {
+  /// The uniforms and variant the kept orbits were iterated under.
+  view: Option<(Uniforms, Option<PipelineKey>)>,
+}
+
+impl WarmStart {
+  /// Whether this frame's iterate pass has to start over rather than resume
+  /// the kept orbits. `requested` is whether the iteration data may change
+  /// at all.
+  fn advance(
+    &mut self,
+    dispatched: Option<Uniforms>,
+    shown: Option<PipelineKey>,
+    requested: bool,
+  ) -> bool {
+    let Some(uniforms) = dispatched else {
+      return true;
+    };
+    if !requested {
+      return false;
+    }
+    let restart = self
+      .view
+      .is_none_or(|(view, key)| key != shown || !view.resumes_to(&uniforms));
+    self.view = Some((uniforms, shown));
+    restart
+  }
+}
+
+/// A compute pass of a frame, with the pipeline of the passes only some
+/// variants have.
+#[derive(Debug, PartialEq)]
+enum SubPass<P> {
+  Iterate,
+  /// Finishes the points the iterate pass left.
+  Detail(P),
+  /// Traces the orbits of the seeds.
+  Orbits(P),
+  Recolor,
+}
+
+impl<P> SubPass<P> {
+  /// Name of the debug group the pass is recorded in.
+  fn name(&self) -> &'static str {
+    match self {
+      Self::Iterate => "iterate",
+      Self::Detail(_) => "detail",
+      Self::Orbits(_) => "orbits",
+      Self::Recolor => "recolor",
+    }
+  }
+}
+
+/// The compute passes of a frame, in the order they run: the iterate pass
+/// and the detail pass, if any, when the view is iterated, then the orbits
+/// pass, if any, and the recolor pass last. Each pass sees the results of
+/// the ones before it, so a feature adding a pass lists it after the
+/// passes it reads from.
+fn pass_order<P>(iterate: bool, detail: Option<P>, orbits: Option<P>) -> Vec<SubPass<P>> {
+  let mut passes = Vec::new();
+  if iterate {
+    passes.push(SubPass::Iterate);
+    passes.extend(detail.map(SubPass::Detail));
+  }
+  passes.extend(orbits.map(SubPass::Orbits));
+  passes.push(SubPass::Recolor);
+  passes
+}
+
+/// One compute dispatch of a frame. See [`MandelbrotNode::sub_passes`].
+struct PassDispatch<'a> {
+  /// Name of the debug group the dispatch is recorded in.
+  name: &'static str,
+  pipeline: &'a ComputePipeline,
+  bind_group: &'a BindGroup,
+  dispatch: Dispatch<'a>,
+}
+
+/// How many workgroups a [`PassDispatch`] dispatches.
+enum Dispatch<'a> {
+  /// A grid of workgroups in x, y and z.
+  Direct(u32, u32, u32),
+  /// As many as the arguments at the start of the buffer say.
+  Indirect(&'a Buffer),
+}
+
+impl MandelbrotNode {
+  /// The workgroup shape of the variant being dispatched, or of the frame's
+  /// `key` until one has loaded.
+  fn shape(&self, key: PipelineKey) -> WorkgroupShape {
+    match self.state {
+      MandelbrotState::Loading => key.workgroup,
+      MandelbrotState::Update(key) => key.workgroup,
+    }
+  }
+
+  /// The compute dispatches of this frame under the variant of `key`, in
+  /// the order [`pass_order`] gives. `None` if the iterate or recolor
+  /// pipeline is unavailable, which leaves the frame undispatched.
+  fn sub_passes<'a>(
+    &self,
+    key: PipelineKey,
+    pipeline: &'a MandelbrotPipeline,
+    pipeline_cache: &'a PipelineCache,
+    bind_group: &'a BindGroup,
+    config: &RenderConfig,
+  ) -> Option<Vec<PassDispatch<'a>>> {
+    let variant = &pipeline.variants[&key];
+    let workgroups = config.workgroups(key.workgroup);
+    // The passes over the texture cover each layer in a slice of workgroups.
+    let layers = config.layers();
+    // A shader reload may have replaced a loaded pipeline since update.
+    let iterate = pipeline_cache.get_compute_pipeline(variant.iterate)?;
+    let recolor = pipeline_cache.get_compute_pipeline(variant.recolor)?;
+    let optional = |id: Option<CachedComputePipelineId>| {
+      id.and_then(|id| pipeline_cache.get_compute_pipeline(id))
+    };
+    let detail = optional(variant.detail);
+    let orbits = optional(variant.orbits).filter(|_| self.orbits.seeds > 0);
+
+    let texture = || Dispatch::Direct(workgroups.0, workgroups.1, layers);
+    let passes = pass_order(self.iterate, detail, orbits)
+      .into_iter()
+      .map(|pass| {
+        let name = pass.name();
+        let (compute, dispatch) = match pass {
+          // A budgeted dispatch is one row of workgroups, which the shader
+          // places from `dispatch_base` on.
+          SubPass::Iterate => match config.compute_budget {
+            Some(_) => (iterate, Dispatch::Direct(self.budget.count, 1, 1)),
+            None => (iterate, texture()),
+          },
+          SubPass::Detail(detail) => (detail, Dispatch::Indirect(&pipeline.detail_args_buffer)),
+          // One row of workgroups per seed, which the shader reads the seed of.
+          SubPass::Orbits(orbits) => (
+            orbits,
+            Dispatch::Direct(ORBIT_WORKGROUPS_PER_SEED, self.orbits.seeds, 1),
+          ),
+          SubPass::Recolor => (recolor, texture()),
+        };
+        PassDispatch {
+          name,
+          pipeline: compute,
+          bind_group,
+          dispatch,
+        }
+      });
+    Some(passes.collect())
+  }
+}
+
+impl render_graph::Node for MandelbrotNode {
+  fn update(&mut self, world: &mut World) {
+    let pipeline = world.resource::<MandelbrotPipeline>();
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let key = *world.resource::<PipelineKey>();
+    let shader_path = world.resource::<ShaderPath>();
+
+    // Switch to the frame's variant once it has loaded. Until then, or if
+    // it fails, keep dispatching the previous one, which may be missing
+    // features.
+    let loaded = match pipeline.variants.get(&key) {
+      Some(variant) => variant.loaded(pipeline_cache, shader_path),
+      None => Ok(false),
+    };
+    let loaded = match loaded {
+      Ok(loaded) => loaded,
+      Err(err) => {
+        if !self.failed {
+          world.resource::<FailureSender>().report(err);
+          self.failed = true;
+        }
+        false
+      }
+    };
+    if loaded {
+      self.failed = false;
+      self.state = MandelbrotState::Update(key);
+    }
+
+    // Nothing is dispatched while inactive, so leave the bookkeeping as is.
+    let config = *world.resource::<RenderConfig>();
+    if world.resource::<RenderActive>().0 {
+      let dispatched = world.resource::<DispatchedUniforms>().current;
+      let frozen = world.resource::<Frozen>().0;
+      let shown = match self.state {
+        MandelbrotState::Loading => None,
+        MandelbrotState::Update(key) => Some(key),
+      };
+      let requested = !frozen || self.frozen_key != shown;
+      self.frozen_key = if frozen { shown } else { None };
+      match config.compute_budget {
+        None => {
+          // Kept orbits resume where they stopped unless the view changed.
+          let restart = !config.warm_start || self.warm.advance(dispatched, shown, requested);
+          self.iterate = requested;
+          self.clear = requested && restart;
+        }
+        Some(budget) => {
+          let (columns, rows) = config.workgroups(self.shape(key));
+          self.clear = self
+            .budget
+            .advance(dispatched, shown, requested, budget, columns * rows);
+          self.iterate = self.budget.count > 0;
+          let pipeline = world.resource::<MandelbrotPipeline>();
+          world.resource::<RenderQueue>().write_buffer(
+            &pipeline.dispatch_base_buffer,
+            0,
+            bytes_of(&self.budget.base),
+          );
+        }
+      }
+
+      let seeds = world.resource::<OrbitSeeds>().active();
+      self.orbits.advance(dispatched, seeds);
+      if !seeds.is_empty() {
+        world.resource::<RenderQueue>().write_buffer(
+          &world.resource::<MandelbrotPipeline>().orbit_seeds_buffer,
+          0,
+          cast_slice(seeds),
+        );
+      }
+    }
+
+    // A view that is still filling in isn't complete enough to read back.
+    let (columns, rows) = config.workgroups(self.shape(key));
+    let complete = config.compute_budget.is_none() || self.budget.complete(columns * rows);
+    if loaded && complete {
+      world.insert_resource(FrameComplete);
+    } else {
+      world.remove_resource::<FrameComplete>();
+    }
+  }
+
+  fn run(
+    &self,
+    _graph: &mut render_graph::RenderGraphContext,
+    render_context: &mut RenderContext,
+    world: &World,
+  ) -> Result<(), render_graph::NodeRunError> {
+    // Leave the texture and iteration buffer holding the last frame.
+    if !world.resource::<RenderActive>().0 {
+      return Ok(());
+    }
+
+    // Labels and debug groups name the work in GPU captures, and the span
+    // times recording it in CPU traces.
+    let _span = info_span!("mandelbrot").entered();
+    let Some(MandelbrotImageBindGroups(bind_group)) = world.get_resource() else {
+      return Ok(());
+    };
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let pipeline = world.resource::<MandelbrotPipeline>();
+    let config = world.resource::<RenderConfig>();
+
+    render_context.command_encoder().copy_buffer_to_buffer(
+      &pipeline.mapped_uniform_buffer,
+      0,
+      &pipeline.uniform_buffer,
+      0,
+      size_of::<Uniforms>() as u64,
+    );
+    // Packed integer counts are OR-ed into the buffer, so it has to start
+    // each iterated view zeroed.
+    if self.clear {
+      render_context
+        .command_encoder()
+        .clear_buffer(&pipeline.iterations_buffer, 0, None);
+      if config.warm_start {
+        render_context
+          .command_encoder()
+          .clear_buffer(&pipeline.orbit_states_buffer, 0, None);
+      }
+    }
+    if self.orbits.clear {
+      render_context
+        .command_encoder()
+        .clear_buffer(&pipeline.orbit_density_buffer, 0, None);
+    }
+    if self.iterate {
+      // The iterate pass counts the pixels it leaves to the detail pass into
+      // the detail dispatch from zero.
+      render_context.command_encoder().copy_buffer_to_buffer(
+        &pipeline.detail_args_reset_buffer,
+        0,
+        &pipeline.detail_args_buffer,
+        0,
+        size_of_val(&DETAIL_ARGS_RESET) as u64,
+      );
+    }
+
+    let timer = GpuTimer::active(world);
+    let mut pass = render_context
+      .command_encoder()
+      .begin_compute_pass(&ComputePassDescriptor {
+        label: Some("Mandelbrot"),
+        timestamp_writes: timer.map(GpuTimer::pass_writes),
+      });
+
+    if let MandelbrotState::Update(key) = self.state {
+      let Some(sub_passes) = self.sub_passes(key, pipeline, pipeline_cache, bind_group, config)
+      else {
+        return Ok(());
+      };
+      for sub_pass in sub_passes {
+        pass.push_debug_group(sub_pass.name);
+        pass.set_pipeline(sub_pass.pipeline);
+        pass.set_bind_group(0, sub_pass.bind_group, &[]);
+        match sub_pass.dispatch {
+          Dispatch::Direct(x, y, z) => pass.dispatch_workgroups(x, y, z),
+          Dispatch::Indirect(buffer) => pass.dispatch_workgroups_indirect(buffer, 0),
+        }
+        pass.pop_debug_group();
+      }
+    }
+    drop(pass);
+    if let Some(timer) = timer {
+      timer.resolve(render_context.command_encoder());
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The offsets of `fields` in [`Uniforms`], by name.
+  macro_rules! offsets {
+    ($($field:ident),* $(,)?) => {
+      [$((stringify!($field), std::mem::offset_of!(Uniforms, $field) as u32)),*]
+    };
+  }
+
+  #[test]
+  fn uniforms_match_the_shader_layout() {
+    // The struct is free of shader defs, so it parses on its own.
+    let shader = include_str!("../assets/mandelbrot.wgsl");
+    let start = shader.find("struct Uniforms {").unwrap();
+    let end = start + shader[start..].find("\n}").unwrap() + 2;
+    let module = naga::front::wgsl::parse_str(&shader[start..end]).unwrap();
+    let (members, span) = module
+      .types
+      .iter()
+      .find_map(|(_, ty)| match &ty.inner {
+        naga::TypeInner::Struct { members, span } => Some((members, *span)),
+        _ => None,
+      })
+      .unwrap();
+
+    let shader_offsets: Vec<_> = members
+      .iter()
+      .map(|member| (member.name.as_deref().unwrap(), member.offset))
+      .collect();
+    let offsets = offsets!(
+      time,
+      iteration_storage,
+      coloring_mode,
+      mapping_curve,
+      shading,
+      light_angle,
+      center,
+      scale,
+      non_finite,
+      alpha_mode,
+      rotation,
+      non_finite_color,
+      boundary,
+      boundary_width,
+      dispatch_order,
+      previous_mapping_curve,
+      previous_shading,
+      previous_boundary,
+      transition,
+      glow,
+      glow_color,
+      glow_radius,
+      warp_amp,
+      warp_freq,
+      pixel_aspect,
+      channel_phase,
+      mode,
+      julia_c,
+      use_symmetry,
+      max_iterations,
+      history_offset,
+      history_blend,
+      history_parity,
+      iteration_cue,
+      iteration_cue_strength,
+      frame,
+      blend_mode,
+      blend_mix,
+      projection,
+      sky_end,
+      eye_separation,
+      sky_color,
+      sky_edge_color,
+    );
+    assert_eq!(shader_offsets, offsets);
+    assert_eq!(size_of::<Uniforms>() as u32, span);
+  }
+
+  #[test]
+  fn pausing_holds_the_clock() {
+    let mut clock = AnimationClock::default();
+    clock.advance(0.5);
+    clock.paused = true;
+    clock.advance(0.25);
+    assert_eq!(clock.elapsed, 0.5);
+    clock.paused = false;
+    clock.advance(0.25);
+    assert_eq!(clock.elapsed, 0.75);
+  }
+
+  #[test]
+  fn orders_every_pass() {
+    use SubPass::*;
+    assert_eq!(
+      pass_order(true, Some(()), Some(())),
+      [Iterate, Detail(()), Orbits(()), Recolor]
+    );
+  }
+
+  #[test]
+  fn frozen_frames_only_recolor() {
+    assert_eq!(pass_order(false, Some(()), None), [SubPass::Recolor]);
+  }
+
+  #[test]
+  fn plain_frames_iterate_and_recolor() {
+    assert_eq!(
+      pass_order::<()>(true, None, None),
+      [SubPass::Iterate, SubPass::Recolor]
+    );
+  }
+}
@@ -1,68 +1,278 @@
-use std::{
-  borrow::Cow,
-  sync::atomic::{AtomicBool, Ordering},
-};
+use std::{fs, process, str::FromStr};
 
 use bevy::{
   DefaultPlugins,
-  app::{App, Plugin, Startup},
-  asset::{AssetMetaCheck, AssetMode, AssetPlugin, AssetServer, Assets, Handle, RenderAssetUsages},
+  app::{App, AppExit, Startup, Update},
+  asset::{AssetMetaCheck, AssetMode, AssetPlugin},
   camera::Camera2d,
-  color::Color,
+  color::{Color, LinearRgba, Srgba},
   ecs::{
+    event::{EventReader, EventWriter},
     resource::Resource,
-    schedule::IntoScheduleConfigs,
-    system::{Commands, Res, ResMut},
-    world::World,
+    system::{Commands, Query, Res, ResMut},
   },
-  image::Image,
-  log::info,
   math::{Vec2, Vec3},
   prelude::{PluginGroup, default},
-  render::{
-    Render, RenderApp, RenderStartup, RenderSystems,
-    camera::ClearColor,
-    extract_resource::{ExtractResource, ExtractResourcePlugin},
-    render_asset::RenderAssets,
-    render_graph::{self, RenderGraph, RenderLabel},
-    render_resource::{
-      BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Buffer,
-      BufferInitDescriptor, BufferUsages, CachedComputePipelineId, CachedPipelineState,
-      ComputePassDescriptor, ComputePipelineDescriptor, MapMode, PipelineCache, PollType,
-      ShaderStages, ShaderType, StorageTextureAccess, TextureFormat, TextureUsages,
-      binding_types::{texture_storage_2d, uniform_buffer},
-    },
-    renderer::{RenderContext, RenderDevice},
-    texture::GpuImage,
-    view::Msaa,
-  },
-  shader::PipelineCacheError,
+  render::{camera::ClearColor, view::Msaa},
   sprite::Sprite,
   transform::components::Transform,
-  window::{Window, WindowPlugin},
+  window::{Window, WindowPlugin, WindowResized},
+};
+use mandelbrot::{
+  BlendMode, ColoringMode, DISPLAY_FACTOR, DispatchOrder, DisplayFilter, IterationStorage,
+  MandelbrotComputePlugin, MandelbrotImages, Projection, RenderConfig,
+  autoiterations::AutoIterations,
+  error::MandelbrotFailure,
+  export::ExrExport,
+  hold::Hold,
+  keybinds::{Action, ActionInput, Keybinds},
+  kfr::parse_kfr,
+  mesh::ObjExport,
+  navigation::{Acceleration, Navigation, PixelScroll},
+  palette::Sky,
+  screensaver::Screensaver,
+  transfer::Transfer,
+  transition::Crossfade,
 };
-use bytemuck::{Pod, Zeroable, bytes_of};
-
-const SHADER_ASSET_PATH: &str = "mandelbrot.wgsl";
 
-const DISPLAY_FACTOR: u32 = 1;
-const SIZE: (u32, u32) = (1280 / DISPLAY_FACTOR, 720 / DISPLAY_FACTOR);
-const WORKGROUP_SIZE: u32 = 8;
+fn main() {
+  // Compact integer iteration storage trades smooth coloring for half the
+  // iteration buffer memory.
+  let iteration_storage = if std::env::args().any(|arg| arg == "--integer-iterations") {
+    IterationStorage::Integer
+  } else {
+    IterationStorage::Smooth
+  };
+  let display_filter = if std::env::args().any(|arg| arg == "--nearest") {
+    DisplayFilter::Nearest
+  } else {
+    DisplayFilter::Linear
+  };
+  // Z-order dispatch can be faster on large textures.
+  let dispatch_order = if std::env::args().any(|arg| arg == "--morton") {
+    DispatchOrder::Morton
+  } else {
+    DispatchOrder::Linear
+  };
+  // Smooth, banded, angle or orbit average coloring, or the raw distance
+  // estimate.
+  let coloring_mode = match flag_value("--coloring").as_deref() {
+    Some("banded") => ColoringMode::Banded,
+    Some("angle") => ColoringMode::Angle,
+    Some("average") => ColoringMode::Average,
+    Some("distance") => ColoringMode::Distance,
+    _ => ColoringMode::Smooth,
+  };
+  // Start in log-polar coordinates about the center.
+  let projection = if std::env::args().any(|arg| arg == "--log-polar") {
+    Projection::LogPolar
+  } else {
+    Projection::Cartesian
+  };
+  // Finish points still iterating partway in a second, indirect pass.
+  let detail_pass = std::env::args().any(|arg| arg == "--detail-pass");
+  // Iterate at most this many workgroups per frame, e.g.
+  // `--compute-budget 256`, filling deep views in over several frames.
+  let compute_budget = parsed_arg("--compute-budget");
+  // Keep orbits between frames and resume them when the iteration cap grows.
+  let warm_start = std::env::args().any(|arg| arg == "--warm-start");
+  // Cycle the palette over time. Space pauses the cycle on a frame.
+  let color_cycle = std::env::args().any(|arg| arg == "--color-cycle");
+  // Keep rendering while the window is in the background.
+  let continuous = std::env::args().any(|arg| arg == "--continuous");
+  // Switch coloring styles instantly instead of crossfading.
+  let crossfade = if std::env::args().any(|arg| arg == "--no-crossfade") {
+    Crossfade { duration: 0. }
+  } else {
+    Crossfade::default()
+  };
+  let mut navigation = Navigation::default();
+  // Pan arbitrarily far from the set.
+  if std::env::args().any(|arg| arg == "--free-pan") {
+    navigation = navigation.with_pan_limit(None);
+  }
+  // Zoom with pixel-precise scrolling too, for mice that report it.
+  if std::env::args().any(|arg| arg == "--scroll-zoom") {
+    navigation = navigation.with_pixel_scroll(PixelScroll::Zoom);
+  }
+  // How fast scroll-wheel zooms ease in, e.g. `--scroll-damping 10`, or 0
+  // to zoom at once.
+  if let Some(damping) = parsed_arg("--scroll-damping") {
+    navigation = navigation.with_scroll_damping(damping);
+  }
+  // Zoom past f32 precision, into blocky territory.
+  if std::env::args().any(|arg| arg == "--unlimited-zoom") {
+    navigation = navigation.with_zoom_limit(false);
+  }
+  // Move at constant rates however long keys are held, or speed up to
+  // `--max-boost` times the base rate.
+  if std::env::args().any(|arg| arg == "--constant-speed") {
+    navigation = navigation.with_acceleration(None);
+  } else if let Some(max_boost) = parsed_arg("--max-boost") {
+    navigation =
+      navigation.with_acceleration(Some(Acceleration { max_boost, ..Acceleration::default() }));
+  }
+  // Speed multipliers while the slow and fast modifiers are held, e.g.
+  // `--slow-factor 0.1 --fast-factor 8`.
+  let slow_factor = parsed_arg("--slow-factor");
+  let fast_factor = parsed_arg("--fast-factor");
+  navigation = navigation.with_speed_factors(
+    slow_factor.unwrap_or(navigation.slow_factor),
+    fast_factor.unwrap_or(navigation.fast_factor),
+  );
+  // Render the view once and hold it, e.g. as a wallpaper, at an iteration
+  // cap of `--static-iterations` if given.
+  let hold = std::env::args().any(|arg| arg == "--static").then(|| Hold {
+    max_iterations: parsed_arg("--static-iterations"),
+  });
+  // Drift through the set after a minute without input, or after
+  // `--screensaver-timeout` seconds. Kiosks showing a fixed view turn it off,
+  // and a held view never drifts.
+  let screensaver = (!std::env::args().any(|arg| arg == "--no-screensaver") && hold.is_none())
+    .then(|| {
+      let screensaver = Screensaver::default();
+      match parsed_arg("--screensaver-timeout") {
+        Some(timeout) => screensaver.with_timeout(timeout),
+        None => screensaver,
+      }
+    });
+  // Adapt the iteration cap to the view, keeping the fraction of escapes
+  // near it at `--auto-iterations-target`, 0.01 by default.
+  let auto_iterations = std::env::args()
+    .any(|arg| arg == "--auto-iterations")
+    .then(|| {
+      let auto_iterations = AutoIterations::default();
+      match parsed_arg("--auto-iterations-target") {
+        Some(target) => auto_iterations.with_target(target),
+        None => auto_iterations,
+      }
+    });
+  // Read view updates as JSON lines from stdin.
+  let stdin_control = std::env::args().any(|arg| arg == "--stdin");
+  let defaults = RenderConfig::default();
+  // Texture dimensions, e.g. `--size 1920x1080`.
+  let size = size_arg().unwrap_or(defaults.size);
+  // Width of a pixel relative to its height on the target display.
+  let pixel_aspect = parsed_arg("--pixel-aspect").unwrap_or(defaults.pixel_aspect);
+  // Weight of the previous frame while panning, e.g. `--temporal-blend 0.5`.
+  let temporal_blend = parsed_arg("--temporal-blend").unwrap_or(defaults.temporal_blend);
+  // Strength of the iteration cue, e.g. `--iteration-cue 0.3`.
+  let iteration_cue = parsed_arg("--iteration-cue").unwrap_or(defaults.iteration_cue);
+  // Blend the Julia set of the view's constant over it, e.g.
+  // `--blend screen`, weighted by `--blend-mix`.
+  let blend = match flag_value("--blend").as_deref() {
+    Some("add") => Some(BlendMode::Add),
+    Some("multiply") => Some(BlendMode::Multiply),
+    Some("screen") => Some(BlendMode::Screen),
+    _ => None,
+  };
+  let blend_mix = parsed_arg("--blend-mix").unwrap_or(defaults.blend_mix);
+  let workgroup_size = parsed_arg("--workgroup-size").unwrap_or(defaults.workgroup_size);
+  // Frame rate while nothing on screen changes, e.g. `--max-fps 30`.
+  let max_idle_fps = parsed_arg("--max-fps");
+  // A custom compute shader, e.g. `--shader my_fractal.wgsl` from `assets/`.
+  let shader = flag_value("--shader");
+  let background = color_arg("--background")
+    .map(Color::from)
+    .unwrap_or(Color::BLACK);
+  // Color the far exterior apart from the palette, up to a fraction of the
+  // range, e.g. `--sky 0.1 --sky-color 0b1026`, fading to `--sky-edge`.
+  let sky = parsed_arg("--sky").map(|end| {
+    let color = color_arg("--sky-color").map_or(Sky::default().color, LinearRgba::from);
+    let edge_color = color_arg("--sky-edge").map_or(color, LinearRgba::from);
+    Sky { end, color, edge_color }
+  });
+  // Map escape iterations to the palette with an expression of `n`, e.g.
+  // `--transfer "sqrt(n) * 3 + sin(n * 0.1)"`, in place of the mapping
+  // curve. An invalid expression leaves the curve in place.
+  let transfer = flag_value("--transfer").and_then(|source| {
+    Transfer::parse(&source)
+      .map_err(|err| eprintln!("Invalid --transfer expression \"{source}\": {err}"))
+      .ok()
+  });
+  // Render a view per eye into a texture array, the eyes a fraction of the
+  // view height apart, e.g. `--stereo 0.03`. The window shows the left eye.
+  let stereo = parsed_arg("--stereo");
+  // Keep the window at a fixed aspect ratio while resizing, e.g.
+  // `--lock-aspect 16:9`.
+  let aspect_lock = aspect_arg();
+  // Writes the raw iteration data of the first frame and exits. A held frame
+  // stays on screen after the export unless `--exit` is passed too.
+  let exit = hold.is_none() || std::env::args().any(|arg| arg == "--exit");
+  // `--export-report` also summarizes the escape iterations of the export
+  // in a JSON file next to it, to help pick an iteration cap.
+  let report = std::env::args().any(|arg| arg == "--export-report");
+  let exr_export =
+    flag_value("--export-exr").map(|path| ExrExport { path: path.into(), exit, report });
+  let obj_export = flag_value("--export-obj").map(|path| {
+    let mut export = ObjExport { exit, ..ObjExport::new(path) };
+    if let Some(height) = parsed_arg("--obj-height") {
+      export.height = height;
+    }
+    if let Some(step) = parsed_arg("--obj-step") {
+      export.step = step;
+    }
+    export
+  });
 
-#[derive(Resource, Clone, Copy, Pod, Zeroable, ShaderType)]
-#[repr(C)]
-struct Uniforms {
-  time: u32,
-}
+  let mut mandelbrot = MandelbrotComputePlugin::default()
+    .with_size(size)
+    .with_pixel_aspect(pixel_aspect)
+    .with_workgroup_size(workgroup_size)
+    .with_temporal_blend(temporal_blend)
+    .with_iteration_cue(iteration_cue)
+    .with_blend(blend, blend_mix)
+    .with_iteration_storage(iteration_storage)
+    .with_coloring_mode(coloring_mode)
+    .with_display_filter(display_filter)
+    .with_dispatch_order(dispatch_order)
+    .with_projection(projection)
+    .with_sky(sky)
+    .with_stereo(stereo)
+    .with_detail_pass(detail_pass)
+    .with_compute_budget(compute_budget)
+    .with_warm_start(warm_start)
+    .with_color_cycle(color_cycle)
+    .with_pause_unfocused(!continuous)
+    .with_max_idle_fps(max_idle_fps)
+    .with_stdin_control(stdin_control);
+  if let Some(shader) = shader {
+    mandelbrot = mandelbrot.with_shader(shader);
+  }
+  // Start at the location of a Kalles Fraktaler parameter file, e.g.
+  // `--import location.kfr`. Everything but the location stays as the other
+  // flags set it.
+  if let Some(path) = flag_value("--import") {
+    let view = fs::read_to_string(&path)
+      .map_err(|err| err.to_string())
+      .and_then(|text| {
+        parse_kfr(&text, mandelbrot.view(), mandelbrot.config()).map_err(|err| err.to_string())
+      });
+    let view = view.unwrap_or_else(|err| {
+      eprintln!("Couldn't import {path}: {err}");
+      process::exit(1);
+    });
+    mandelbrot = mandelbrot.with_view(view);
+  }
 
-fn main() {
-  App::new()
-    .insert_resource(ClearColor(Color::BLACK))
+  let width = (size.0 * DISPLAY_FACTOR) as f32;
+  let window_size = match &aspect_lock {
+    Some(AspectLock(ratio)) => (width, width / ratio),
+    None => (width, (size.1 * DISPLAY_FACTOR) as f32),
+  };
+
+  let mut app = App::new();
+  app
+    .insert_resource(ClearColor(background))
+    .insert_resource(Backgrounds::new(background))
+    .insert_resource(Keybinds::load("keybinds.toml"))
+    .insert_resource(crossfade)
+    .insert_resource(navigation)
     .add_plugins(
       DefaultPlugins
         .set(WindowPlugin {
           primary_window: Some(Window {
-            resolution: (1280., 720.).into(),
+            resolution: window_size.into(),
             ..default()
           }),
           ..default()
@@ -73,249 +283,165 @@ fn main() {
           ..default()
         }),
     )
-    .add_plugins(MandelbrotComputePlugin)
+    .add_plugins(mandelbrot)
     .add_systems(Startup, setup)
-    .run();
+    .add_systems(Update, (cycle_background, exit_on_failure));
+  if let Some(export) = exr_export {
+    app.insert_resource(export);
+  }
+  if let Some(export) = obj_export {
+    app.insert_resource(export);
+  }
+  if let Some(screensaver) = screensaver {
+    app.insert_resource(screensaver);
+  }
+  if let Some(hold) = hold {
+    app.insert_resource(hold);
+  }
+  if let Some(transfer) = transfer {
+    app.insert_resource(transfer);
+  }
+  if let Some(auto_iterations) = auto_iterations {
+    app.insert_resource(auto_iterations);
+  }
+  if let Some(aspect_lock) = aspect_lock {
+    app
+      .insert_resource(aspect_lock)
+      .add_systems(Update, lock_aspect);
+  }
+  app.run();
 }
 
-fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
-  let mut image = Image::new_target_texture(SIZE.0, SIZE.1, TextureFormat::Rgba32Float);
-  image.asset_usage = RenderAssetUsages::RENDER_WORLD;
-  image.texture_descriptor.usage =
-    TextureUsages::COPY_DST | TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING;
-  let image = images.add(image);
-
-  commands.spawn((
-    Sprite {
-      image: image.clone(),
-      custom_size: Some(Vec2::new(SIZE.0 as f32, SIZE.1 as f32)),
-      ..default()
-    },
-    Transform::from_scale(Vec3::splat(DISPLAY_FACTOR as f32)),
-  ));
-  commands.spawn((Msaa::Off, Camera2d));
-
-  commands.insert_resource(MandelbrotImages { texture: image });
+/// The argument following `flag`, if the flag was passed.
+fn flag_value(flag: &str) -> Option<String> {
+  let mut args = std::env::args().skip_while(|arg| arg != flag);
+  args.next()?;
+  let value = args.next();
+  if value.is_none() {
+    eprintln!("{flag} needs a value");
+  }
+  value
 }
 
-#[derive(Resource, Clone, ExtractResource)]
-struct MandelbrotImages {
-  texture: Handle<Image>,
+/// Parses the value following `flag`, e.g. `--max-fps 30`.
+fn parsed_arg<T: FromStr>(flag: &str) -> Option<T> {
+  let value = flag_value(flag)?;
+  let parsed = value.parse().ok();
+  if parsed.is_none() {
+    eprintln!("Invalid value {value:?} for {flag}");
+  }
+  parsed
 }
 
-#[derive(Resource)]
-struct MandelbrotImageBindGroups(BindGroup);
-
-fn prepare_bind_group(
-  mut commands: Commands,
-  pipeline: Res<MandelbrotPipeline>,
-  gpu_images: Res<RenderAssets<GpuImage>>,
-  game_of_life_images: Res<MandelbrotImages>,
-  render_device: Res<RenderDevice>,
-  mut uniform_data: ResMut<Uniforms>,
-) {
-  uniform_data.time += 1;
-
-  let view = gpu_images.get(&game_of_life_images.texture).unwrap();
-  let bind_group_0 = render_device.create_bind_group(
-    None,
-    &pipeline.texture_bind_group_layout,
-    &BindGroupEntries::sequential((
-      &view.texture_view,
-      pipeline.uniform_buffer.as_entire_buffer_binding(),
-    )),
-  );
-  commands.insert_resource(MandelbrotImageBindGroups(bind_group_0));
+/// Parses the `WIDTHxHEIGHT` following `--size`.
+fn size_arg() -> Option<(u32, u32)> {
+  let value = flag_value("--size")?;
+  let size = value
+    .split_once('x')
+    .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+    .filter(|&(width, height)| width > 0 && height > 0);
+  if size.is_none() {
+    eprintln!("Invalid size {value:?}, expected WIDTHxHEIGHT");
+  }
+  size
 }
 
-fn update_uniforms(
-  pipeline: Res<MandelbrotPipeline>,
-  uniform_data: Res<Uniforms>,
-  render_device: Res<RenderDevice>,
-) {
-  static MAPPED: AtomicBool = AtomicBool::new(false);
-
-  let uniform_data = *uniform_data;
-  let buffer = pipeline.mapped_uniform_buffer.clone();
-  if MAPPED.swap(true, Ordering::SeqCst) {
-    return;
+/// Parses the `WIDTH:HEIGHT` ratio following `--lock-aspect`.
+fn aspect_arg() -> Option<AspectLock> {
+  let value = flag_value("--lock-aspect")?;
+  let ratio = value
+    .split_once(':')
+    .and_then(|(width, height)| Some(width.parse::<f32>().ok()? / height.parse::<f32>().ok()?))
+    .filter(|ratio| ratio.is_finite() && *ratio > 0.);
+  if ratio.is_none() {
+    eprintln!("Invalid aspect ratio {value:?}, expected WIDTH:HEIGHT");
   }
-
-  // Maps the buffer so it can be read on the cpu
-  pipeline
-    .mapped_uniform_buffer
-    .slice(..)
-    .map_async(MapMode::Write, move |r| match r {
-      // This will execute once the gpu is ready, so after the call to poll()
-      Ok(_) => {
-        buffer
-          .slice(..)
-          .get_mapped_range_mut()
-          .copy_from_slice(bytes_of(&uniform_data));
-
-        buffer.unmap();
-        MAPPED.store(false, Ordering::SeqCst);
-      }
-      Err(err) => panic!("Failed to map buffer {err}"),
-    });
-
-  render_device
-    .poll(PollType::Wait)
-    .expect("Failed to wait for render device");
+  ratio.map(AspectLock)
 }
 
-struct MandelbrotComputePlugin;
-
-#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
-struct MandelbrotLabel;
-
-impl Plugin for MandelbrotComputePlugin {
-  fn build(&self, app: &mut App) {
-    app.add_plugins(ExtractResourcePlugin::<MandelbrotImages>::default());
-    let render_app = app.sub_app_mut(RenderApp);
-    render_app
-      .add_systems(RenderStartup, init_mandelbrot_pipeline)
-      .add_systems(
-        Render,
-        (
-          prepare_bind_group.in_set(RenderSystems::PrepareBindGroups),
-          update_uniforms.after(RenderSystems::Render),
-        ),
-      );
-
-    let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
-    render_graph.add_node(MandelbrotLabel, MandelbrotNode::default());
-    render_graph.add_node_edge(MandelbrotLabel, bevy::render::graph::CameraDriverLabel);
+/// Parses the hex color following `flag`, e.g. `--background 1e1e2e`.
+fn color_arg(flag: &str) -> Option<Srgba> {
+  let hex = flag_value(flag)?;
+  match Srgba::hex(&hex) {
+    Ok(color) => Some(color),
+    Err(err) => {
+      eprintln!("Invalid color {hex:?} for {flag}: {err}");
+      None
+    }
   }
 }
 
+/// Clear colors cycled through with [`Action::CycleBackground`]. The clear
+/// color shows through transparent parts of the sprite.
 #[derive(Resource)]
-struct MandelbrotPipeline {
-  texture_bind_group_layout: BindGroupLayout,
-  checker_board_pipeline: CachedComputePipelineId,
-  uniform_buffer: Buffer,
-  mapped_uniform_buffer: Buffer,
+struct Backgrounds {
+  colors: Vec<Color>,
+  current: usize,
 }
 
-fn init_mandelbrot_pipeline(
-  mut commands: Commands,
-  render_device: Res<RenderDevice>,
-  asset_server: Res<AssetServer>,
-  pipeline_cache: Res<PipelineCache>,
-) {
-  let uniforms = Uniforms { time: 0 };
-  let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
-    label: Some("Uniforms"),
-    contents: bytes_of(&uniforms),
-    usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-  });
-  let mapped_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
-    label: Some("Mapped uniforms"),
-    contents: bytes_of(&uniforms),
-    usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
-  });
-  commands.insert_resource(uniforms);
-
-  let texture_bind_group_layout = render_device.create_bind_group_layout(
-    "Mandelbrot",
-    &BindGroupLayoutEntries::sequential(
-      ShaderStages::COMPUTE,
-      (
-        texture_storage_2d(TextureFormat::Rgba32Float, StorageTextureAccess::WriteOnly),
-        uniform_buffer::<Uniforms>(false),
-      ),
-    ),
-  );
-
-  let shader = asset_server.load(SHADER_ASSET_PATH);
-  let checker_board_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
-    layout: vec![texture_bind_group_layout.clone()],
-    shader: shader,
-    entry_point: Some(Cow::from("checker_board")),
-    ..default()
-  });
-
-  commands.insert_resource(MandelbrotPipeline {
-    texture_bind_group_layout,
-    checker_board_pipeline,
-    uniform_buffer: buffer,
-    mapped_uniform_buffer: mapped_buffer,
-  });
-}
-
-enum MandelbrotState {
-  Loading,
-  Update,
+impl Backgrounds {
+  /// Starts on `initial`, followed by a few fixed presets.
+  fn new(initial: Color) -> Self {
+    let mut colors = vec![initial];
+    for preset in [Color::BLACK, Color::WHITE, Color::srgb(0.5, 0.5, 0.5)] {
+      if !colors.contains(&preset) {
+        colors.push(preset);
+      }
+    }
+    Self { colors, current: 0 }
+  }
 }
 
-struct MandelbrotNode {
-  state: MandelbrotState,
+fn cycle_background(
+  input: ActionInput,
+  mut backgrounds: ResMut<Backgrounds>,
+  mut clear_color: ResMut<ClearColor>,
+) {
+  if input.just_pressed(Action::CycleBackground) {
+    backgrounds.current = (backgrounds.current + 1) % backgrounds.colors.len();
+    clear_color.0 = backgrounds.colors[backgrounds.current];
+  }
 }
 
-impl Default for MandelbrotNode {
-  fn default() -> Self {
-    Self { state: MandelbrotState::Loading }
+/// Exits with an error status once rendering fails. The library has already
+/// logged what went wrong.
+fn exit_on_failure(mut failures: EventReader<MandelbrotFailure>, mut exit: EventWriter<AppExit>) {
+  if failures.read().count() > 0 {
+    exit.write(AppExit::error());
   }
 }
 
-impl render_graph::Node for MandelbrotNode {
-  fn update(&mut self, world: &mut World) {
-    let pipeline = world.resource::<MandelbrotPipeline>();
-    let pipeline_cache = world.resource::<PipelineCache>();
-
-    // if the corresponding pipeline has loaded, transition to the next stage
-    match self.state {
-      MandelbrotState::Loading => {
-        match pipeline_cache.get_compute_pipeline_state(pipeline.checker_board_pipeline) {
-          CachedPipelineState::Ok(_) => {
-            self.state = MandelbrotState::Update;
-          }
-          // If the shader hasn't loaded yet, just wait.
-          CachedPipelineState::Err(PipelineCacheError::ShaderNotLoaded(_)) => {}
-          CachedPipelineState::Err(err) => {
-            panic!("Initializing assets/{SHADER_ASSET_PATH}:\n{err}")
-          }
-          _ => {}
-        }
-      }
-      MandelbrotState::Update => {}
+/// Width over height the window is held at while resizing.
+#[derive(Resource)]
+struct AspectLock(f32);
+
+/// Follows each resize with one to the height that restores the locked
+/// aspect ratio, keeping the width the user chose.
+fn lock_aspect(
+  lock: Res<AspectLock>,
+  mut resized: EventReader<WindowResized>,
+  mut windows: Query<&mut Window>,
+) {
+  for event in resized.read() {
+    let height = event.width / lock.0;
+    // The corrective resize comes back as an event too, within rounding.
+    if (event.height - height).abs() < 1. {
+      continue;
     }
-  }
-
-  fn run(
-    &self,
-    _graph: &mut render_graph::RenderGraphContext,
-    render_context: &mut RenderContext,
-    world: &World,
-  ) -> Result<(), render_graph::NodeRunError> {
-    let bind_group = &world.resource::<MandelbrotImageBindGroups>().0;
-    let pipeline_cache = world.resource::<PipelineCache>();
-    let pipeline = world.resource::<MandelbrotPipeline>();
-
-    render_context.command_encoder().copy_buffer_to_buffer(
-      &pipeline.mapped_uniform_buffer,
-      0,
-      &pipeline.uniform_buffer,
-      0,
-      size_of::<Uniforms>() as u64,
-    );
-
-    let mut pass = render_context
-      .command_encoder()
-      .begin_compute_pass(&ComputePassDescriptor::default());
-
-    match self.state {
-      MandelbrotState::Loading => {}
-      MandelbrotState::Update => {
-        let checker_board_pipeline = pipeline_cache
-          .get_compute_pipeline(pipeline.checker_board_pipeline)
-          .unwrap();
-        pass.set_bind_group(0, bind_group, &[]);
-        pass.set_pipeline(checker_board_pipeline);
-        pass.dispatch_workgroups(SIZE.0 / WORKGROUP_SIZE, SIZE.1 / WORKGROUP_SIZE, 1);
-      }
+    if let Ok(mut window) = windows.get_mut(event.window) {
+      window.resolution.set(event.width, height);
     }
-
-    Ok(())
   }
 }
+
+fn setup(mut commands: Commands, config: Res<RenderConfig>, images: Res<MandelbrotImages>) {
+  commands.spawn((
+    Sprite {
+      image: images.texture().clone(),
+      custom_size: Some(Vec2::new(config.size.0 as f32, config.size.1 as f32)),
+      ..default()
+    },
+    Transform::from_scale(Vec3::splat(DISPLAY_FACTOR as f32)),
+  ));
+  commands.spawn((Msaa::Off, Camera2d));
+}
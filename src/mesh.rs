@@ -0,0 +1,169 @@
+//! Exporting the iteration data of a frame as a height field mesh.
+//!
+//! An [`ObjExport`] reads back the next complete frame like an EXR export
+//! does, and writes the smooth escape iteration of each sampled texel as the
+//! height of a grid of triangles in a Wavefront OBJ file, for 3D printing or
+//! rendering elsewhere.
+//!
+//! The mesh spans the view with a height of 1 unit and its width in
+//! proportion, x right and y up. Escaped points rise with the logarithm of
+//! their escape iteration, so the terrain climbs steeply towards the set,
+//! and points inside the set form a flat plateau at [`ObjExport::height`].
+
+use std::{
+  fs::File,
+  io::{self, BufWriter, Write},
+  path::PathBuf,
+};
+
+use bevy::{
+  app::{App, AppExit, Plugin, Update},
+  ecs::{
+    event::{EventReader, EventWriter},
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Commands, Res, ResMut},
+  },
+  log::{error, info},
+};
+
+use crate::{
+  RenderConfig, Uniforms,
+  export::{self, FrameReadback, ReadbackRequest},
+};
+
+/// Requests that the next complete frame be written to an OBJ mesh. The
+/// request is removed once the file is written.
+#[derive(Resource, Clone, Debug)]
+pub struct ObjExport {
+  pub path: PathBuf,
+  /// Height of the plateau inside the set, relative to the view height.
+  pub height: f32,
+  /// Spacing of the sampled texels, 1 for a vertex per texel. Larger steps
+  /// decimate the mesh to a manageable size.
+  pub step: u32,
+  /// Exit the app once the file is written.
+  pub exit: bool,
+}
+
+impl ObjExport {
+  /// An export to `path` with a plateau a tenth of the view height and a
+  /// vertex every fourth texel.
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    Self {
+      path: path.into(),
+      height: 0.1,
+      step: 4,
+      exit: false,
+    }
+  }
+}
+
+pub(crate) struct MeshExportPlugin;
+
+impl Plugin for MeshExportPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      Update,
+      (
+        request_obj_export.before(export::receive_readbacks),
+        write_obj_export.after(export::receive_readbacks),
+      ),
+    );
+  }
+}
+
+fn request_obj_export(export: Option<Res<ObjExport>>, mut request: ResMut<ReadbackRequest>) {
+  if export.is_some_and(|export| export.is_added()) {
+    request.request();
+  }
+}
+
+fn write_obj_export(
+  mut commands: Commands,
+  mut readbacks: EventReader<FrameReadback>,
+  export: Option<Res<ObjExport>>,
+  config: Res<RenderConfig>,
+  mut exit: EventWriter<AppExit>,
+) {
+  let Some(readback) = readbacks.read().last() else {
+    return;
+  };
+  let Some(export) = export else {
+    return;
+  };
+
+  match write_obj(&export, &config, &readback.uniforms, &readback.iterations) {
+    Ok(()) => info!("Exported height field mesh to {}", export.path.display()),
+    Err(err) => error!("Failed to write {}: {err}", export.path.display()),
+  }
+
+  commands.remove_resource::<ObjExport>();
+  if export.exit {
+    exit.write(AppExit::Success);
+  }
+}
+
+/// Height of a texel with escape iteration `d`, from 0 to 1.
+fn height(d: f32, max_iterations: u32) -> f32 {
+  if d == -1. {
+    // Inside the set.
+    1.
+  } else if d < 0. {
+    // Highlighted non-finite orbits.
+    0.
+  } else {
+    ((1. + d).ln() / (1. + max_iterations as f32).ln()).min(1.)
+  }
+}
+
+fn write_obj(
+  export: &ObjExport,
+  config: &RenderConfig,
+  uniforms: &Uniforms,
+  iterations: &[f32],
+) -> io::Result<()> {
+  let (width, height_texels) = (config.size.0 as usize, config.size.1 as usize);
+  let step = export.step.max(1) as usize;
+  // Always include the last row and column, so the mesh covers the view.
+  let samples = |len: usize| {
+    let mut samples: Vec<usize> = (0..len).step_by(step).collect();
+    if samples.last() != Some(&(len - 1)) {
+      samples.push(len - 1);
+    }
+    samples
+  };
+  let (columns, rows) = (samples(width), samples(height_texels));
+
+  let mut out = BufWriter::new(File::create(&export.path)?);
+  writeln!(
+    out,
+    "# Mandelbrot height field, center {} {:+}i, scale {}",
+    uniforms.center.x, uniforms.center.y, uniforms.scale
+  )?;
+  let texel = 1. / height_texels as f32;
+  for &y in &rows {
+    for &x in &columns {
+      let d = iterations[y * width + x];
+      writeln!(
+        out,
+        "v {} {} {}",
+        (x as f32 - width as f32 / 2.) * texel * uniforms.pixel_aspect,
+        (height_texels as f32 / 2. - y as f32) * texel,
+        export.height * height(d, uniforms.max_iterations)
+      )?;
+    }
+  }
+  // Two triangles per cell, wound counterclockwise seen from above. OBJ
+  // indices start at 1.
+  let vertex = |row: usize, column: usize| row * columns.len() + column + 1;
+  for row in 0..rows.len() - 1 {
+    for column in 0..columns.len() - 1 {
+      let (top_left, top_right) = (vertex(row, column), vertex(row, column + 1));
+      let (bottom_left, bottom_right) = (vertex(row + 1, column), vertex(row + 1, column + 1));
+      writeln!(out, "f {top_left} {bottom_left} {bottom_right}")?;
+      writeln!(out, "f {top_left} {bottom_right} {top_right}")?;
+    }
+  }
+  out.flush()
+}
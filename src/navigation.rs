@@ -0,0 +1,601 @@
+//! Pan and zoom controls.
+//!
+//! Continuous motion is scaled by the frame's delta time, so navigation speed
+//! in complex-plane units per second is independent of the frame rate: held
+//! keys move the view by `speed * dt`, and drag inertia follows the closed
+//! form of exponential decay, which covers the same distance whether a second
+//! is split into 30 frames or 300. Discrete inputs like scroll-wheel ticks are
+//! applied once per event and don't depend on frame timing at all.
+//!
+//! Panning far past the set only finds flat exterior, so by default the view
+//! is pulled back once its center strays past [`Navigation::pan_limit`]. The
+//! pull is a spring rather than a wall, so navigation near the limit never
+//! stops dead.
+//!
+//! Past the depth f32 can resolve the image only turns blocky, so zooming
+//! stops there, with a notice at the bottom of the view, unless
+//! [`Navigation::zoom_limit`] is turned off.
+//!
+//! Scroll-wheel zooms don't jump: each tick moves a target scale, and the
+//! view eases toward it over a few frames at [`Navigation::scroll_damping`],
+//! keeping the point under the cursor fixed, so a flicked wheel can't
+//! overshoot.
+//!
+//! Trackpads report pixel-precise scrolling, which by default pans the view
+//! with the fingers as [`PixelScroll::Pan`] describes, while pinches zoom.
+//! Wheels that scroll by lines zoom as before.
+//!
+//! Holding [`Action::SlowModifier`] (left Shift) slows keyboard, scroll and
+//! drag navigation down for fine control, and holding
+//! [`Action::FastModifier`] (left Ctrl) speeds it up. A modified drag moves
+//! the image by a multiple of the cursor's motion, so the point grabbed
+//! drifts out from under the cursor.
+//!
+//! Held pan and zoom keys also speed up the longer they are held, and zoom
+//! keys zoom faster the deeper the view is, as [`Acceleration`] describes.
+//! A tap still moves the view a little for fine adjustments, while holding
+//! covers long distances and many decades of zoom without switching
+//! controls.
+//!
+//! [`Action::ToggleProjection`] switches between the Cartesian and the
+//! log-polar projection. In log-polar views the center is the pole that
+//! zooms and rotations happen about, so zooming never moves it toward the
+//! cursor, and drags shift the image instead: across to rotate and down to
+//! zoom out. Keyboard panning still moves the pole.
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    event::EventReader,
+    query::With,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Res, ResMut, Single},
+  },
+  input::{
+    ButtonInput,
+    gestures::PinchGesture,
+    mouse::{MouseButton, MouseScrollUnit, MouseWheel},
+  },
+  log::info,
+  math::Vec2,
+  time::Time,
+  window::{PrimaryWindow, Window},
+};
+
+use crate::{
+  DISPLAY_FACTOR, INITIAL_SCALE, Projection, RenderConfig, Uniforms,
+  keybinds::{Action, ActionInput},
+};
+
+/// Scroll distance treated as one wheel line for pixel-precise scrolling.
+const PIXELS_PER_LINE: f32 = 100.;
+
+/// Radius of the disk around the origin that holds the whole set.
+const SET_RADIUS: f32 = 2.;
+
+/// What pixel-precise scrolling, as trackpads report it, does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PixelScroll {
+  /// Move the view with the fingers, as if dragging it. Pinches zoom.
+  #[default]
+  Pan,
+  /// Zoom about the cursor like a scroll wheel, for mice that scroll by
+  /// pixels.
+  Zoom,
+}
+
+/// How held pan and zoom keys speed up.
+#[derive(Clone, Copy, Debug)]
+pub struct Acceleration {
+  /// Speed multiplier reached once a key has been held for `ramp_time`. The
+  /// multiplier grows linearly from 1 until then.
+  pub max_boost: f32,
+  /// Seconds a key is held before it moves at `max_boost`.
+  pub ramp_time: f32,
+  /// How much faster zoom keys zoom per decade of zoom depth: the zoom rate
+  /// is raised to `1 + depth_gain * log10(zoom)`. Zero zooms at the same
+  /// rate at every depth.
+  pub depth_gain: f32,
+}
+
+impl Default for Acceleration {
+  /// Gentle acceleration, tripling the speed over two seconds.
+  fn default() -> Self {
+    Self {
+      max_boost: 3.,
+      ramp_time: 2.,
+      depth_gain: 0.1,
+    }
+  }
+}
+
+impl Acceleration {
+  /// Speed multiplier after holding a key for `held` seconds.
+  fn boost(&self, held: f32) -> f32 {
+    let ramp = if self.ramp_time > 0. {
+      (held / self.ramp_time).min(1.)
+    } else {
+      1.
+    };
+    1. + (self.max_boost - 1.) * ramp
+  }
+}
+
+/// Tuning for the pan and zoom controls.
+#[derive(Resource, Clone, Debug)]
+pub struct Navigation {
+  /// Keyboard pan speed, in view heights per second.
+  pub pan_speed: f32,
+  /// Factor the view shrinks by per second while a zoom key is held.
+  pub zoom_rate: f32,
+  /// Rotation speed while a rotate key is held, in radians per second.
+  pub rotation_speed: f32,
+  /// Factor the view shrinks by per scroll-wheel line.
+  pub scroll_zoom_factor: f32,
+  /// Rate scroll-wheel zooms ease toward their target at, per second: each
+  /// frame covers `1 - exp(-rate * dt)` of the remaining zoom. Zero zooms
+  /// at once.
+  pub scroll_damping: f32,
+  /// What pixel-precise scrolling does.
+  pub pixel_scroll: PixelScroll,
+  /// How fast pinches zoom: the view shrinks by `e` per unit of
+  /// magnification the gesture reports.
+  pub pinch_zoom_rate: f32,
+  /// Factor the view shrinks by per press of a step zoom key.
+  pub step_zoom_factor: f32,
+  /// Decay rate of drag inertia, per second.
+  pub friction: f32,
+  /// How far the center may stray from the origin before it is pulled back,
+  /// in multiples of the set's radius, on top of the view's half-height so a
+  /// zoomed-out view can still frame the set anywhere. `None` pans freely.
+  pub pan_limit: Option<f32>,
+  /// Rate the center is pulled back past the pan limit at, per second.
+  pub pan_spring: f32,
+  /// Speed multiplier while [`Action::SlowModifier`] is held.
+  pub slow_factor: f32,
+  /// Speed multiplier while [`Action::FastModifier`] is held.
+  pub fast_factor: f32,
+  /// Stop zooming in at the depth f32 can still resolve.
+  pub zoom_limit: bool,
+  /// Speed-up of held pan and zoom keys. `None` moves at constant rates.
+  pub acceleration: Option<Acceleration>,
+  /// Seconds the pan keys and the zoom keys have been held without a break.
+  pan_held: f32,
+  zoom_held: f32,
+  /// Whether the view was held at the zoom limit this frame.
+  at_zoom_limit: bool,
+  /// Inertial pan velocity, in complex-plane units per second.
+  velocity: Vec2,
+  /// Cursor position on the previous frame of a drag.
+  drag: Option<Vec2>,
+  /// The scale scroll-wheel zooms are easing toward.
+  zoom_target: Option<ZoomTarget>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ZoomTarget {
+  scale: f32,
+  /// The scale the last easing step left, to notice other controls
+  /// changing it in between.
+  eased: f32,
+}
+
+impl Default for Navigation {
+  fn default() -> Self {
+    Self {
+      pan_speed: 0.5,
+      zoom_rate: 2.,
+      rotation_speed: FRAC_PI_2,
+      scroll_zoom_factor: 1.2,
+      scroll_damping: 20.,
+      pixel_scroll: PixelScroll::default(),
+      pinch_zoom_rate: 1.,
+      step_zoom_factor: 2.,
+      friction: 5.,
+      pan_limit: Some(2.),
+      pan_spring: 8.,
+      slow_factor: 0.2,
+      fast_factor: 4.,
+      zoom_limit: true,
+      acceleration: Some(Acceleration::default()),
+      pan_held: 0.,
+      zoom_held: 0.,
+      at_zoom_limit: false,
+      velocity: Vec2::ZERO,
+      drag: None,
+      zoom_target: None,
+    }
+  }
+}
+
+impl Navigation {
+  /// Replaces the pan limit, e.g. with `None` for unrestricted panning.
+  pub fn with_pan_limit(mut self, pan_limit: Option<f32>) -> Self {
+    self.pan_limit = pan_limit;
+    self
+  }
+
+  /// Sets what pixel-precise scrolling does.
+  pub fn with_pixel_scroll(mut self, pixel_scroll: PixelScroll) -> Self {
+    self.pixel_scroll = pixel_scroll;
+    self
+  }
+
+  /// Sets how fast scroll-wheel zooms ease in, zero to zoom at once.
+  pub fn with_scroll_damping(mut self, scroll_damping: f32) -> Self {
+    self.scroll_damping = scroll_damping;
+    self
+  }
+
+  /// Sets whether zooming in stops at the depth f32 can resolve.
+  pub fn with_zoom_limit(mut self, zoom_limit: bool) -> Self {
+    self.zoom_limit = zoom_limit;
+    self
+  }
+
+  /// Sets the speed multipliers of the slow and fast modifiers.
+  pub fn with_speed_factors(mut self, slow_factor: f32, fast_factor: f32) -> Self {
+    self.slow_factor = slow_factor;
+    self.fast_factor = fast_factor;
+    self
+  }
+
+  /// Replaces the speed-up of held keys, e.g. with `None` for constant
+  /// rates.
+  pub fn with_acceleration(mut self, acceleration: Option<Acceleration>) -> Self {
+    self.acceleration = acceleration;
+    self
+  }
+
+  /// Whether the view is held at the zoom limit.
+  pub fn at_zoom_limit(&self) -> bool {
+    self.at_zoom_limit
+  }
+
+  /// Multiplier for navigation speed under the held modifier keys.
+  fn speed_factor(&self, input: &ActionInput) -> f32 {
+    let mut factor = 1.;
+    if input.pressed(Action::SlowModifier) {
+      factor *= self.slow_factor;
+    }
+    if input.pressed(Action::FastModifier) {
+      factor *= self.fast_factor;
+    }
+    factor
+  }
+}
+
+pub(crate) struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<Navigation>().add_systems(
+      Update,
+      (
+        (
+          (toggle_projection, keyboard_navigation).chain(),
+          (drag_navigation, apply_inertia).chain(),
+          (scroll_navigation, ease_zoom).chain(),
+          pinch_zoom,
+          step_zoom,
+        ),
+        (pull_toward_set, limit_zoom),
+      )
+        .chain(),
+    );
+  }
+}
+
+impl Uniforms {
+  /// Scales the view by `factor` while keeping the complex point `anchor`
+  /// at the same place on screen.
+  fn zoom_about(&mut self, anchor: Vec2, factor: f32) {
+    self.center = anchor + (self.center - anchor) * factor;
+    self.scale *= factor;
+  }
+
+  /// Moves a log-polar view so its image follows a drag by `texels`, growing
+  /// right and down.
+  fn drag_log_polar(&mut self, config: &RenderConfig, texels: Vec2) {
+    let step = self.log_polar_step(config);
+    self.rotation = (self.rotation - texels.x * self.pixel_aspect * step) % TAU;
+    self.scale *= (texels.y * step).exp();
+  }
+}
+
+fn toggle_projection(input: ActionInput, mut uniforms: ResMut<Uniforms>) {
+  if !input.just_pressed(Action::ToggleProjection) {
+    return;
+  }
+
+  let (projection, name) = if uniforms.log_polar() {
+    (Projection::Cartesian, "Cartesian")
+  } else {
+    (Projection::LogPolar, "log-polar")
+  };
+  info!("Switched to the {name} projection");
+  uniforms.projection = projection as u32;
+}
+
+fn keyboard_navigation(
+  input: ActionInput,
+  time: Res<Time>,
+  mut navigation: ResMut<Navigation>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  // Scaling time scales every speed below alike.
+  let dt = time.delta_secs() * navigation.speed_factor(&input);
+
+  let mut direction = Vec2::ZERO;
+  if input.pressed(Action::PanLeft) {
+    direction.x -= 1.;
+  }
+  if input.pressed(Action::PanRight) {
+    direction.x += 1.;
+  }
+  if input.pressed(Action::PanUp) {
+    direction.y += 1.;
+  }
+  if input.pressed(Action::PanDown) {
+    direction.y -= 1.;
+  }
+  let zooming = input.pressed(Action::ZoomIn) || input.pressed(Action::ZoomOut);
+  navigation.pan_held = if direction == Vec2::ZERO {
+    0.
+  } else {
+    navigation.pan_held + time.delta_secs()
+  };
+  navigation.zoom_held = if zooming {
+    navigation.zoom_held + time.delta_secs()
+  } else {
+    0.
+  };
+  let (pan_boost, zoom_boost) = match navigation.acceleration {
+    Some(acceleration) => {
+      // Zoomed out past the initial view, zoom at the base rate.
+      let depth = (INITIAL_SCALE / uniforms.scale).log10().max(0.);
+      (
+        acceleration.boost(navigation.pan_held),
+        acceleration.boost(navigation.zoom_held) * (1. + acceleration.depth_gain * depth),
+      )
+    }
+    None => (1., 1.),
+  };
+
+  // Pan relative to the screen, not the complex axes.
+  let direction = Vec2::from_angle(uniforms.rotation).rotate(direction);
+  let view_height = 2. * uniforms.scale;
+  uniforms.center += direction * navigation.pan_speed * view_height * dt * pan_boost;
+
+  if input.pressed(Action::RotateLeft) {
+    uniforms.rotation = (uniforms.rotation + navigation.rotation_speed * dt) % TAU;
+  }
+  if input.pressed(Action::RotateRight) {
+    uniforms.rotation = (uniforms.rotation - navigation.rotation_speed * dt) % TAU;
+  }
+
+  if input.pressed(Action::ZoomIn) {
+    uniforms.scale /= navigation.zoom_rate.powf(dt * zoom_boost);
+  }
+  if input.pressed(Action::ZoomOut) {
+    uniforms.scale *= navigation.zoom_rate.powf(dt * zoom_boost);
+  }
+}
+
+fn drag_navigation(
+  buttons: Res<ButtonInput<MouseButton>>,
+  input: ActionInput,
+  window: Single<&Window, With<PrimaryWindow>>,
+  config: Res<RenderConfig>,
+  time: Res<Time>,
+  mut navigation: ResMut<Navigation>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let dragging = buttons.pressed(MouseButton::Left);
+  let Some(position) = window.cursor_position().filter(|_| dragging) else {
+    navigation.drag = None;
+    return;
+  };
+
+  let speed = navigation.speed_factor(&input);
+  match navigation.drag {
+    // Log-polar drags don't pan, so they leave no inertia.
+    Some(last) if uniforms.log_polar() => {
+      uniforms.drag_log_polar(&config, (position - last) * speed / DISPLAY_FACTOR as f32);
+    }
+    Some(last) => {
+      let to = last + (position - last) * speed;
+      let delta = uniforms.window_to_complex(&config, &window, last)
+        - uniforms.window_to_complex(&config, &window, to);
+      uniforms.center += delta;
+      let dt = time.delta_secs();
+      if dt > 0. {
+        navigation.velocity = delta / dt;
+      }
+    }
+    None => navigation.velocity = Vec2::ZERO,
+  }
+  navigation.drag = Some(position);
+}
+
+fn apply_inertia(
+  time: Res<Time>,
+  mut navigation: ResMut<Navigation>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  if navigation.drag.is_some() || navigation.velocity == Vec2::ZERO {
+    return;
+  }
+
+  // Integrates v(t) = v0 * exp(-friction * t) exactly over the frame.
+  let decay = (-navigation.friction * time.delta_secs()).exp();
+  uniforms.center += navigation.velocity * (1. - decay) / navigation.friction;
+  navigation.velocity *= decay;
+  if navigation.velocity.length() < uniforms.scale * 1e-3 {
+    navigation.velocity = Vec2::ZERO;
+  }
+}
+
+/// The complex point under the cursor, or the center without one or in
+/// log-polar views, which zoom about their center.
+fn cursor_anchor(window: &Window, config: &RenderConfig, uniforms: &Uniforms) -> Vec2 {
+  match window.cursor_position() {
+    Some(position) if !uniforms.log_polar() => uniforms.window_to_complex(config, window, position),
+    _ => uniforms.center,
+  }
+}
+
+fn scroll_navigation(
+  mut wheel: EventReader<MouseWheel>,
+  input: ActionInput,
+  window: Single<&Window, With<PrimaryWindow>>,
+  config: Res<RenderConfig>,
+  mut navigation: ResMut<Navigation>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  for event in wheel.read() {
+    let speed = navigation.speed_factor(&input);
+    let lines = match (event.unit, navigation.pixel_scroll) {
+      (MouseScrollUnit::Line, _) => event.y,
+      (MouseScrollUnit::Pixel, PixelScroll::Zoom) => event.y / PIXELS_PER_LINE,
+      (MouseScrollUnit::Pixel, PixelScroll::Pan) => {
+        // The scrolled content moves by the delta, like a drag from the
+        // middle of the window.
+        let from = window.size() / 2.;
+        let to = from + Vec2::new(event.x, event.y) * speed;
+        if uniforms.log_polar() {
+          uniforms.drag_log_polar(&config, (to - from) / DISPLAY_FACTOR as f32);
+          continue;
+        }
+        let delta = uniforms.window_to_complex(&config, &window, from)
+          - uniforms.window_to_complex(&config, &window, to);
+        uniforms.center += delta;
+        continue;
+      }
+    };
+    let factor = navigation.scroll_zoom_factor.powf(-lines * speed);
+    if navigation.scroll_damping > 0. {
+      let target = match navigation.zoom_target {
+        Some(target) => target,
+        None => ZoomTarget {
+          scale: uniforms.scale,
+          eased: uniforms.scale,
+        },
+      };
+      navigation.zoom_target = Some(ZoomTarget { scale: target.scale * factor, ..target });
+      continue;
+    }
+    // Keep the point under the cursor fixed.
+    let anchor = cursor_anchor(&window, &config, &uniforms);
+    uniforms.zoom_about(anchor, factor);
+  }
+}
+
+/// Moves the scale part of the way to the scroll-wheel zoom target, about
+/// the point under the cursor as it is now.
+fn ease_zoom(
+  time: Res<Time>,
+  window: Single<&Window, With<PrimaryWindow>>,
+  config: Res<RenderConfig>,
+  mut navigation: ResMut<Navigation>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let Some(mut target) = navigation.zoom_target else {
+    return;
+  };
+  // Another control or a loaded view moved the scale, which wins.
+  if target.eased != uniforms.scale {
+    navigation.zoom_target = None;
+    return;
+  }
+  // Easing toward a target past the zoom limit would pull the center toward
+  // the cursor while the scale stays clamped.
+  if navigation.zoom_limit {
+    target.scale = target.scale.max(uniforms.min_scale(&config));
+  }
+
+  let remaining = (target.scale / uniforms.scale).ln();
+  let done = remaining.abs() < 1e-3;
+  let step = if done {
+    remaining
+  } else {
+    remaining * (1. - (-navigation.scroll_damping * time.delta_secs()).exp())
+  };
+  let anchor = cursor_anchor(&window, &config, &uniforms);
+  uniforms.zoom_about(anchor, step.exp());
+  navigation.zoom_target = (!done).then_some(ZoomTarget { eased: uniforms.scale, ..target });
+}
+
+fn pinch_zoom(
+  mut pinches: EventReader<PinchGesture>,
+  input: ActionInput,
+  window: Single<&Window, With<PrimaryWindow>>,
+  config: Res<RenderConfig>,
+  navigation: Res<Navigation>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  for pinch in pinches.read() {
+    // Spreading the fingers reports positive magnification and zooms in.
+    let factor = (-pinch.0 * navigation.pinch_zoom_rate * navigation.speed_factor(&input)).exp();
+    let anchor = cursor_anchor(&window, &config, &uniforms);
+    uniforms.zoom_about(anchor, factor);
+  }
+}
+
+/// Zooms by a fixed factor per key press around the center of the view,
+/// leaving `center` exactly as it was.
+fn step_zoom(input: ActionInput, navigation: Res<Navigation>, mut uniforms: ResMut<Uniforms>) {
+  let step = navigation
+    .step_zoom_factor
+    .powf(navigation.speed_factor(&input));
+  if input.just_pressed(Action::StepZoomIn) {
+    uniforms.scale /= step;
+  }
+  if input.just_pressed(Action::StepZoomOut) {
+    uniforms.scale *= step;
+  }
+}
+
+/// Springs the center back toward the pan limit once it has passed it. The
+/// pull grows with the overshoot, and like inertia it is integrated exactly
+/// over the frame.
+fn pull_toward_set(time: Res<Time>, navigation: Res<Navigation>, mut uniforms: ResMut<Uniforms>) {
+  let Some(pan_limit) = navigation.pan_limit else {
+    return;
+  };
+  let limit = pan_limit * SET_RADIUS + uniforms.scale;
+  let distance = uniforms.center.length();
+  if distance <= limit {
+    return;
+  }
+
+  let overshoot = distance - limit;
+  let pull = 1. - (-navigation.pan_spring * time.delta_secs()).exp();
+  let direction = uniforms.center / distance;
+  uniforms.center -= direction * overshoot * pull;
+}
+
+/// Holds the scale at the zoom limit, logging the first time it does so.
+fn limit_zoom(
+  config: Res<RenderConfig>,
+  mut navigation: ResMut<Navigation>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let min_scale = uniforms.min_scale(&config);
+  let at_limit = navigation.zoom_limit && uniforms.scale <= min_scale;
+  if at_limit {
+    if !navigation.at_zoom_limit {
+      info!(
+        "Reached the zoom limit of f32 precision at {}x",
+        INITIAL_SCALE / min_scale
+      );
+    }
+    uniforms.scale = min_scale;
+  }
+  if navigation.at_zoom_limit != at_limit {
+    navigation.at_zoom_limit = at_limit;
+  }
+}
@@ -0,0 +1,153 @@
+//! Accumulating the orbits of chosen points into a density layer.
+//!
+//! With [`Action::ToggleOrbitDensity`] on, the `orbits` pass traces the
+//! orbits of points jittered within a texel of each seed every frame and
+//! counts how often they visit each pixel, and the recolor pass draws the
+//! counts over the fractal on a log scale. The counts build up over frames
+//! and start over when the view or the seeds change, so holding still shows
+//! how orbits from a neighborhood flow through the plane. Unlike a
+//! Buddhabrot, only the seeds' neighborhoods are traced.
+//!
+//! The seed is the point under the cursor, until right clicks pin seeds of
+//! their own. Turning the layer off forgets the pinned seeds.
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    query::With,
+    resource::Resource,
+    system::{Res, ResMut, Single},
+  },
+  input::{ButtonInput, mouse::MouseButton},
+  log::info,
+  math::Vec2,
+  render::extract_resource::{ExtractResource, ExtractResourcePlugin},
+  window::{PrimaryWindow, Window},
+};
+
+use crate::{
+  RenderConfig, Uniforms,
+  keybinds::{Action, ActionInput},
+};
+
+/// Most seeds pinned at once. Pinning another replaces the oldest.
+pub(crate) const MAX_ORBIT_SEEDS: usize = 16;
+/// Workgroups of the `orbits` pass per seed and frame. Each traces
+/// `ORBIT_WORKGROUP_SIZE` orbits in the shader.
+pub(crate) const ORBIT_WORKGROUPS_PER_SEED: u32 = 4;
+
+/// The points whose orbits are accumulated.
+#[derive(Resource, Clone, Default, ExtractResource)]
+pub(crate) struct OrbitSeeds {
+  /// Whether the density layer is drawn.
+  pub enabled: bool,
+  /// Seeds pinned by right clicks, oldest first.
+  pinned: Vec<Vec2>,
+  /// The point under the cursor, while it is over the window.
+  hovered: Option<Vec2>,
+}
+
+impl OrbitSeeds {
+  /// The seeds traced this frame: the pinned ones, or the hovered point
+  /// until one is pinned. None while the layer is off.
+  pub(crate) fn active(&self) -> &[Vec2] {
+    if !self.enabled {
+      &[]
+    } else if self.pinned.is_empty() {
+      self.hovered.as_slice()
+    } else {
+      &self.pinned
+    }
+  }
+
+  /// Describes the seeds for the status line, while the layer is on.
+  pub(crate) fn describe(&self) -> Option<String> {
+    self.enabled.then(|| match self.pinned.len() {
+      0 => "orbits of the cursor".to_owned(),
+      1 => "orbits of 1 point".to_owned(),
+      count => format!("orbits of {count} points"),
+    })
+  }
+}
+
+pub(crate) struct OrbitsPlugin;
+
+impl Plugin for OrbitsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<OrbitSeeds>()
+      .add_plugins(ExtractResourcePlugin::<OrbitSeeds>::default())
+      .add_systems(Update, (toggle_orbit_density, track_seeds).chain());
+  }
+}
+
+fn toggle_orbit_density(input: ActionInput, mut seeds: ResMut<OrbitSeeds>) {
+  if !input.just_pressed(Action::ToggleOrbitDensity) {
+    return;
+  }
+  seeds.enabled = !seeds.enabled;
+  if seeds.enabled {
+    info!("Orbit density on, right click to pin seeds");
+  } else {
+    seeds.pinned.clear();
+  }
+}
+
+fn track_seeds(
+  buttons: Res<ButtonInput<MouseButton>>,
+  window: Single<&Window, With<PrimaryWindow>>,
+  config: Res<RenderConfig>,
+  uniforms: Res<Uniforms>,
+  mut seeds: ResMut<OrbitSeeds>,
+) {
+  if !seeds.enabled {
+    return;
+  }
+
+  let hovered = window
+    .cursor_position()
+    .map(|position| uniforms.window_to_complex(&config, &window, position));
+  if seeds.hovered != hovered {
+    seeds.hovered = hovered;
+  }
+  if let Some(point) = hovered
+    && buttons.just_pressed(MouseButton::Right)
+  {
+    if seeds.pinned.len() == MAX_ORBIT_SEEDS {
+      seeds.pinned.remove(0);
+    }
+    seeds.pinned.push(point);
+    info!("Tracing the orbits of {} {:+}i", point.x, point.y);
+  }
+}
+
+/// How far the density has been accumulated, kept by the compute node.
+#[derive(Default)]
+pub(crate) struct OrbitProgress {
+  /// The uniforms and seeds the density holds the orbits of.
+  view: Option<(Uniforms, Vec<Vec2>)>,
+  /// Seeds traced this frame.
+  pub seeds: u32,
+  /// Whether the density starts over this frame.
+  pub clear: bool,
+}
+
+impl OrbitProgress {
+  /// Plans this frame's tracing of `seeds`, which reach the GPU this frame,
+  /// starting over if they or the view changed. `dispatched` are the
+  /// uniforms this frame's dispatch sees.
+  pub(crate) fn advance(&mut self, dispatched: Option<Uniforms>, seeds: &[Vec2]) {
+    let Some(uniforms) = dispatched else {
+      self.seeds = 0;
+      return;
+    };
+    self.clear = self
+      .view
+      .as_ref()
+      .is_none_or(|(view, traced)| !view.iterates_like(&uniforms) || traced != seeds);
+    if self.clear {
+      self.view = Some((uniforms, seeds.to_vec()));
+    }
+    self.seeds = seeds.len() as u32;
+  }
+}
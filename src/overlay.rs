@@ -0,0 +1,362 @@
+//! Guides drawn over the fractal with gizmos.
+//!
+//! The guides mark points and lines of the plane, which log-polar views
+//! bend out of shape, so they are only drawn in Cartesian views.
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+  app::{App, Plugin, Update},
+  color::{Alpha, Color},
+  ecs::{
+    component::Component,
+    entity::Entity,
+    query::With,
+    resource::Resource,
+    system::{Commands, Query, Res, ResMut},
+  },
+  gizmos::gizmos::Gizmos,
+  math::Vec2,
+  prelude::{Text2d, TextColor, TextFont},
+  transform::components::Transform,
+};
+
+use crate::{
+  DISPLAY_FACTOR, FractalMode, RenderConfig, Uniforms,
+  keybinds::{Action, ActionInput},
+  navigation::Navigation,
+};
+
+/// Half the length of each crosshair arm, in screen pixels.
+const CROSSHAIR_RADIUS: f32 = 12.;
+
+/// Offset of the center label below the crosshair's lower arm, in screen
+/// pixels.
+const CENTER_LABEL_OFFSET: f32 = 10.;
+
+/// Roughly how many grid lines span the height of the view.
+const GRID_LINES: f32 = 6.;
+/// Font size of overlay labels, in screen pixels.
+const LABEL_SIZE: f32 = 12.;
+/// Offset of each grid label from the point it labels, in screen pixels.
+const GRID_LABEL_OFFSET: Vec2 = Vec2::new(4., 8.);
+/// Distance of the zoom limit notice from the bottom of the view, in screen
+/// pixels.
+const ZOOM_LIMIT_MARGIN: f32 = 16.;
+
+/// Bulbs up to this period are marked.
+const MAX_BULB_PERIOD: u32 = 12;
+/// Bulbs smaller than this radius in texels aren't marked.
+const MIN_BULB_RADIUS: f32 = 3.;
+/// Segments of the polylines drawn for the cardioid and each bulb.
+const CARDIOID_SEGMENTS: u32 = 128;
+const BULB_SEGMENTS: u32 = 48;
+
+/// The coordinate of the view's center, shown below the crosshair.
+#[derive(Component, Default)]
+struct CenterLabel;
+
+/// A text label of a grid line, reused from frame to frame.
+#[derive(Component, Default)]
+struct GridLabel;
+
+/// A text label of the main cardioid or a bulb, reused from frame to frame.
+#[derive(Component, Default)]
+struct BulbLabel;
+
+/// The notice shown while zooming is held at the zoom limit.
+#[derive(Component, Default)]
+struct ZoomLimitLabel;
+
+/// Which overlays are shown.
+#[derive(Resource, Default)]
+pub struct Overlays {
+  /// A crosshair at the center of the view, labeled with its coordinate.
+  pub crosshair: bool,
+  /// Gridlines at round values of the complex plane, with the real and
+  /// imaginary axes highlighted.
+  pub grid: bool,
+  /// The main cardioid and the bulbs attached to it, labeled with their
+  /// internal angle. Only drawn for the Mandelbrot set.
+  pub bulbs: bool,
+  /// The deepest zoom f32 can resolve around the center, in the status
+  /// line. See the `precision` module.
+  pub precision: bool,
+}
+
+pub(crate) struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<Overlays>().add_systems(
+      Update,
+      (
+        toggle_overlays,
+        draw_crosshair,
+        draw_grid,
+        draw_bulbs,
+        draw_zoom_limit,
+      ),
+    );
+  }
+}
+
+fn toggle_overlays(input: ActionInput, mut overlays: ResMut<Overlays>) {
+  if input.just_pressed(Action::ToggleCrosshair) {
+    overlays.crosshair = !overlays.crosshair;
+  }
+  if input.just_pressed(Action::ToggleGrid) {
+    overlays.grid = !overlays.grid;
+  }
+  if input.just_pressed(Action::ToggleBulbs) {
+    overlays.bulbs = !overlays.bulbs;
+  }
+  if input.just_pressed(Action::TogglePrecision) {
+    overlays.precision = !overlays.precision;
+  }
+}
+
+fn draw_crosshair(
+  mut commands: Commands,
+  overlays: Res<Overlays>,
+  uniforms: Res<Uniforms>,
+  mut labels: Query<(Entity, &mut Text2d, &mut Transform), With<CenterLabel>>,
+  mut gizmos: Gizmos,
+) {
+  let color = Color::WHITE.with_alpha(0.6);
+  if !overlays.crosshair || uniforms.log_polar() {
+    sync_labels(&mut commands, &mut labels, Vec::new(), color);
+    return;
+  }
+
+  // The sprite is centered on the camera, so the view's center is the
+  // origin.
+  gizmos.line_2d(
+    Vec2::new(-CROSSHAIR_RADIUS, 0.),
+    Vec2::new(CROSSHAIR_RADIUS, 0.),
+    color,
+  );
+  gizmos.line_2d(
+    Vec2::new(0., -CROSSHAIR_RADIUS),
+    Vec2::new(0., CROSSHAIR_RADIUS),
+    color,
+  );
+
+  // Overlays are placed in the left eye's view, whose center is what the
+  // crosshair marks.
+  let center = uniforms.left_eye_center();
+  sync_labels(
+    &mut commands,
+    &mut labels,
+    vec![(
+      format!("{} {:+}i", center.x, center.y),
+      Vec2::new(0., -CROSSHAIR_RADIUS - CENTER_LABEL_OFFSET),
+    )],
+    color,
+  );
+}
+
+/// Tells the user why zooming in stopped, for as long as the view is held at
+/// the zoom limit.
+fn draw_zoom_limit(
+  mut commands: Commands,
+  config: Res<RenderConfig>,
+  navigation: Res<Navigation>,
+  mut labels: Query<(Entity, &mut Text2d, &mut Transform), With<ZoomLimitLabel>>,
+) {
+  let mut wanted = Vec::new();
+  if navigation.at_zoom_limit() {
+    // The sprite is centered on the camera, so the bottom of the view is half
+    // its height down.
+    let bottom = -((config.size.1 * DISPLAY_FACTOR) as f32) / 2.;
+    wanted.push((
+      "Zoom limit: f32 can't resolve any deeper".to_owned(),
+      Vec2::new(0., bottom + ZOOM_LIMIT_MARGIN),
+    ));
+  }
+  sync_labels(
+    &mut commands,
+    &mut labels,
+    wanted,
+    Color::srgb(1., 0.8, 0.3),
+  );
+}
+
+impl Uniforms {
+  /// Maps a complex point to world space, where the sprite is centered on
+  /// the origin. The inverse of [`Uniforms::window_to_complex`], with y
+  /// growing upward, so in stereo views it maps from the left eye's view.
+  fn complex_to_world(&self, config: &RenderConfig, point: Vec2) -> Vec2 {
+    let offset = Vec2::from_angle(-self.rotation).rotate(point - self.left_eye_center());
+    let texels = Vec2::new(offset.x / self.pixel_aspect, offset.y) / self.texel_size(config);
+    texels * DISPLAY_FACTOR as f32
+  }
+}
+
+/// Shows the `wanted` texts at their world positions, reusing last frame's
+/// label entities and spawning or despawning the difference.
+fn sync_labels<M: Component + Default>(
+  commands: &mut Commands,
+  labels: &mut Query<(Entity, &mut Text2d, &mut Transform), With<M>>,
+  wanted: Vec<(String, Vec2)>,
+  color: Color,
+) {
+  let mut wanted = wanted.into_iter();
+  for (entity, mut text, mut transform) in labels {
+    match wanted.next() {
+      Some((label, position)) => {
+        if text.0 != label {
+          text.0 = label;
+        }
+        transform.translation = position.extend(1.);
+      }
+      None => commands.entity(entity).despawn(),
+    }
+  }
+  for (label, position) in wanted {
+    commands.spawn((
+      M::default(),
+      Text2d::new(label),
+      TextFont::from_font_size(LABEL_SIZE),
+      TextColor(color),
+      Transform::from_translation(position.extend(1.)),
+    ));
+  }
+}
+
+/// The smallest of 1, 2 and 5 times a power of ten that is at least `target`.
+fn round_step(target: f32) -> f32 {
+  let magnitude = 10f32.powf(target.log10().floor());
+  [1., 2., 5.]
+    .into_iter()
+    .map(|mantissa| mantissa * magnitude)
+    .find(|&step| step >= target)
+    .unwrap_or(10. * magnitude)
+}
+
+fn draw_grid(
+  mut commands: Commands,
+  overlays: Res<Overlays>,
+  config: Res<RenderConfig>,
+  uniforms: Res<Uniforms>,
+  mut labels: Query<(Entity, &mut Text2d, &mut Transform), With<GridLabel>>,
+  mut gizmos: Gizmos,
+) {
+  let line_color = Color::WHITE.with_alpha(0.25);
+  let axis_color = Color::WHITE.with_alpha(0.7);
+  if !overlays.grid || uniforms.log_polar() {
+    sync_labels(&mut commands, &mut labels, Vec::new(), axis_color);
+    return;
+  }
+
+  let (min, max) = uniforms.view_bounds(&config);
+
+  let step = round_step(2. * uniforms.scale / GRID_LINES);
+  let decimals = (-step.log10().floor()).max(0.) as usize;
+  // Labels sit where their line crosses the other axis, or at the edge of
+  // the view nearest it when that axis is out of view.
+  let label_at = Vec2::ZERO.clamp(min, max);
+
+  let mut wanted = Vec::new();
+  for (axis, other) in [(0, 1), (1, 0)] {
+    let first = (min[axis] / step).ceil() as i64;
+    let last = (max[axis] / step).floor() as i64;
+    for k in first..=last {
+      let value = k as f32 * step;
+      let mut start = Vec2::ZERO;
+      start[axis] = value;
+      start[other] = min[other];
+      let mut end = start;
+      end[other] = max[other];
+      let color = if k == 0 { axis_color } else { line_color };
+      gizmos.line_2d(
+        uniforms.complex_to_world(&config, start),
+        uniforms.complex_to_world(&config, end),
+        color,
+      );
+
+      let mut anchor = label_at;
+      anchor[axis] = value;
+      let text = match axis {
+        0 => format!("{value:.decimals$}"),
+        _ => format!("{value:.decimals$}i"),
+      };
+      wanted.push((
+        text,
+        uniforms.complex_to_world(&config, anchor) + GRID_LABEL_OFFSET,
+      ));
+    }
+  }
+
+  sync_labels(&mut commands, &mut labels, wanted, axis_color);
+}
+
+/// The point of the main cardioid's boundary at internal angle `turns`.
+fn cardioid_point(turns: f32) -> Vec2 {
+  Vec2::from_angle(TAU * turns) / 2. - Vec2::from_angle(2. * TAU * turns) / 4.
+}
+
+/// The circle approximating the bulb attached to the main cardioid at
+/// internal angle `p / q`, whose interior has period `q`: it touches the
+/// cardioid where it attaches and has radius `sin(πp/q) / q²`. Exact for the
+/// period 2 bulb.
+fn bulb(p: u32, q: u32) -> (Vec2, f32) {
+  let turns = p as f32 / q as f32;
+  // The outward normal of the cardioid, perpendicular to its derivative.
+  let normal = (Vec2::from_angle(TAU * turns) - Vec2::from_angle(2. * TAU * turns)).normalize();
+  let radius = (PI * turns).sin() / (q * q) as f32;
+  (cardioid_point(turns) + normal * radius, radius)
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+  if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn draw_bulbs(
+  mut commands: Commands,
+  overlays: Res<Overlays>,
+  config: Res<RenderConfig>,
+  uniforms: Res<Uniforms>,
+  mut labels: Query<(Entity, &mut Text2d, &mut Transform), With<BulbLabel>>,
+  mut gizmos: Gizmos,
+) {
+  let color = Color::srgb(0.4, 1., 0.6).with_alpha(0.8);
+  if !overlays.bulbs || uniforms.mode != FractalMode::Mandelbrot as u32 || uniforms.log_polar() {
+    sync_labels(&mut commands, &mut labels, Vec::new(), color);
+    return;
+  }
+
+  let (min, max) = uniforms.view_bounds(&config);
+  let visible = |center: Vec2, radius: f32| center.clamp(min, max).distance(center) <= radius;
+  let to_world = |point: Vec2| uniforms.complex_to_world(&config, point);
+  let mut wanted = Vec::new();
+
+  gizmos.linestrip_2d(
+    (0..=CARDIOID_SEGMENTS).map(|i| to_world(cardioid_point(i as f32 / CARDIOID_SEGMENTS as f32))),
+    color,
+  );
+  // The middle of the cardioid's interior, where its label goes.
+  let cardioid_middle = Vec2::new(-0.1, 0.);
+  if visible(cardioid_middle, 0.) {
+    wanted.push(("period 1".to_owned(), to_world(cardioid_middle)));
+  }
+
+  let min_radius = MIN_BULB_RADIUS * uniforms.texel_size(&config);
+  for q in 2..=MAX_BULB_PERIOD {
+    for p in (1..q).filter(|&p| gcd(p, q) == 1) {
+      let (center, radius) = bulb(p, q);
+      if radius < min_radius || !visible(center, radius) {
+        continue;
+      }
+      gizmos.linestrip_2d(
+        (0..=BULB_SEGMENTS).map(|i| {
+          let angle = TAU * i as f32 / BULB_SEGMENTS as f32;
+          to_world(center + Vec2::from_angle(angle) * radius)
+        }),
+        color,
+      );
+      wanted.push((format!("{p}/{q}"), to_world(center)));
+    }
+  }
+  sync_labels(&mut commands, &mut labels, wanted, color);
+}
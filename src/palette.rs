@@ -0,0 +1,222 @@
+//! Palettes defined by color stops.
+//!
+//! A [`Palette`] is a list of colors at positions between 0 and 1, blended
+//! linearly in between. It is baked into a one-texel-high lookup texture
+//! that the recolor pass reads the mapped escape iteration from, so
+//! palettes can be defined, edited and saved without touching the shader.
+//!
+//! A [`Sky`] splits off the start of the palette's domain, the far exterior
+//! where points escape almost at once, and colors it separately, so a calm
+//! backdrop can frame a busy boundary. The palette then spans the rest.
+
+use bevy::{
+  app::{App, Plugin, PostUpdate},
+  asset::{Assets, RenderAssetUsages},
+  color::{ColorToComponents, LinearRgba, Mix},
+  ecs::{
+    resource::Resource,
+    system::{Res, ResMut},
+  },
+  image::Image,
+  render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bytemuck::cast_slice;
+use serde::{Deserialize, Serialize};
+
+use crate::MandelbrotImages;
+
+/// Texels in the palette lookup texture.
+pub const PALETTE_SIZE: u32 = 256;
+/// Format of the palette lookup texture.
+const PALETTE_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
+
+/// Upper bound of [`Sky::end`], which leaves the palette some of the range.
+pub(crate) const MAX_SKY_END: f32 = 0.95;
+
+/// A gradient for the points that escape soonest, in place of the start of
+/// the palette.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sky {
+  /// Where the palette takes over, as a fraction of the mapped escape
+  /// iteration range, above 0 and at most [`MAX_SKY_END`].
+  pub end: f32,
+  /// The color of the points that escape first.
+  pub color: LinearRgba,
+  /// The color at `end`, blended to from `color`.
+  pub edge_color: LinearRgba,
+}
+
+impl Default for Sky {
+  /// A solid night blue over the first tenth of the range.
+  fn default() -> Self {
+    Self::solid(0.1, LinearRgba::rgb(0.005, 0.01, 0.04))
+  }
+}
+
+impl Sky {
+  /// A sky of a single color.
+  pub fn solid(end: f32, color: LinearRgba) -> Self {
+    Self { end, color, edge_color: color }
+  }
+}
+
+/// The colors escape iterations are mapped to, as stops sorted by position.
+/// Positions are clamped to [0, 1]; before the first stop and after the last
+/// the palette holds their colors.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Stops")]
+pub struct Palette {
+  stops: Vec<(f32, LinearRgba)>,
+}
+
+/// The on-disk form of a palette, which may be unsorted.
+#[derive(Deserialize)]
+struct Stops {
+  stops: Vec<(f32, LinearRgba)>,
+}
+
+impl From<Stops> for Palette {
+  fn from(stops: Stops) -> Self {
+    Self::new(stops.stops)
+  }
+}
+
+impl Default for Palette {
+  fn default() -> Self {
+    Self::classic()
+  }
+}
+
+impl Palette {
+  /// A palette of the given stops, in any order.
+  pub fn new(stops: impl IntoIterator<Item = (f32, LinearRgba)>) -> Self {
+    let mut palette = Self { stops: Vec::new() };
+    for (position, color) in stops {
+      palette.add_stop(position, color);
+    }
+    palette
+  }
+
+  /// The original palette: red rising fastest and blue slowest.
+  pub fn classic() -> Self {
+    Self::new((0..=16).map(|i| {
+      let t = i as f32 / 16.;
+      (t, LinearRgba::rgb(t.sqrt(), t, t * t))
+    }))
+  }
+
+  /// Black through red and yellow to white.
+  pub fn fire() -> Self {
+    Self::new([
+      (0., LinearRgba::BLACK),
+      (0.35, LinearRgba::rgb(0.8, 0.05, 0.)),
+      (0.7, LinearRgba::rgb(1., 0.75, 0.)),
+      (1., LinearRgba::WHITE),
+    ])
+  }
+
+  /// Deep blue through cyan to white.
+  pub fn ocean() -> Self {
+    Self::new([
+      (0., LinearRgba::rgb(0., 0.01, 0.08)),
+      (0.5, LinearRgba::rgb(0., 0.45, 0.7)),
+      (0.8, LinearRgba::rgb(0.5, 0.9, 1.)),
+      (1., LinearRgba::WHITE),
+    ])
+  }
+
+  /// Black to white.
+  pub fn grayscale() -> Self {
+    Self::new([(0., LinearRgba::BLACK), (1., LinearRgba::WHITE)])
+  }
+
+  /// The stops, sorted by position.
+  pub fn stops(&self) -> &[(f32, LinearRgba)] {
+    &self.stops
+  }
+
+  /// Adds a stop and returns its index. A stop at the same position as
+  /// existing ones goes after them.
+  pub fn add_stop(&mut self, position: f32, color: LinearRgba) -> usize {
+    let position = position.clamp(0., 1.);
+    let index = self.stops.partition_point(|&(p, _)| p <= position);
+    self.stops.insert(index, (position, color));
+    index
+  }
+
+  /// Removes the stop at `index`, if there is one.
+  pub fn remove_stop(&mut self, index: usize) -> Option<(f32, LinearRgba)> {
+    (index < self.stops.len()).then(|| self.stops.remove(index))
+  }
+
+  /// Moves the stop at `index` to `position` and returns its new index, or
+  /// `None` if there is no such stop.
+  pub fn move_stop(&mut self, index: usize, position: f32) -> Option<usize> {
+    let (_, color) = self.remove_stop(index)?;
+    Some(self.add_stop(position, color))
+  }
+
+  /// The color at `t`, blending the stops around it.
+  pub fn color_at(&self, t: f32) -> LinearRgba {
+    let next = self.stops.partition_point(|&(p, _)| p <= t);
+    match (
+      next.checked_sub(1).map(|i| self.stops[i]),
+      self.stops.get(next),
+    ) {
+      (Some((p0, c0)), Some(&(p1, c1))) => c0.mix(&c1, (t - p0) / (p1 - p0)),
+      (Some((_, color)), None) | (None, Some(&(_, color))) => color,
+      (None, None) => LinearRgba::BLACK,
+    }
+  }
+
+  /// The palette sampled at the texels of the lookup texture.
+  fn texture_data(&self) -> Vec<u8> {
+    let texels: Vec<[f32; 4]> = (0..PALETTE_SIZE)
+      .map(|i| {
+        self
+          .color_at(i as f32 / (PALETTE_SIZE - 1) as f32)
+          .to_f32_array()
+      })
+      .collect();
+    cast_slice(&texels).to_vec()
+  }
+
+  /// The lookup texture read by the recolor pass.
+  pub(crate) fn image(&self) -> Image {
+    Image::new(
+      Extent3d {
+        width: PALETTE_SIZE,
+        height: 1,
+        depth_or_array_layers: 1,
+      },
+      TextureDimension::D2,
+      self.texture_data(),
+      PALETTE_FORMAT,
+      // Kept in the main world too, so edits can rewrite it in place.
+      RenderAssetUsages::default(),
+    )
+  }
+}
+
+pub(crate) struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Palette>()
+      .add_systems(PostUpdate, upload_palette);
+  }
+}
+
+fn upload_palette(
+  palette: Res<Palette>,
+  mandelbrot_images: Res<MandelbrotImages>,
+  mut images: ResMut<Assets<Image>>,
+) {
+  if !palette.is_changed() {
+    return;
+  }
+  if let Some(image) = images.get_mut(&mandelbrot_images.palette) {
+    image.data = Some(palette.texture_data());
+  }
+}
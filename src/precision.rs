@@ -0,0 +1,123 @@
+//! How deep the view can zoom before f32 coordinates run out.
+//!
+//! With [`Overlays::precision`] on, the deepest zoom around the current
+//! center is logged and kept in the status line. The theoretical limit
+//! follows from the 24-bit mantissa alone: it is where neighboring texels
+//! come within a few f32 steps of each other, the depth the navigation's
+//! zoom limit stops at. The observed limit maps the middle row and column of
+//! texels the way the shader's `pixel_to_complex` does, and bisects for the
+//! deepest zoom at which every pair of neighbors still lands on distinct
+//! points.
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    resource::Resource,
+    system::{Local, Res, ResMut},
+  },
+  log::info,
+  math::Vec2,
+};
+
+use crate::{INITIAL_SCALE, RenderConfig, Uniforms, overlay::Overlays};
+
+/// Bisection steps over the logarithm of the scale.
+const BISECTION_STEPS: u32 = 32;
+
+/// Deepest zooms around the current center, relative to the initial view.
+/// Only kept up to date while [`Overlays::precision`] is on.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PrecisionLimits {
+  /// Where neighboring texels come within a few f32 steps of each other at
+  /// the center, which is where the zoom limit stops.
+  pub theoretical: f32,
+  /// Where the coordinate mapping first sends neighboring texels to the same
+  /// point.
+  pub observed: f32,
+}
+
+pub(crate) struct PrecisionPlugin;
+
+impl Plugin for PrecisionPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<PrecisionLimits>()
+      .add_systems(Update, update_precision_limits);
+  }
+}
+
+/// Whether the middle row and column of texels map to pairwise distinct
+/// points at `scale`, computed as the shader's `pixel_to_complex` does.
+fn resolves(config: &RenderConfig, uniforms: &Uniforms, scale: f32) -> bool {
+  let (width, height) = config.size;
+  let size = Vec2::new(width as f32, height as f32);
+  let (sin, cos) = uniforms.rotation.sin_cos();
+  let point = |x: u32, y: u32| {
+    let texels = Vec2::new(x as f32, y as f32) + 0.5 - size / 2.;
+    let offset = Vec2::new(texels.x * uniforms.pixel_aspect, -texels.y) * (2. * scale / size.y);
+    uniforms.center
+      + Vec2::new(
+        cos * offset.x - sin * offset.y,
+        sin * offset.x + cos * offset.y,
+      )
+  };
+  (1..width).all(|x| point(x - 1, height / 2) != point(x, height / 2))
+    && (1..height).all(|y| point(width / 2, y - 1) != point(width / 2, y))
+}
+
+fn precision_limits(config: &RenderConfig, uniforms: &Uniforms) -> PrecisionLimits {
+  // The same bound the zoom limit and the precision warning use. A view
+  // centered on the origin has none, short of the smallest normal f32.
+  let theoretical = uniforms.min_scale(config).max(f32::MIN_POSITIVE);
+
+  // Bisect between a scale that resolves and one well past the theoretical
+  // limit, in log space since the limit may be anywhere.
+  let (mut coarse, mut fine) = (INITIAL_SCALE, theoretical / 16.);
+  if resolves(config, uniforms, fine) {
+    coarse = fine;
+  }
+  for _ in 0..BISECTION_STEPS {
+    let middle = (coarse.log2() + fine.log2()) / 2.;
+    let middle = middle.exp2();
+    if resolves(config, uniforms, middle) {
+      coarse = middle;
+    } else {
+      fine = middle;
+    }
+  }
+
+  PrecisionLimits {
+    theoretical: INITIAL_SCALE / theoretical,
+    observed: INITIAL_SCALE / coarse,
+  }
+}
+
+fn update_precision_limits(
+  overlays: Res<Overlays>,
+  config: Res<RenderConfig>,
+  uniforms: Res<Uniforms>,
+  mut computed_for: Local<Option<(Vec2, f32)>>,
+  mut limits: ResMut<PrecisionLimits>,
+) {
+  if !overlays.precision {
+    *computed_for = None;
+    return;
+  }
+  // The limits depend on the center and rotation, not the current zoom.
+  let inputs = (uniforms.center, uniforms.rotation);
+  if *computed_for == Some(inputs) {
+    return;
+  }
+
+  let reported = computed_for.is_some();
+  *computed_for = Some(inputs);
+  *limits = precision_limits(&config, &uniforms);
+  if !reported {
+    info!(
+      "Deepest zoom around the center: {:.3e}x in theory, {:.3e}x observed, now at {:.3e}x",
+      limits.theoretical,
+      limits.observed,
+      INITIAL_SCALE / uniforms.scale
+    );
+  }
+}
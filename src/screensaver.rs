@@ -0,0 +1,158 @@
+//! Drifting through the set while nobody is at the controls.
+//!
+//! With a [`Screensaver`] resource present, the view starts a slow zoom
+//! and rotation once no input has arrived for [`Screensaver::timeout`]
+//! seconds. An [`AutoCenter`] steers the zoom toward detail so it doesn't
+//! sink into flat exterior or the interior, and once the zoom runs out of
+//! f32 precision the view starts over from the initial one. Any input hands
+//! control back where the drift left off.
+
+use std::f32::consts::TAU;
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    event::EventReader,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Commands, Res, ResMut},
+  },
+  input::{
+    ButtonInput,
+    gestures::PinchGesture,
+    keyboard::{KeyCode, KeyboardInput},
+    mouse::{MouseButton, MouseMotion, MouseWheel},
+  },
+  log::info,
+  time::Time,
+};
+
+use crate::{RenderConfig, Uniforms, autocenter::AutoCenter};
+
+/// Drifts the view after a period without input while present.
+#[derive(Resource, Clone, Debug)]
+pub struct Screensaver {
+  /// Seconds without input before the drift starts.
+  pub timeout: f32,
+  /// Logarithm of the factor the view shrinks by per second.
+  pub zoom_rate: f32,
+  /// Rotation speed of the view, in radians per second.
+  pub rotation_speed: f32,
+  /// Strength of the steering toward detail, as [`AutoCenter::strength`].
+  pub steering: f32,
+}
+
+impl Default for Screensaver {
+  fn default() -> Self {
+    Self {
+      timeout: 60.,
+      zoom_rate: 0.15,
+      rotation_speed: 0.03,
+      steering: 0.05,
+    }
+  }
+}
+
+impl Screensaver {
+  /// A screensaver starting after `timeout` seconds without input.
+  pub fn with_timeout(mut self, timeout: f32) -> Self {
+    self.timeout = timeout;
+    self
+  }
+}
+
+/// Progress of the screensaver.
+#[derive(Resource, Default)]
+struct Drift {
+  /// Whether any input arrived this frame.
+  input: bool,
+  /// Seconds since the last input.
+  idle: f32,
+  active: bool,
+  /// Whether the screensaver inserted the [`AutoCenter`], and so removes it
+  /// again.
+  steering: bool,
+}
+
+pub(crate) struct ScreensaverPlugin;
+
+impl Plugin for ScreensaverPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Drift>()
+      .add_systems(Update, (track_input, drift).chain());
+  }
+}
+
+fn track_input(
+  keys: Res<ButtonInput<KeyCode>>,
+  buttons: Res<ButtonInput<MouseButton>>,
+  mut typed: EventReader<KeyboardInput>,
+  mut motion: EventReader<MouseMotion>,
+  mut wheel: EventReader<MouseWheel>,
+  mut pinch: EventReader<PinchGesture>,
+  mut state: ResMut<Drift>,
+) {
+  // Typed keys count even when the coordinate prompt swallowed them.
+  state.input = keys.get_pressed().next().is_some()
+    || buttons.get_pressed().next().is_some()
+    || typed.read().count() > 0
+    || motion.read().count() > 0
+    || wheel.read().count() > 0
+    || pinch.read().count() > 0;
+}
+
+fn drift(
+  mut commands: Commands,
+  screensaver: Option<Res<Screensaver>>,
+  auto_center: Option<Res<AutoCenter>>,
+  config: Res<RenderConfig>,
+  time: Res<Time>,
+  mut state: ResMut<Drift>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let Some(screensaver) = screensaver else {
+    if state.steering {
+      commands.remove_resource::<AutoCenter>();
+    }
+    *state = Drift::default();
+    return;
+  };
+
+  if state.input {
+    if state.active {
+      info!("Input received, stopping the screensaver");
+    }
+    if state.steering {
+      commands.remove_resource::<AutoCenter>();
+    }
+    *state = Drift::default();
+    return;
+  }
+
+  state.idle += time.delta_secs();
+  if state.idle < screensaver.timeout {
+    return;
+  }
+  if !state.active {
+    info!(
+      "No input for {}s, starting the screensaver",
+      screensaver.timeout
+    );
+    state.active = true;
+    // An AutoCenter the host inserted stays in charge of steering.
+    if auto_center.is_none() {
+      commands.insert_resource(AutoCenter { strength: screensaver.steering });
+      state.steering = true;
+    }
+  }
+
+  let dt = time.delta_secs();
+  uniforms.scale *= (-screensaver.zoom_rate * dt).exp();
+  uniforms.rotation = (uniforms.rotation + screensaver.rotation_speed * dt) % TAU;
+  if uniforms.scale <= uniforms.min_scale(&config) {
+    let initial = Uniforms::new(&config);
+    uniforms.center = initial.center;
+    uniforms.scale = initial.scale;
+  }
+}
@@ -0,0 +1,108 @@
+//! A status line shown in the primary window's title bar.
+
+use bevy::{
+  app::{App, Plugin, PostUpdate},
+  ecs::{
+    query::With,
+    system::{Res, Single, SystemParam},
+  },
+  window::{PrimaryWindow, Window},
+};
+
+use crate::{
+  AnimationClock, DEFAULT_MAX_ITERATIONS, INITIAL_SCALE, RenderConfig, Uniforms,
+  dimension::BoundaryDimension,
+  iterations::IterationSteps,
+  navigation::Navigation,
+  orbits::OrbitSeeds,
+  overlay::Overlays,
+  precision::PrecisionLimits,
+  tour::Tour,
+  workgroups::{WorkgroupShape, WorkgroupTuning},
+};
+
+const TITLE: &str = "Mandelbrot";
+
+pub(crate) struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(PostUpdate, update_title);
+  }
+}
+
+/// The state the status line reports on, besides the view itself.
+#[derive(SystemParam)]
+struct Readouts<'w> {
+  clock: Res<'w, AnimationClock>,
+  overlays: Res<'w, Overlays>,
+  steps: Res<'w, IterationSteps>,
+  navigation: Res<'w, Navigation>,
+  limits: Res<'w, PrecisionLimits>,
+  dimension: Res<'w, BoundaryDimension>,
+  tour: Res<'w, Tour>,
+  orbits: Res<'w, OrbitSeeds>,
+  workgroup: Res<'w, WorkgroupShape>,
+  tuning: Res<'w, WorkgroupTuning>,
+}
+
+fn update_title(
+  mut window: Single<&mut Window, With<PrimaryWindow>>,
+  config: Res<RenderConfig>,
+  uniforms: Res<Uniforms>,
+  readouts: Readouts,
+) {
+  let Readouts {
+    clock,
+    overlays,
+    steps,
+    navigation,
+    limits,
+    dimension,
+    tour,
+    orbits,
+    workgroup,
+    tuning,
+  } = readouts;
+  let mut title = TITLE.to_string();
+  if let Some(landmark) = tour.landmark() {
+    title += &format!(" | {landmark}");
+  }
+  if steps.revealing() || uniforms.max_iterations != DEFAULT_MAX_ITERATIONS {
+    title += &format!(" | {} iterations", uniforms.max_iterations);
+  }
+  if overlays.precision {
+    title += &format!(
+      " | zoom {:.3e}x of {:.3e}x observed, {:.3e}x in theory",
+      INITIAL_SCALE / uniforms.scale,
+      limits.observed,
+      limits.theoretical
+    );
+  }
+  if let Some(dimension) = dimension.describe(&uniforms) {
+    title += &format!(" | {dimension}");
+  }
+  if let Some(orbits) = orbits.describe() {
+    title += &format!(" | {orbits}");
+  }
+  if let Some(tuning) = tuning.describe(&workgroup) {
+    title += &format!(" | {tuning}");
+  }
+  if uniforms.log_polar() {
+    title += " [log-polar]";
+  }
+  if clock.paused {
+    title += " [paused]";
+  }
+  if navigation.at_zoom_limit() {
+    title += " [zoom limit: f32 precision]";
+  } else if uniforms.precision_limited(&config) {
+    title += " [precision limited]";
+  }
+
+  // Only touch the window when the title changes, so it isn't marked changed
+  // every frame.
+  if window.title != title {
+    window.title = title;
+  }
+}
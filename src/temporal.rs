@@ -0,0 +1,86 @@
+//! Temporal smoothing of panned frames.
+//!
+//! With [`RenderConfig::temporal_blend`] above zero, the recolor pass blends
+//! each pixel with the color its point had in the previous frame, found by
+//! shifting back by the pan since then. The history is clamped towards the
+//! current color, so newly uncovered detail doesn't ghost. Any change to
+//! the view other than a pan moves points between pixels in ways a shift
+//! can't follow, so those frames skip the blend. Log-polar views are the
+//! other way around: zooms and rotations about the center shift them, and a
+//! pan is what can't be followed.
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+  app::{App, Last, Plugin},
+  ecs::system::{Local, Res, ResMut},
+  math::Vec2,
+};
+
+use crate::{RenderConfig, Uniforms, focus::RenderActive};
+
+pub(crate) struct TemporalPlugin;
+
+impl Plugin for TemporalPlugin {
+  fn build(&self, app: &mut App) {
+    // Last, so the offset covers every change made to the view this frame.
+    app.add_systems(Last, update_history);
+  }
+}
+
+/// Where the points of `current` were in the `previous` frame, as an offset
+/// in texels growing right and down, or `None` if they moved by more than a
+/// shift.
+fn history_offset(config: &RenderConfig, previous: &Uniforms, current: &Uniforms) -> Option<Vec2> {
+  let same_points = previous.warp_amp == current.warp_amp
+    && previous.warp_freq == current.warp_freq
+    && previous.mode == current.mode
+    && previous.julia_c == current.julia_c
+    && previous.max_iterations == current.max_iterations
+    && previous.projection == current.projection;
+  if !same_points {
+    return None;
+  }
+
+  if current.log_polar() {
+    if previous.center != current.center {
+      return None;
+    }
+    let step = current.log_polar_step(config);
+    // The shorter way around, since rotations wrap.
+    let turn = (current.rotation - previous.rotation + PI).rem_euclid(TAU) - PI;
+    return Some(Vec2::new(
+      turn / (current.pixel_aspect * step),
+      (previous.scale / current.scale).ln() / step,
+    ));
+  }
+
+  if previous.scale != current.scale || previous.rotation != current.rotation {
+    return None;
+  }
+  let delta = Vec2::from_angle(-current.rotation).rotate(current.center - previous.center)
+    / current.texel_size(config);
+  Some(Vec2::new(delta.x / current.pixel_aspect, -delta.y))
+}
+
+fn update_history(
+  config: Res<RenderConfig>,
+  active: Res<RenderActive>,
+  mut previous: Local<Option<Uniforms>>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  // Inactive frames aren't rendered, so the history stays where it is.
+  if config.temporal_blend == 0. || !active.0 {
+    return;
+  }
+
+  let offset = previous.and_then(|previous| history_offset(&config, &previous, &uniforms));
+  let (offset, blend) = match offset {
+    Some(offset) => (offset, config.temporal_blend),
+    None => (Vec2::ZERO, 0.),
+  };
+  uniforms.history_offset = offset;
+  uniforms.history_blend = blend;
+  uniforms.history_parity ^= 1;
+  *previous = Some(*uniforms);
+}
@@ -0,0 +1,174 @@
+//! A tour of well-known locations.
+//!
+//! [`Action::NextLandmark`] flies the view to the next of the
+//! [`FAMOUS_VIEWS`], in order, and its name shows in the status line until
+//! the view moves away. The flight zooms logarithmically while keeping the
+//! destination at a steady pace toward the middle of the view, so it reads
+//! as a single smooth zoom rather than a pan followed by a zoom. Any other
+//! control that moves the view ends the flight where it is.
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Res, ResMut},
+  },
+  log::info,
+  math::Vec2,
+  time::Time,
+};
+
+use crate::{
+  INITIAL_SCALE, Uniforms,
+  keybinds::{Action, ActionInput},
+};
+
+/// Length of the flight to a landmark, in seconds.
+const FLIGHT_SECONDS: f32 = 3.;
+
+/// A named location worth visiting.
+#[derive(Clone, Copy, Debug)]
+pub struct BookmarkedView {
+  pub name: &'static str,
+  pub center: Vec2,
+  /// Zoom relative to the initial view.
+  pub zoom: f32,
+  /// Iterations needed to resolve the location's detail.
+  pub max_iterations: u32,
+}
+
+/// The landmarks of the tour, shallow enough for f32 to resolve.
+pub const FAMOUS_VIEWS: [BookmarkedView; 6] = [
+  BookmarkedView {
+    name: "Seahorse Valley",
+    center: Vec2::new(-0.7453, 0.1127),
+    zoom: 150.,
+    max_iterations: 1000,
+  },
+  BookmarkedView {
+    name: "Elephant Valley",
+    center: Vec2::new(0.2822, 0.0101),
+    zoom: 100.,
+    max_iterations: 1000,
+  },
+  BookmarkedView {
+    name: "Triple Spiral Valley",
+    center: Vec2::new(-0.0883, 0.6543),
+    zoom: 100.,
+    max_iterations: 1000,
+  },
+  BookmarkedView {
+    name: "Mini Mandelbrot at -1.75",
+    center: Vec2::new(-1.7549, 0.),
+    zoom: 50.,
+    max_iterations: 500,
+  },
+  BookmarkedView {
+    name: "Feigenbaum Point",
+    center: Vec2::new(-1.401155, 0.),
+    zoom: 1000.,
+    max_iterations: 2000,
+  },
+  BookmarkedView {
+    name: "Dendrite at i",
+    center: Vec2::new(0., 1.),
+    zoom: 25.,
+    max_iterations: 500,
+  },
+];
+
+/// A flight in progress toward a landmark.
+#[derive(Clone, Copy)]
+struct Flight {
+  from: (Vec2, f32),
+  to: (Vec2, f32),
+  elapsed: f32,
+}
+
+/// Progress through the tour.
+#[derive(Resource, Default)]
+pub(crate) struct Tour {
+  /// Index of the next landmark to visit.
+  next: usize,
+  /// The landmark flown to last, while the view is still on its way there
+  /// or at it.
+  current: Option<usize>,
+  flight: Option<Flight>,
+  /// The center and scale the tour last left the view at, to tell when
+  /// something else moved it.
+  placed: Option<(Vec2, f32)>,
+}
+
+impl Tour {
+  /// The name of the landmark in view, if any.
+  pub(crate) fn landmark(&self) -> Option<&'static str> {
+    self.current.map(|index| FAMOUS_VIEWS[index].name)
+  }
+}
+
+pub(crate) struct TourPlugin;
+
+impl Plugin for TourPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Tour>()
+      .add_systems(Update, (start_flight, fly).chain());
+  }
+}
+
+fn start_flight(input: ActionInput, mut tour: ResMut<Tour>, mut uniforms: ResMut<Uniforms>) {
+  if !input.just_pressed(Action::NextLandmark) {
+    return;
+  }
+
+  let index = tour.next;
+  let landmark = FAMOUS_VIEWS[index];
+  info!("Flying to {}", landmark.name);
+  uniforms.max_iterations = landmark.max_iterations;
+  *tour = Tour {
+    next: (index + 1) % FAMOUS_VIEWS.len(),
+    current: Some(index),
+    flight: Some(Flight {
+      from: (uniforms.center, uniforms.scale),
+      to: (landmark.center, INITIAL_SCALE / landmark.zoom),
+      elapsed: 0.,
+    }),
+    placed: Some((uniforms.center, uniforms.scale)),
+  };
+}
+
+fn fly(time: Res<Time>, mut tour: ResMut<Tour>, mut uniforms: ResMut<Uniforms>) {
+  let Some(placed) = tour.placed else {
+    return;
+  };
+  // Another control or a loaded view moved the view, which wins.
+  if placed != (uniforms.center, uniforms.scale) {
+    let next = tour.next;
+    *tour = Tour { next, ..Tour::default() };
+    return;
+  }
+  let Some(mut flight) = tour.flight else {
+    return;
+  };
+
+  flight.elapsed += time.delta_secs();
+  let t = (flight.elapsed / FLIGHT_SECONDS).min(1.);
+  // Ease in and out of the flight.
+  let t = t * t * (3. - 2. * t);
+  let ((from_center, from_scale), (to_center, to_scale)) = (flight.from, flight.to);
+  let scale = from_scale * (to_scale / from_scale).powf(t);
+  // Moving the center in step with the scale, as zooming about the
+  // destination would, keeps the destination heading steadily for the
+  // middle of the view however far the zoom goes.
+  let progress = if from_scale == to_scale {
+    t
+  } else {
+    (from_scale - scale) / (from_scale - to_scale)
+  };
+  uniforms.center = from_center.lerp(to_center, progress);
+  uniforms.scale = scale;
+
+  tour.placed = Some((uniforms.center, uniforms.scale));
+  tour.flight = (t < 1.).then_some(flight);
+}
@@ -0,0 +1,487 @@
+//! User-supplied transfer functions from escape iterations to the palette.
+//!
+//! A [`Transfer`] replaces the mapping curve with an arithmetic expression
+//! of the smooth escape iteration `n`, like `sqrt(n) * 3 + sin(n * 0.1)`.
+//! Its value is the palette coordinate, with the palette's ends at 0 and 1,
+//! and values past either end reflect back into the palette as channel
+//! phases do. The expression is compiled to instructions for a small stack
+//! machine the shader evaluates, so nothing is recompiled for it.
+//!
+//! Expressions are built from numbers, `n`, `max` (the iteration cap), the
+//! operators `+`, `-`, `*`, `/` and `^`, parentheses, and the functions
+//! `sqrt`, `log`, `exp`, `sin`, `cos`, `abs`, `floor` and `fract`.
+
+use std::fmt;
+
+use bevy::{
+  app::{App, Plugin},
+  ecs::{resource::Resource, schedule::IntoScheduleConfigs, system::Res},
+  render::{
+    Render, RenderApp, RenderSystems,
+    extract_resource::{ExtractResource, ExtractResourcePlugin},
+    renderer::RenderQueue,
+  },
+};
+use bytemuck::{Pod, Zeroable, cast_slice};
+
+use crate::MandelbrotPipeline;
+
+/// Most instructions an expression compiles to, as many as the shader's
+/// buffer holds.
+pub(crate) const MAX_TRANSFER_OPS: usize = 64;
+/// Most values an expression keeps on the stack at once. Must match
+/// `TRANSFER_STACK` in the shader.
+const MAX_TRANSFER_STACK: usize = 16;
+/// Most levels of negations, powers and parentheses an expression nests,
+/// which bounds the recursion of the parser.
+const MAX_TRANSFER_NESTING: usize = 64;
+
+/// Instructions of the stack machine. Must match the `TRANSFER_*` constants
+/// in the shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+enum Opcode {
+  /// Stops evaluation. The buffer is padded with these.
+  End = 0,
+  /// Pushes the instruction's value.
+  Constant = 1,
+  /// Pushes the escape iteration.
+  Iteration = 2,
+  /// Pushes the iteration cap.
+  MaxIterations = 3,
+  Add = 4,
+  Subtract = 5,
+  Multiply = 6,
+  Divide = 7,
+  Power = 8,
+  Negate = 9,
+  Sqrt = 10,
+  Log = 11,
+  Exp = 12,
+  Sin = 13,
+  Cos = 14,
+  Abs = 15,
+  Floor = 16,
+  Fract = 17,
+}
+
+impl Opcode {
+  /// The function called `name`.
+  fn function(name: &str) -> Option<Self> {
+    Some(match name {
+      "sqrt" => Self::Sqrt,
+      "log" => Self::Log,
+      "exp" => Self::Exp,
+      "sin" => Self::Sin,
+      "cos" => Self::Cos,
+      "abs" => Self::Abs,
+      "floor" => Self::Floor,
+      "fract" => Self::Fract,
+      _ => return None,
+    })
+  }
+
+  /// How the instruction changes the number of values on the stack.
+  fn stack_effect(self) -> isize {
+    match self {
+      Self::Constant | Self::Iteration | Self::MaxIterations => 1,
+      Self::Add | Self::Subtract | Self::Multiply | Self::Divide | Self::Power => -1,
+      _ => 0,
+    }
+  }
+}
+
+/// One instruction, laid out as `TransferOp` in the shader.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub(crate) struct TransferOp {
+  op: u32,
+  /// The value pushed by [`Opcode::Constant`], unused otherwise.
+  value: f32,
+}
+
+/// Maps escape iterations to the palette in place of the mapping curve
+/// while present. See the module docs for the expressions it accepts.
+#[derive(Resource, Clone, Debug, ExtractResource)]
+pub struct Transfer {
+  source: String,
+  ops: Vec<TransferOp>,
+}
+
+impl Transfer {
+  /// Compiles `source`, or says why it can't be.
+  pub fn parse(source: &str) -> Result<Self, TransferError> {
+    let mut compiler = Compiler {
+      tokens: tokenize(source)?,
+      next: 0,
+      ops: Vec::new(),
+      depth: 0,
+      max_depth: 0,
+      nesting: 0,
+    };
+    compiler.expression()?;
+    if compiler.next < compiler.tokens.len() {
+      return Err(compiler.unexpected("an operator or the end"));
+    }
+    if compiler.ops.len() > MAX_TRANSFER_OPS {
+      return Err(TransferError::TooLong);
+    }
+    if compiler.max_depth > MAX_TRANSFER_STACK {
+      return Err(TransferError::TooDeep);
+    }
+    Ok(Self {
+      source: source.to_owned(),
+      ops: compiler.ops,
+    })
+  }
+
+  /// The expression as written.
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+}
+
+/// Why an expression couldn't be compiled.
+#[derive(Debug)]
+pub enum TransferError {
+  /// A character that starts no token.
+  UnexpectedChar(char),
+  InvalidNumber(String),
+  /// A name that is neither a variable nor a function.
+  UnknownName(String),
+  /// A token, or the end, where something else was expected.
+  Unexpected {
+    found: String,
+    expected: &'static str,
+  },
+  /// More instructions than the shader holds.
+  TooLong,
+  /// More values on the stack at once than the shader holds.
+  TooDeep,
+  /// More nested levels than the parser follows.
+  TooNested,
+}
+
+impl fmt::Display for TransferError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::UnexpectedChar(c) => write!(f, "unexpected character `{c}`"),
+      Self::InvalidNumber(number) => write!(f, "`{number}` isn't a number"),
+      Self::UnknownName(name) => write!(f, "unknown name `{name}`"),
+      Self::Unexpected { found, expected } => write!(f, "expected {expected}, found {found}"),
+      Self::TooLong => write!(f, "longer than {MAX_TRANSFER_OPS} instructions"),
+      Self::TooDeep => write!(f, "nested deeper than {MAX_TRANSFER_STACK} values"),
+      Self::TooNested => write!(f, "nested more than {MAX_TRANSFER_NESTING} levels"),
+    }
+  }
+}
+
+impl std::error::Error for TransferError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+  Number(f32),
+  Name(String),
+  Symbol(char),
+}
+
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Number(value) => write!(f, "`{value}`"),
+      Self::Name(name) => write!(f, "`{name}`"),
+      Self::Symbol(symbol) => write!(f, "`{symbol}`"),
+    }
+  }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, TransferError> {
+  let mut tokens = Vec::new();
+  let mut rest = source.trim_start();
+  while let Some(c) = rest.chars().next() {
+    let length = if c.is_ascii_digit() || c == '.' {
+      let length = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+      let number = &rest[..length];
+      let value = number
+        .parse()
+        .map_err(|_| TransferError::InvalidNumber(number.to_owned()))?;
+      tokens.push(Token::Number(value));
+      length
+    } else if c.is_ascii_alphabetic() {
+      let length = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+      tokens.push(Token::Name(rest[..length].to_owned()));
+      length
+    } else if "+-*/^()".contains(c) {
+      tokens.push(Token::Symbol(c));
+      1
+    } else {
+      return Err(TransferError::UnexpectedChar(c));
+    };
+    rest = rest[length..].trim_start();
+  }
+  Ok(tokens)
+}
+
+/// A recursive descent parser emitting instructions in postfix order.
+struct Compiler {
+  tokens: Vec<Token>,
+  next: usize,
+  ops: Vec<TransferOp>,
+  /// Values on the stack after the instructions emitted so far.
+  depth: isize,
+  max_depth: usize,
+  /// Factors being parsed, one inside the other.
+  nesting: usize,
+}
+
+impl Compiler {
+  fn emit(&mut self, op: Opcode, value: f32) {
+    self.depth += op.stack_effect();
+    self.max_depth = self.max_depth.max(self.depth as usize);
+    self.ops.push(TransferOp { op: op as u32, value });
+  }
+
+  /// Consumes the next token if it is `symbol`.
+  fn eat(&mut self, symbol: char) -> bool {
+    let found = self.tokens.get(self.next) == Some(&Token::Symbol(symbol));
+    if found {
+      self.next += 1;
+    }
+    found
+  }
+
+  fn expect(&mut self, symbol: char, expected: &'static str) -> Result<(), TransferError> {
+    if self.eat(symbol) {
+      Ok(())
+    } else {
+      Err(self.unexpected(expected))
+    }
+  }
+
+  fn unexpected(&self, expected: &'static str) -> TransferError {
+    let found = match self.tokens.get(self.next) {
+      Some(token) => token.to_string(),
+      None => "the end".to_owned(),
+    };
+    TransferError::Unexpected { found, expected }
+  }
+
+  /// expression := term (("+" | "-") term)*
+  fn expression(&mut self) -> Result<(), TransferError> {
+    self.term()?;
+    loop {
+      let op = if self.eat('+') {
+        Opcode::Add
+      } else if self.eat('-') {
+        Opcode::Subtract
+      } else {
+        return Ok(());
+      };
+      self.term()?;
+      self.emit(op, 0.);
+    }
+  }
+
+  /// term := factor (("*" | "/") factor)*
+  fn term(&mut self) -> Result<(), TransferError> {
+    self.factor()?;
+    loop {
+      let op = if self.eat('*') {
+        Opcode::Multiply
+      } else if self.eat('/') {
+        Opcode::Divide
+      } else {
+        return Ok(());
+      };
+      self.factor()?;
+      self.emit(op, 0.);
+    }
+  }
+
+  /// factor := "-" factor | atom ("^" factor)?
+  ///
+  /// Powers bind tighter than negation and group to the right, so `-2^2`
+  /// is -4 and `2^3^2` is 512.
+  fn factor(&mut self) -> Result<(), TransferError> {
+    // Every level of nesting passes through here, so this keeps long runs
+    // of `-` or `(` from overflowing the stack.
+    self.nesting += 1;
+    if self.nesting > MAX_TRANSFER_NESTING {
+      return Err(TransferError::TooNested);
+    }
+    if self.eat('-') {
+      self.factor()?;
+      self.emit(Opcode::Negate, 0.);
+    } else {
+      self.atom()?;
+      if self.eat('^') {
+        self.factor()?;
+        self.emit(Opcode::Power, 0.);
+      }
+    }
+    self.nesting -= 1;
+    Ok(())
+  }
+
+  /// atom := number | "n" | "max" | function "(" expression ")"
+  ///       | "(" expression ")"
+  fn atom(&mut self) -> Result<(), TransferError> {
+    match self.tokens.get(self.next).cloned() {
+      Some(Token::Number(value)) => {
+        self.next += 1;
+        self.emit(Opcode::Constant, value);
+      }
+      Some(Token::Name(name)) => {
+        self.next += 1;
+        match name.as_str() {
+          "n" => self.emit(Opcode::Iteration, 0.),
+          "max" => self.emit(Opcode::MaxIterations, 0.),
+          _ => {
+            let op = Opcode::function(&name).ok_or(TransferError::UnknownName(name))?;
+            self.expect('(', "`(` after a function")?;
+            self.expression()?;
+            self.expect(')', "`)`")?;
+            self.emit(op, 0.);
+          }
+        }
+      }
+      Some(Token::Symbol('(')) => {
+        self.next += 1;
+        self.expression()?;
+        self.expect(')', "`)`")?;
+      }
+      _ => return Err(self.unexpected("a number, `n`, `max`, a function or `(`")),
+    }
+    Ok(())
+  }
+}
+
+pub(crate) struct TransferPlugin;
+
+impl Plugin for TransferPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_plugins(ExtractResourcePlugin::<Transfer>::default());
+    app
+      .sub_app_mut(RenderApp)
+      .add_systems(Render, write_transfer.in_set(RenderSystems::Prepare));
+  }
+}
+
+/// Uploads the instructions of a new transfer function, padded with
+/// [`Opcode::End`].
+fn write_transfer(
+  transfer: Option<Res<Transfer>>,
+  pipeline: Res<MandelbrotPipeline>,
+  render_queue: Res<RenderQueue>,
+) {
+  let Some(transfer) = transfer.filter(|transfer| transfer.is_changed()) else {
+    return;
+  };
+  let end = TransferOp { op: Opcode::End as u32, value: 0. };
+  let mut ops = [end; MAX_TRANSFER_OPS];
+  ops[..transfer.ops.len()].copy_from_slice(&transfer.ops);
+  render_queue.write_buffer(&pipeline.transfer_buffer, 0, cast_slice(&ops));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn opcodes(source: &str) -> Vec<u32> {
+    let transfer = Transfer::parse(source).unwrap();
+    transfer.ops.iter().map(|op| op.op).collect()
+  }
+
+  #[test]
+  fn compiles_doc_example() {
+    assert!(Transfer::parse("sqrt(n) * 3 + sin(n * 0.1)").is_ok());
+  }
+
+  #[test]
+  fn powers_bind_tighter_than_negation() {
+    use Opcode::*;
+    let expected = [Constant, Constant, Power, Negate].map(|op| op as u32);
+    assert_eq!(opcodes("-2^2"), expected);
+  }
+
+  #[test]
+  fn powers_group_right() {
+    use Opcode::*;
+    let expected = [Constant, Constant, Constant, Power, Power].map(|op| op as u32);
+    assert_eq!(opcodes("2^3^2"), expected);
+    let transfer = Transfer::parse("2^3^2").unwrap();
+    let values: Vec<f32> = transfer.ops[..3].iter().map(|op| op.value).collect();
+    assert_eq!(values, [2., 3., 2.]);
+  }
+
+  #[test]
+  fn limits_length() {
+    // `-1` and 31 additions of `1` compile to exactly the limit.
+    let fits = format!("-1{}", "+1".repeat(31));
+    assert_eq!(opcodes(&fits).len(), MAX_TRANSFER_OPS);
+    let over = format!("-1{}", "+1".repeat(32));
+    assert!(matches!(
+      Transfer::parse(&over),
+      Err(TransferError::TooLong)
+    ));
+  }
+
+  #[test]
+  fn limits_stack_depth() {
+    // Powers group right, so each base waits on the stack for its exponent.
+    let fits = format!("{}2", "2^".repeat(MAX_TRANSFER_STACK - 1));
+    assert!(Transfer::parse(&fits).is_ok());
+    let over = format!("{}2", "2^".repeat(MAX_TRANSFER_STACK));
+    assert!(matches!(
+      Transfer::parse(&over),
+      Err(TransferError::TooDeep)
+    ));
+  }
+
+  #[test]
+  fn limits_nesting() {
+    let fits = format!(
+      "{}n{}",
+      "(".repeat(MAX_TRANSFER_NESTING - 1),
+      ")".repeat(MAX_TRANSFER_NESTING - 1)
+    );
+    assert!(Transfer::parse(&fits).is_ok());
+    for source in [
+      format!("{}n{}", "(".repeat(100_000), ")".repeat(100_000)),
+      format!("{}n", "-".repeat(100_000)),
+    ] {
+      assert!(matches!(
+        Transfer::parse(&source),
+        Err(TransferError::TooNested)
+      ));
+    }
+  }
+
+  #[test]
+  fn rejects_unknown_names() {
+    let err = Transfer::parse("tan(n)").unwrap_err();
+    assert!(matches!(err, TransferError::UnknownName(name) if name == "tan"));
+  }
+
+  #[test]
+  fn rejects_missing_parenthesis() {
+    let err = Transfer::parse("sqrt(n * 2").unwrap_err();
+    assert!(matches!(
+      err,
+      TransferError::Unexpected { expected: "`)`", .. }
+    ));
+  }
+
+  #[test]
+  fn rejects_trailing_tokens() {
+    let err = Transfer::parse("n 2").unwrap_err();
+    assert!(matches!(
+      err,
+      TransferError::Unexpected { expected: "an operator or the end", .. }
+    ));
+  }
+}
@@ -0,0 +1,89 @@
+//! Crossfading between coloring styles.
+//!
+//! Switching the mapping curve, relief shading or boundary mode only changes
+//! how the recolor pass colors the iteration buffer, so rather than keeping a
+//! second texture around, the recolor pass colors each pixel under both the
+//! previous and the current style and blends them.
+
+use bevy::{
+  app::{App, Plugin, PostUpdate},
+  ecs::{
+    resource::Resource,
+    system::{Local, Res, ResMut},
+  },
+  time::Time,
+};
+
+use crate::Uniforms;
+
+/// Tuning for style crossfades.
+#[derive(Resource, Clone, Debug)]
+pub struct Crossfade {
+  /// Length of a crossfade, in seconds. Zero switches styles instantly.
+  pub duration: f32,
+}
+
+impl Default for Crossfade {
+  fn default() -> Self {
+    Self { duration: 0.3 }
+  }
+}
+
+/// The uniforms a crossfade blends between.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Style {
+  mapping_curve: u32,
+  shading: u32,
+  boundary: u32,
+}
+
+impl Style {
+  fn of(uniforms: &Uniforms) -> Self {
+    Self {
+      mapping_curve: uniforms.mapping_curve,
+      shading: uniforms.shading,
+      boundary: uniforms.boundary,
+    }
+  }
+}
+
+pub(crate) struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Crossfade>()
+      .add_systems(PostUpdate, advance_transition);
+  }
+}
+
+/// Starts a crossfade whenever the style changes, and advances it. A style
+/// change in the middle of a crossfade restarts it from the style on screen
+/// before the change.
+pub(crate) fn advance_transition(
+  time: Res<Time>,
+  crossfade: Res<Crossfade>,
+  mut shown: Local<Option<Style>>,
+  mut elapsed: Local<f32>,
+  mut uniforms: ResMut<Uniforms>,
+) {
+  let style = Style::of(&uniforms);
+  let previous = *shown.get_or_insert(style);
+  if previous != style {
+    uniforms.previous_mapping_curve = previous.mapping_curve;
+    uniforms.previous_shading = previous.shading;
+    uniforms.previous_boundary = previous.boundary;
+    *shown = Some(style);
+    *elapsed = 0.;
+  }
+
+  *elapsed += time.delta_secs();
+  let transition = if crossfade.duration > 0. {
+    (*elapsed / crossfade.duration).min(1.)
+  } else {
+    1.
+  };
+  if uniforms.transition != transition {
+    uniforms.transition = transition;
+  }
+}
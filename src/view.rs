@@ -0,0 +1,267 @@
+//! Saved views and their versioned on-disk format.
+//!
+//! Views are stored as JSON wrapped in an envelope carrying the format
+//! version:
+//!
+//! ```json
+//! { "version": 1, "view": { "center": [-0.5, 0.0], "scale": 1.25, ... } }
+//! ```
+//!
+//! Adding a field to [`View`] doesn't need a new version: missing fields take
+//! their defaults and unknown fields are ignored, so old and new builds can
+//! read each other's files. Changes that can't be expressed that way (renames,
+//! changed units) bump [`VERSION`] and append a step to [`MIGRATIONS`].
+
+use std::{fmt, fs};
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::system::{Res, ResMut},
+  log::{info, warn},
+  math::{Vec2, Vec3},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+  FractalMode, MappingCurve, RenderConfig, Uniforms,
+  keybinds::{Action, ActionInput},
+  palette::{MAX_SKY_END, Sky},
+};
+
+/// Version of the format written by [`View::to_json`].
+pub const VERSION: u32 = 1;
+
+/// `MIGRATIONS[i]` upgrades the JSON of a version `i + 1` file to version
+/// `i + 2` in place.
+const MIGRATIONS: [fn(&mut Value); VERSION as usize - 1] = [];
+
+/// Where [`Action::SaveView`] and [`Action::LoadView`] keep the view.
+const VIEW_PATH: &str = "view.json";
+
+/// The user-adjustable part of the uniforms, as saved to disk.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct View {
+  pub center: Vec2,
+  pub scale: f32,
+  pub rotation: f32,
+  pub mapping_curve: MappingCurve,
+  pub shading: bool,
+  pub light_angle: f32,
+  /// Offsets of the red, green and blue channels along the palette.
+  pub channel_phase: Vec3,
+  /// Colors of the far exterior, set apart from the palette.
+  pub sky: Option<Sky>,
+  /// Amplitude and frequency of the coordinate warp applied before
+  /// iterating.
+  pub warp_amp: f32,
+  pub warp_freq: f32,
+  /// The constant of the Julia set shown, or `None` for the Mandelbrot set.
+  pub julia_c: Option<Vec2>,
+  pub max_iterations: u32,
+}
+
+impl Default for View {
+  fn default() -> Self {
+    Self::from_uniforms(&Uniforms::new(&RenderConfig::default()))
+  }
+}
+
+#[derive(Serialize)]
+struct VersionedView<'a> {
+  version: u32,
+  view: &'a View,
+}
+
+/// Why a saved view couldn't be read.
+#[derive(Debug)]
+pub enum ViewFileError {
+  Json(serde_json::Error),
+  MissingVersion,
+  /// The file was written by a newer build with an unknown format.
+  UnsupportedVersion(u64),
+}
+
+impl fmt::Display for ViewFileError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Json(err) => write!(f, "{err}"),
+      Self::MissingVersion => write!(f, "missing format version"),
+      Self::UnsupportedVersion(version) => {
+        write!(
+          f,
+          "unsupported format version {version}, expected at most {VERSION}"
+        )
+      }
+    }
+  }
+}
+
+impl std::error::Error for ViewFileError {}
+
+impl From<serde_json::Error> for ViewFileError {
+  fn from(err: serde_json::Error) -> Self {
+    Self::Json(err)
+  }
+}
+
+impl View {
+  pub(crate) fn from_uniforms(uniforms: &Uniforms) -> Self {
+    Self {
+      center: uniforms.center,
+      scale: uniforms.scale,
+      rotation: uniforms.rotation,
+      mapping_curve: MappingCurve::ALL[uniforms.mapping_curve as usize],
+      shading: uniforms.shading != 0,
+      light_angle: uniforms.light_angle,
+      channel_phase: uniforms.channel_phase,
+      sky: uniforms.sky(),
+      warp_amp: uniforms.warp_amp,
+      warp_freq: uniforms.warp_freq,
+      julia_c: (uniforms.mode == FractalMode::Julia as u32).then_some(uniforms.julia_c),
+      max_iterations: uniforms.max_iterations,
+    }
+  }
+
+  pub(crate) fn apply_to(&self, uniforms: &mut Uniforms) {
+    uniforms.center = self.center;
+    uniforms.scale = self.scale;
+    uniforms.rotation = self.rotation;
+    uniforms.mapping_curve = self.mapping_curve as u32;
+    uniforms.shading = self.shading as u32;
+    uniforms.light_angle = self.light_angle;
+    uniforms.channel_phase = self.channel_phase;
+    *uniforms = uniforms.with_sky(self.sky.map(|sky| Sky {
+      end: sky.end.clamp(0., MAX_SKY_END),
+      ..sky
+    }));
+    uniforms.warp_amp = self.warp_amp;
+    uniforms.warp_freq = self.warp_freq;
+    uniforms.max_iterations = self.max_iterations.max(1);
+    match self.julia_c {
+      Some(julia_c) => {
+        uniforms.mode = FractalMode::Julia as u32;
+        uniforms.julia_c = julia_c;
+      }
+      None => uniforms.mode = FractalMode::Mandelbrot as u32,
+    }
+  }
+
+  /// Serializes the view in the current format version.
+  pub fn to_json(&self) -> String {
+    let file = VersionedView { version: VERSION, view: self };
+    serde_json::to_string_pretty(&file).expect("views always serialize")
+  }
+
+  /// Reads a view written by this or any earlier format version.
+  pub fn from_json(json: &str) -> Result<Self, ViewFileError> {
+    let mut file: Value = serde_json::from_str(json)?;
+    let version = file
+      .get("version")
+      .and_then(Value::as_u64)
+      .ok_or(ViewFileError::MissingVersion)?;
+    if !(1..=VERSION as u64).contains(&version) {
+      return Err(ViewFileError::UnsupportedVersion(version));
+    }
+
+    for migration in &MIGRATIONS[version as usize - 1..] {
+      migration(&mut file);
+    }
+    Ok(serde_json::from_value(file["view"].take())?)
+  }
+}
+
+pub(crate) struct ViewPlugin;
+
+impl Plugin for ViewPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(Update, (save_view, load_view));
+  }
+}
+
+fn save_view(input: ActionInput, uniforms: Res<Uniforms>) {
+  if !input.just_pressed(Action::SaveView) {
+    return;
+  }
+
+  match fs::write(VIEW_PATH, View::from_uniforms(&uniforms).to_json()) {
+    Ok(()) => info!("Saved view to {VIEW_PATH}"),
+    Err(err) => warn!("Couldn't save view to {VIEW_PATH}: {err}"),
+  }
+}
+
+fn load_view(input: ActionInput, mut uniforms: ResMut<Uniforms>) {
+  if !input.just_pressed(Action::LoadView) {
+    return;
+  }
+
+  let view = fs::read_to_string(VIEW_PATH)
+    .map_err(|err| err.to_string())
+    .and_then(|json| View::from_json(&json).map_err(|err| err.to_string()));
+  match view {
+    Ok(view) => {
+      view.apply_to(&mut uniforms);
+      info!("Loaded view from {VIEW_PATH}");
+    }
+    Err(err) => warn!("Couldn't load view from {VIEW_PATH}: {err}"),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn loads_v1_fixture() {
+    let view = View::from_json(include_str!("../tests/fixtures/view_v1.json")).unwrap();
+    assert_eq!(view.center, Vec2::new(-0.743643, 0.131825));
+    assert_eq!(view.scale, 0.0001);
+    assert_eq!(view.rotation, 0.5);
+    assert_eq!(view.mapping_curve, MappingCurve::Log);
+    assert!(view.shading);
+    assert_eq!(view.channel_phase, Vec3::new(0., 0.1, 0.2));
+    assert_eq!(view.julia_c, None);
+    assert_eq!(view.max_iterations, 5000);
+    // The fixture predates the sky.
+    assert_eq!(view.sky, View::default().sky);
+  }
+
+  #[test]
+  fn missing_fields_take_defaults() {
+    let view = View::from_json(r#"{ "version": 1, "view": { "scale": 0.5 } }"#).unwrap();
+    assert_eq!(view, View { scale: 0.5, ..View::default() });
+  }
+
+  #[test]
+  fn unknown_fields_are_ignored() {
+    let json = r#"{ "version": 1, "view": { "scale": 0.5, "bookmark": "seahorse" } }"#;
+    let view = View::from_json(json).unwrap();
+    assert_eq!(view, View { scale: 0.5, ..View::default() });
+  }
+
+  #[test]
+  fn rejects_newer_version() {
+    let err = View::from_json(r#"{ "version": 2, "view": {} }"#).unwrap_err();
+    assert!(matches!(err, ViewFileError::UnsupportedVersion(2)));
+  }
+
+  #[test]
+  fn rejects_missing_version() {
+    let err = View::from_json(r#"{ "view": {} }"#).unwrap_err();
+    assert!(matches!(err, ViewFileError::MissingVersion));
+  }
+
+  #[test]
+  fn round_trips() {
+    let view = View {
+      center: Vec2::new(0.25, -0.125),
+      scale: 0.001,
+      sky: Some(Sky::default()),
+      julia_c: Some(Vec2::new(-0.8, 0.156)),
+      max_iterations: 4000,
+      ..View::default()
+    };
+    assert_eq!(View::from_json(&view.to_json()).unwrap(), view);
+  }
+}
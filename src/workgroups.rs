@@ -0,0 +1,290 @@
+//! Trying out workgroup shapes at runtime.
+//!
+//! The best workgroup shape depends on the GPU, and
+//! [`RenderConfig::workgroup_size`](crate::RenderConfig::workgroup_size)
+//! only picks a square one at startup. [`Action::CycleWorkgroupShape`]
+//! switches the iterate and recolor passes to the next of [`SHAPES`] the
+//! device supports, which compiles a pipeline variant for it, and shows the
+//! shape and the smoothed frame time in the status line to compare them by.
+//! On devices with timestamp queries, the frame time is the time the GPU
+//! spends in the compute pass. Elsewhere it is measured on the CPU, which
+//! waits for the GPU each frame, so in views that keep the GPU busy it
+//! tracks the GPU time. The status line says which of the two it shows.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bevy::{
+  app::{App, Plugin, Update},
+  ecs::{
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::{Commands, Res, ResMut},
+    world::World,
+  },
+  log::{error, info, warn},
+  render::{
+    Render, RenderApp, RenderStartup, RenderSystems,
+    extract_resource::{ExtractResource, ExtractResourcePlugin},
+    render_resource::{Buffer, BufferDescriptor, BufferUsages},
+    renderer::{RenderDevice, RenderQueue},
+  },
+  time::Time,
+};
+use crossbeam_channel::{Receiver, Sender};
+use wgpu::{
+  CommandEncoder, ComputePassTimestampWrites, Features, QuerySet, QuerySetDescriptor, QueryType,
+};
+
+use crate::{
+  export::read_buffer,
+  focus::RenderActive,
+  keybinds::{Action, ActionInput},
+};
+
+/// Shapes [`Action::CycleWorkgroupShape`] cycles through, as width by
+/// height in invocations. WebGPU only guarantees 256 invocations per
+/// workgroup, so the larger ones are skipped on devices with fewer.
+pub const SHAPES: [(u32, u32); 8] = [
+  (8, 8),
+  (16, 16),
+  (16, 8),
+  (8, 16),
+  (32, 8),
+  (32, 4),
+  (32, 2),
+  (4, 4),
+];
+
+/// Weight of each new frame in the smoothed frame time.
+const FRAME_TIME_SMOOTHING: f32 = 0.05;
+
+/// The shape of the workgroups the iterate and recolor passes are dispatched
+/// in, in invocations.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WorkgroupShape {
+  pub width: u32,
+  pub height: u32,
+}
+
+impl WorkgroupShape {
+  pub(crate) fn square(side: u32) -> Self {
+    Self { width: side, height: side }
+  }
+
+  /// Whether `device` can dispatch workgroups of this shape.
+  fn supported(self, device: &RenderDevice) -> bool {
+    let limits = device.limits();
+    self.width <= limits.max_compute_workgroup_size_x
+      && self.height <= limits.max_compute_workgroup_size_y
+      && self.width * self.height <= limits.max_compute_invocations_per_workgroup
+  }
+}
+
+impl Default for WorkgroupShape {
+  /// The default [`RenderConfig::workgroup_size`](crate::RenderConfig).
+  fn default() -> Self {
+    Self::square(8)
+  }
+}
+
+/// The frame time readout, shown once a shape has been picked.
+#[derive(Resource, ExtractResource, Clone, Default)]
+pub(crate) struct WorkgroupTuning {
+  shown: bool,
+  /// Exponentially smoothed frame time, in seconds.
+  frame_time: f32,
+  /// Whether the frame time comes from GPU timestamps.
+  gpu: bool,
+}
+
+impl WorkgroupTuning {
+  fn smooth(&mut self, dt: f32) {
+    self.frame_time = if self.frame_time == 0. {
+      dt
+    } else {
+      self.frame_time + (dt - self.frame_time) * FRAME_TIME_SMOOTHING
+    };
+  }
+}
+
+impl WorkgroupTuning {
+  /// Describes the shape and its frame time for the status line, once
+  /// tuning has started.
+  pub(crate) fn describe(&self, shape: &WorkgroupShape) -> Option<String> {
+    self.shown.then(|| {
+      format!(
+        "workgroups {}x{}, {:.1} ms/frame ({})",
+        shape.width,
+        shape.height,
+        self.frame_time * 1000.,
+        if self.gpu { "GPU" } else { "CPU" }
+      )
+    })
+  }
+}
+
+pub(crate) struct WorkgroupsPlugin;
+
+impl Plugin for WorkgroupsPlugin {
+  fn build(&self, app: &mut App) {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    app
+      .init_resource::<WorkgroupTuning>()
+      .insert_resource(GpuTimeReceiver(receiver))
+      .add_plugins(ExtractResourcePlugin::<WorkgroupTuning>::default())
+      .add_systems(Update, (cycle_workgroup_shape, measure_frame_time));
+    app
+      .sub_app_mut(RenderApp)
+      .insert_resource(GpuTimeSender(sender))
+      .add_systems(RenderStartup, init_gpu_timer)
+      .add_systems(Render, read_gpu_time.after(RenderSystems::Render));
+  }
+}
+
+/// Timestamps written at the start and end of the compute pass, on devices
+/// that support them.
+#[derive(Resource)]
+pub(crate) struct GpuTimer {
+  queries: QuerySet,
+  /// The resolved timestamps, as two `u64` ticks.
+  resolved: Buffer,
+  /// Nanoseconds per tick.
+  period: f32,
+  /// Whether the last frame resolved new timestamps to read.
+  written: AtomicBool,
+}
+
+impl GpuTimer {
+  /// The timer of the render world, while the frame time is shown and the
+  /// device supports timestamps.
+  pub(crate) fn active(world: &World) -> Option<&Self> {
+    let shown = world
+      .get_resource::<WorkgroupTuning>()
+      .is_some_and(|tuning| tuning.shown);
+    world.get_resource::<Self>().filter(|_| shown)
+  }
+
+  /// The timestamp writes around the compute pass.
+  pub(crate) fn pass_writes(&self) -> ComputePassTimestampWrites<'_> {
+    ComputePassTimestampWrites {
+      query_set: &self.queries,
+      beginning_of_pass_write_index: Some(0),
+      end_of_pass_write_index: Some(1),
+    }
+  }
+
+  /// Resolves the timestamps of the finished compute pass for
+  /// [`read_gpu_time`] to read.
+  pub(crate) fn resolve(&self, encoder: &mut CommandEncoder) {
+    encoder.resolve_query_set(&self.queries, 0..2, &self.resolved, 0);
+    self.written.store(true, Ordering::Relaxed);
+  }
+}
+
+#[derive(Resource)]
+struct GpuTimeSender(Sender<f32>);
+
+#[derive(Resource)]
+struct GpuTimeReceiver(Receiver<f32>);
+
+fn init_gpu_timer(mut commands: Commands, device: Res<RenderDevice>, queue: Res<RenderQueue>) {
+  if !device.features().contains(Features::TIMESTAMP_QUERY) {
+    info!("The device has no timestamp queries, so frame times are measured on the CPU");
+    return;
+  }
+  let queries = device.wgpu_device().create_query_set(&QuerySetDescriptor {
+    label: Some("Compute pass timestamps"),
+    ty: QueryType::Timestamp,
+    count: 2,
+  });
+  let resolved = device.create_buffer(&BufferDescriptor {
+    label: Some("Resolved timestamps"),
+    size: 2 * size_of::<u64>() as u64,
+    usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+    mapped_at_creation: false,
+  });
+  commands.insert_resource(GpuTimer {
+    queries,
+    resolved,
+    period: queue.get_timestamp_period(),
+    written: AtomicBool::new(false),
+  });
+}
+
+/// Sends the GPU time of the compute pass just rendered to the main world.
+fn read_gpu_time(
+  timer: Option<Res<GpuTimer>>,
+  active: Res<RenderActive>,
+  device: Res<RenderDevice>,
+  queue: Res<RenderQueue>,
+  sender: Res<GpuTimeSender>,
+) {
+  let Some(timer) = timer else {
+    return;
+  };
+  if !active.0 || !timer.written.swap(false, Ordering::Relaxed) {
+    return;
+  }
+  let bytes = match read_buffer(&device, &queue, &timer.resolved) {
+    Ok(bytes) => bytes,
+    Err(err) => {
+      error!("Reading back the compute pass timestamps: {err}");
+      return;
+    }
+  };
+  let ticks: [u64; 2] = bytemuck::pod_read_unaligned(&bytes);
+  let nanoseconds = ticks[1].saturating_sub(ticks[0]) as f32 * timer.period;
+  // The receiver lives as long as the app.
+  let _ = sender.0.send(nanoseconds * 1e-9);
+}
+
+fn cycle_workgroup_shape(
+  input: ActionInput,
+  device: Res<RenderDevice>,
+  mut tuning: ResMut<WorkgroupTuning>,
+  mut shape: ResMut<WorkgroupShape>,
+) {
+  if !input.just_pressed(Action::CycleWorkgroupShape) {
+    return;
+  }
+
+  // Continue after the current shape, or from the start for one the list
+  // doesn't have.
+  let current = SHAPES
+    .iter()
+    .position(|&(width, height)| WorkgroupShape { width, height } == *shape);
+  let start = current.map_or(0, |index| index + 1);
+  let next = (0..SHAPES.len())
+    .map(|offset| SHAPES[(start + offset) % SHAPES.len()])
+    .map(|(width, height)| WorkgroupShape { width, height })
+    .find(|candidate| candidate.supported(&device));
+  let Some(next) = next else {
+    warn!("The device supports none of the workgroup shapes to try");
+    return;
+  };
+
+  info!("Dispatching {}x{} workgroups", next.width, next.height);
+  tuning.shown = true;
+  *shape = next;
+}
+
+fn measure_frame_time(
+  time: Res<Time>,
+  receiver: Res<GpuTimeReceiver>,
+  mut tuning: ResMut<WorkgroupTuning>,
+) {
+  let gpu_times: Vec<f32> = receiver.0.try_iter().collect();
+  if !tuning.shown {
+    return;
+  }
+  // Once the GPU reports its times, they replace the CPU's.
+  if !tuning.gpu && !gpu_times.is_empty() {
+    tuning.gpu = true;
+    tuning.frame_time = 0.;
+  }
+  if tuning.gpu {
+    gpu_times.into_iter().for_each(|dt| tuning.smooth(dt));
+  } else {
+    tuning.smooth(time.delta_secs());
+  }
+}